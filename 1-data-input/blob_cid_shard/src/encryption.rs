@@ -0,0 +1,63 @@
+use age::armor::{ArmoredReader, ArmoredWriter, Format};
+use age::secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// Environment variable holding the passphrase for encrypted metadata
+pub const PASSPHRASE_ENV: &str = "SHARD_METADATA_PASSPHRASE";
+
+/// Current version of the encrypted metadata stub format
+pub const STUB_VERSION: u32 = 1;
+
+/// Public part of an encrypted metadata file. Only the CID and format version
+/// are readable without the passphrase; everything else (filenames, sizes,
+/// chunk hashes) lives in the age-encrypted payload.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncryptedMetadataStub {
+    pub version: u32,
+    pub cid: String,
+    /// ASCII-armored age ciphertext of the full ShardMetadata JSON
+    pub encrypted_metadata: String,
+}
+
+fn invalid_data<E: std::fmt::Display>(e: E) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+}
+
+/// Reads the metadata passphrase from the environment.
+pub fn passphrase_from_env() -> std::io::Result<SecretString> {
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) if !passphrase.is_empty() => Ok(SecretString::from(passphrase)),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{} environment variable must be set to a non-empty passphrase", PASSPHRASE_ENV),
+        )),
+    }
+}
+
+/// Encrypts the given plaintext with a passphrase, returning ASCII-armored age output.
+pub fn encrypt(plaintext: &[u8], passphrase: SecretString) -> std::io::Result<String> {
+    let encryptor = age::Encryptor::with_user_passphrase(passphrase);
+
+    let mut armored = Vec::new();
+    let armor = ArmoredWriter::wrap_output(&mut armored, Format::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armor)?;
+    writer.write_all(plaintext)?;
+    writer.finish()?.finish()?;
+
+    String::from_utf8(armored).map_err(invalid_data)
+}
+
+/// Decrypts ASCII-armored age output produced by `encrypt`.
+pub fn decrypt(armored: &str, passphrase: SecretString) -> std::io::Result<Vec<u8>> {
+    let decryptor = age::Decryptor::new(ArmoredReader::new(armored.as_bytes())).map_err(invalid_data)?;
+    let identity = age::scrypt::Identity::new(passphrase);
+
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .map_err(invalid_data)?;
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext)?;
+
+    Ok(plaintext)
+}
@@ -0,0 +1,7 @@
+//! Splits large files into hashed chunks under a single content identifier,
+//! and reassembles them from the generated metadata.
+
+pub mod encryption;
+mod shard;
+
+pub use shard::{BYTES_PER_MB, ChunkInfo, ChunkSize, FileSharder, ShardMetadata};
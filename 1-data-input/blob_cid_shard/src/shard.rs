@@ -0,0 +1,380 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use cid::Cid;
+use multihash::Multihash;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::encryption::{self, EncryptedMetadataStub};
+
+pub const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Upper bound on the number of chunks auto mode aims for
+const AUTO_MAX_CHUNK_COUNT: u64 = 4096;
+
+/// Smallest chunk size auto mode will pick
+const AUTO_MIN_CHUNK_SIZE_MB: u64 = 1;
+
+/// Largest chunk size auto mode will pick; keeps every chunk well under
+/// Pinata's per-file upload limit so chunks can be pinned individually
+const AUTO_MAX_CHUNK_SIZE_MB: u64 = 1024;
+
+/// Requested chunk size: a fixed number of MB, or chosen from the input size
+#[derive(Clone, Copy, Debug)]
+pub enum ChunkSize {
+    Auto,
+    Megabytes(u64),
+}
+
+impl ChunkSize {
+    /// Resolves the chunk size in bytes for a file of the given size.
+    ///
+    /// Auto mode picks the smallest power-of-two MB size that keeps the file
+    /// within AUTO_MAX_CHUNK_COUNT chunks, clamped to the supported range.
+    pub fn resolve_bytes(&self, file_size: u64) -> u64 {
+        match self {
+            ChunkSize::Megabytes(mb) => mb * BYTES_PER_MB,
+            ChunkSize::Auto => {
+                let min_bytes = file_size.div_ceil(AUTO_MAX_CHUNK_COUNT);
+                let mb = min_bytes
+                    .div_ceil(BYTES_PER_MB)
+                    .next_power_of_two()
+                    .clamp(AUTO_MIN_CHUNK_SIZE_MB, AUTO_MAX_CHUNK_SIZE_MB);
+                mb * BYTES_PER_MB
+            }
+        }
+    }
+}
+
+impl FromStr for ChunkSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(ChunkSize::Auto);
+        }
+        match s.parse::<u64>() {
+            Ok(0) => Err("chunk size must be greater than 0".to_string()),
+            Ok(mb) => Ok(ChunkSize::Megabytes(mb)),
+            Err(_) => Err(format!("invalid chunk size '{}': expected a number of MB or \"auto\"", s)),
+        }
+    }
+}
+
+impl fmt::Display for ChunkSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkSize::Auto => write!(f, "auto"),
+            ChunkSize::Megabytes(mb) => write!(f, "{} MB", mb),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChunkInfo {
+    pub filename: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Whether the chunk was re-read from disk and matched its hash after writing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verified: bool,
+    /// IPFS CID the chunk was pinned under, filled in by ipfs-pinata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShardMetadata {
+    pub original_file: String,
+    pub total_size: u64,
+    pub chunk_count: u32,
+    /// Chunk size in bytes used when sharding (0 for metadata predating this field)
+    #[serde(default)]
+    pub chunk_size: u64,
+    pub chunks: Vec<ChunkInfo>,
+    pub cid: String,
+}
+
+/// Splits files into fixed-size chunks in an output directory and
+/// reassembles them from the generated metadata.
+pub struct FileSharder {
+    chunk_size: ChunkSize,
+    output_dir: PathBuf,
+    verify_writes: bool,
+    /// Suppresses progress messages on stdout
+    quiet: bool,
+}
+
+impl FileSharder {
+    pub fn new(chunk_size: ChunkSize, output_dir: impl AsRef<Path>, verify_writes: bool) -> std::io::Result<Self> {
+        let output_path = output_dir.as_ref().to_path_buf();
+        
+        // Create output directory if it doesn't exist
+        std::fs::create_dir_all(&output_path)?;
+        
+        Ok(FileSharder {
+            chunk_size,
+            output_dir: output_path,
+            verify_writes,
+            quiet: false,
+        })
+    }
+
+    /// Stops the sharder printing progress, for callers that own stdout.
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    fn log(&self, message: fmt::Arguments) {
+        if !self.quiet {
+            println!("{}", message);
+        }
+    }
+    
+    pub fn shard_file(&self, input_path: impl AsRef<Path>) -> std::io::Result<ShardMetadata> {
+        let input_path = input_path.as_ref();
+        let input_file = File::open(input_path)?;
+        let file_size = input_file.metadata()?.len();
+        let mut reader = BufReader::new(input_file);
+        
+        let original_filename = input_path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        
+        let chunk_size_bytes = self.chunk_size.resolve_bytes(file_size);
+        let chunk_count = file_size.div_ceil(chunk_size_bytes);
+        let mut chunks = Vec::new();
+        let mut buffer = vec![0u8; chunk_size_bytes as usize];
+        
+        self.log(format_args!("Sharding file: {} ({} bytes)", input_path.display(), file_size));
+        if let ChunkSize::Auto = self.chunk_size {
+            self.log(format_args!("Auto-selected chunk size: {} MB", chunk_size_bytes / BYTES_PER_MB));
+        }
+        self.log(format_args!("Creating {} chunks of max {} MB each", chunk_count, chunk_size_bytes / BYTES_PER_MB));
+        
+        for chunk_index in 0..chunk_count {
+            let chunk_filename = format!("chunk_{:03}.part", chunk_index);
+            let chunk_path = self.output_dir.join(&chunk_filename);
+            
+            // Read chunk data
+            let bytes_read = reader.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            
+            let chunk_data = &buffer[..bytes_read];
+            
+            // Calculate SHA256 for this chunk
+            let mut hasher = Sha256::new();
+            hasher.update(chunk_data);
+            let chunk_hash = hasher.finalize();
+            let chunk_sha256 = hex::encode(chunk_hash);
+            
+            // Write chunk to file
+            let mut chunk_file = File::create(&chunk_path)?;
+            chunk_file.write_all(chunk_data)?;
+            
+            // Optionally read the chunk back to catch silent write corruption
+            if self.verify_writes {
+                chunk_file.sync_all()?;
+                drop(chunk_file);
+                self.verify_chunk(&chunk_path, chunk_index, bytes_read as u64, &chunk_sha256)?;
+            }
+            
+            // Store chunk info
+            chunks.push(ChunkInfo {
+                filename: chunk_filename,
+                size: bytes_read as u64,
+                sha256: chunk_sha256,
+                verified: self.verify_writes,
+                ipfs_hash: None,
+            });
+            
+            if self.verify_writes {
+                self.log(format_args!("Created chunk {}: {} bytes (verified)", chunk_index, bytes_read));
+            } else {
+                self.log(format_args!("Created chunk {}: {} bytes", chunk_index, bytes_read));
+            }
+        }
+        
+        // Generate global CID for the entire file
+        let global_cid = self.generate_global_cid(&chunks, &original_filename, file_size)?;
+        
+        let metadata = ShardMetadata {
+            original_file: original_filename,
+            total_size: file_size,
+            chunk_count: chunks.len() as u32,
+            chunk_size: chunk_size_bytes,
+            chunks,
+            cid: global_cid,
+        };
+        
+        Ok(metadata)
+    }
+    
+    fn verify_chunk(&self, chunk_path: &Path, chunk_index: u64, expected_size: u64, expected_sha256: &str) -> std::io::Result<()> {
+        let mut chunk_file = File::open(chunk_path)?;
+        let mut chunk_data = Vec::new();
+        chunk_file.read_to_end(&mut chunk_data)?;
+        
+        let mut hasher = Sha256::new();
+        hasher.update(&chunk_data);
+        let computed_hash = hex::encode(hasher.finalize());
+        
+        if chunk_data.len() as u64 != expected_size || computed_hash != expected_sha256 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Chunk {} read-after-write verification failed", chunk_index)
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    fn generate_global_cid(&self, chunks: &[ChunkInfo], original_filename: &str, total_size: u64) -> std::io::Result<String> {
+        // Create a composite hash from all chunk hashes, filename, and size
+        let mut global_hasher = Sha256::new();
+        
+        // Include original filename
+        global_hasher.update(original_filename.as_bytes());
+        
+        // Include total size
+        global_hasher.update(total_size.to_be_bytes());
+        
+        // Include all chunk hashes in order
+        for chunk in chunks {
+            global_hasher.update(chunk.filename.as_bytes());
+            global_hasher.update(chunk.size.to_be_bytes());
+            global_hasher.update(hex::decode(&chunk.sha256).map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+            })?);
+        }
+        
+        let global_hash = global_hasher.finalize();
+        
+        // Create multihash using SHA2-256 (code 0x12)
+        let multihash = Multihash::wrap(0x12, &global_hash).map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Multihash error: {}", e))
+        })?;
+        
+        // Create CID v1 with raw codec
+        let cid = Cid::new_v1(0x55, multihash); // 0x55 is raw codec
+        
+        Ok(cid.to_string())
+    }
+    
+    /// Writes the metadata JSON next to the chunks and returns its path.
+    pub fn save_metadata(&self, metadata: &ShardMetadata, encrypt: bool) -> std::io::Result<PathBuf> {
+        let json = serde_json::to_string_pretty(metadata)?;
+
+        // Encrypted metadata is named after the CID so the filename doesn't leak the original name
+        let (metadata_filename, contents) = if encrypt {
+            let passphrase = encryption::passphrase_from_env()?;
+            let stub = EncryptedMetadataStub {
+                version: encryption::STUB_VERSION,
+                cid: metadata.cid.clone(),
+                encrypted_metadata: encryption::encrypt(json.as_bytes(), passphrase)?,
+            };
+            (format!("{}_metadata.json", metadata.cid), serde_json::to_string_pretty(&stub)?)
+        } else {
+            (format!("{}_metadata.json",
+                metadata.original_file.split('.').next().unwrap_or("file")), json)
+        };
+        let metadata_path = self.output_dir.join(metadata_filename);
+        
+        let mut metadata_file = File::create(&metadata_path)?;
+        metadata_file.write_all(contents.as_bytes())?;
+        
+        if encrypt {
+            self.log(format_args!("Encrypted metadata saved with CID: {}", metadata.cid));
+        } else {
+            self.log(format_args!("Metadata saved with CID: {}", metadata.cid));
+        }
+        Ok(metadata_path)
+    }
+
+    pub fn load_metadata(&self, metadata_path: &str) -> std::io::Result<ShardMetadata> {
+        let metadata_content = std::fs::read_to_string(metadata_path)?;
+        let value: serde_json::Value = serde_json::from_str(&metadata_content)?;
+
+        if value.get("encrypted_metadata").is_none() {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        let stub: EncryptedMetadataStub = serde_json::from_value(value)?;
+        if stub.version != encryption::STUB_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported encrypted metadata version: {}", stub.version)
+            ));
+        }
+
+        self.log(format_args!("Decrypting metadata for CID: {}", stub.cid));
+        let passphrase = encryption::passphrase_from_env()?;
+        let plaintext = encryption::decrypt(&stub.encrypted_metadata, passphrase)?;
+        let metadata: ShardMetadata = serde_json::from_slice(&plaintext)?;
+
+        if metadata.cid != stub.cid {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("CID mismatch: stub has {}, decrypted metadata has {}", stub.cid, metadata.cid)
+            ));
+        }
+
+        Ok(metadata)
+    }
+    
+    pub fn reassemble_file(&self, metadata_path: &str, output_path: &str) -> std::io::Result<()> {
+        // Read metadata, decrypting it first if needed
+        let metadata = self.load_metadata(metadata_path)?;
+        
+        self.log(format_args!("Reassembling file: {}", metadata.original_file));
+        self.log(format_args!("Expected total size: {} bytes", metadata.total_size));
+        
+        let mut output_file = File::create(output_path)?;
+        let mut total_written = 0u64;
+        
+        for (index, chunk_info) in metadata.chunks.iter().enumerate() {
+            let chunk_path = self.output_dir.join(&chunk_info.filename);
+            let mut chunk_file = File::open(&chunk_path)?;
+            let mut chunk_data = Vec::new();
+            chunk_file.read_to_end(&mut chunk_data)?;
+            
+            // Verify chunk integrity
+            let mut hasher = Sha256::new();
+            hasher.update(&chunk_data);
+            let computed_hash = hex::encode(hasher.finalize());
+            
+            if computed_hash != chunk_info.sha256 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Chunk {} integrity check failed", index)
+                ));
+            }
+            
+            output_file.write_all(&chunk_data)?;
+            total_written += chunk_data.len() as u64;
+            
+            self.log(format_args!("Reassembled chunk {}: {} bytes", index, chunk_data.len()));
+        }
+        
+        if total_written != metadata.total_size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Size mismatch: expected {}, got {}", metadata.total_size, total_written)
+            ));
+        }
+        
+        self.log(format_args!("File reassembled successfully: {} bytes", total_written));
+        self.log(format_args!("Original CID: {}", metadata.cid));
+        
+        Ok(())
+    }
+}
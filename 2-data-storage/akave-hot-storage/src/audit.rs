@@ -0,0 +1,186 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::S3Store;
+use crate::checksum::SHA256_METADATA_KEY;
+use crate::output::{OutputFormat, Record, print_records, status};
+use crate::store::ObjectStore;
+
+/// Chunks checked at the same time
+const AUDIT_CONCURRENCY: usize = 16;
+
+#[derive(clap::Args)]
+pub struct AuditArgs {
+    /// ShardMetadata JSON written by blob_cid_shard, listing the chunks to check
+    #[arg(long, value_name = "PATH")]
+    metadata: PathBuf,
+
+    /// Prefix the chunks were uploaded with; keys are `<prefix>_<filename>`
+    #[arg(long)]
+    name_prefix: Option<String>,
+
+    /// Download every chunk and hash its contents, rather than trusting the
+    /// size and SHA-256 recorded at upload
+    #[arg(long)]
+    deep: bool,
+
+    /// How to print the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+}
+
+/// The parts of blob_cid_shard's ShardMetadata an audit checks
+#[derive(Debug, Deserialize)]
+struct ShardMetadata {
+    original_file: String,
+    cid: String,
+    chunks: Vec<ChunkInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkInfo {
+    filename: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum AuditStatus {
+    /// Present, with the expected size and SHA-256
+    Ok,
+    /// No object under the chunk's key
+    Missing,
+    /// Present, but its size or SHA-256 differs from the metadata
+    Mismatched,
+    /// Present with the expected size, but no SHA-256 was recorded to check
+    Unverified,
+}
+
+/// The outcome of checking one chunk
+#[derive(Serialize)]
+struct ChunkAudit {
+    chunk_index: usize,
+    key: String,
+    status: AuditStatus,
+    problems: String,
+}
+
+impl Record for ChunkAudit {
+    const COLUMNS: &'static [&'static str] = &["chunk_index", "key", "status", "problems"];
+}
+
+/// Checks that every chunk in the metadata file is in the bucket with the
+/// size and SHA-256 it was sharded with, and fails if any is missing or
+/// doesn't match.
+pub async fn run(client: &S3Store, args: &AuditArgs) -> Result<()> {
+    let json = tokio::fs::read_to_string(&args.metadata)
+        .await
+        .with_context(|| format!("Failed to read shard metadata: {:?}", args.metadata))?;
+    let metadata: ShardMetadata = serde_json::from_str(&json).with_context(|| {
+        format!(
+            "Failed to parse shard metadata: {:?} (encrypted metadata must be decrypted first)",
+            args.metadata
+        )
+    })?;
+
+    status!(
+        "🔍 Auditing {} chunks of {} (CID {}){}...",
+        metadata.chunks.len(),
+        metadata.original_file,
+        metadata.cid,
+        if args.deep { ", reading every chunk" } else { "" }
+    );
+
+    let audits: Vec<ChunkAudit> = stream::iter(metadata.chunks.iter().enumerate())
+        .map(|(chunk_index, chunk)| audit_chunk(client, args, chunk_index, chunk))
+        .buffered(AUDIT_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let count = |status| audits.iter().filter(|audit| audit.status == status).count();
+    let (ok, missing, mismatched, unverified) = (
+        count(AuditStatus::Ok),
+        count(AuditStatus::Missing),
+        count(AuditStatus::Mismatched),
+        count(AuditStatus::Unverified),
+    );
+    if args.output == OutputFormat::Table {
+        for audit in audits.iter().filter(|audit| audit.status != AuditStatus::Ok) {
+            println!("  ❌ chunk {} ({}): {}", audit.chunk_index, audit.key, audit.problems);
+        }
+    }
+    status!("\n📊 Audit summary:");
+    status!("   ✅ OK: {}", ok);
+    status!("   ❌ Missing: {}", missing);
+    status!("   ❌ Mismatched: {}", mismatched);
+    if unverified > 0 {
+        status!("   ⚠️  No SHA-256 recorded: {}", unverified);
+    }
+    print_records(args.output, &audits)?;
+
+    if ok < audits.len() {
+        anyhow::bail!(
+            "Audit failed: {} of {} chunks of CID {} are missing or don't match",
+            audits.len() - ok,
+            audits.len(),
+            metadata.cid
+        );
+    }
+    Ok(())
+}
+
+/// Looks up one chunk's object and compares it with the metadata.
+async fn audit_chunk(client: &S3Store, args: &AuditArgs, chunk_index: usize, chunk: &ChunkInfo) -> Result<ChunkAudit> {
+    let key = match &args.name_prefix {
+        Some(prefix) => format!("{}_{}", prefix, chunk.filename),
+        None => chunk.filename.clone(),
+    };
+    let audit = |status, problems: Vec<String>| ChunkAudit {
+        chunk_index,
+        key: key.clone(),
+        status,
+        problems: problems.join("; "),
+    };
+
+    let Some(head) = client.head(&key).await? else {
+        return Ok(audit(AuditStatus::Missing, vec!["not in the bucket".to_string()]));
+    };
+
+    let mut problems = Vec::new();
+    if head.size != chunk.size {
+        problems.push(format!("size is {} bytes, expected {}", head.size, chunk.size));
+    }
+    let recorded = head.metadata.get(SHA256_METADATA_KEY);
+    match recorded {
+        Some(recorded) if recorded.eq_ignore_ascii_case(&chunk.sha256) => {}
+        Some(recorded) => problems.push(format!("recorded SHA-256 is {}, expected {}", recorded, chunk.sha256)),
+        None => {}
+    }
+    if args.deep && problems.is_empty() {
+        let sha256 = content_sha256(client, &key).await?;
+        if !sha256.eq_ignore_ascii_case(&chunk.sha256) {
+            problems.push(format!("contents hash to {}, expected {}", sha256, chunk.sha256));
+        }
+    }
+
+    Ok(match (problems.is_empty(), recorded.is_some() || args.deep) {
+        (false, _) => audit(AuditStatus::Mismatched, problems),
+        (true, true) => audit(AuditStatus::Ok, problems),
+        (true, false) => audit(AuditStatus::Unverified, vec!["no SHA-256 recorded, use --deep".to_string()]),
+    })
+}
+
+/// Downloads the object under `key` and returns the SHA-256 of its contents.
+async fn content_sha256(client: &S3Store, key: &str) -> Result<String> {
+    let mut body = client.get(key).await?;
+    let mut hasher = Sha256::new();
+    while let Some(bytes) = body.try_next().await.with_context(|| format!("Failed to read object: {}", key))? {
+        hasher.update(&bytes);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use aws_sdk_s3::operation::create_bucket::CreateBucketError;
+use aws_sdk_s3::types::{BucketVersioningStatus, VersioningConfiguration};
+use clap::Subcommand;
+
+use crate::S3Store;
+
+#[derive(clap::Args)]
+pub struct BucketArgs {
+    #[command(subcommand)]
+    action: BucketAction,
+}
+
+#[derive(Subcommand)]
+enum BucketAction {
+    /// Create the bucket, failing if it already exists
+    Create,
+    /// Create the bucket unless it already exists; safe to run on every deployment
+    Ensure,
+    /// Show the bucket's region, owner, creation date and versioning status
+    Info,
+    /// Show whether the bucket keeps earlier versions of objects, or turn it on or off
+    Versioning {
+        /// Keep every version of an object when it is overwritten or deleted
+        #[arg(long, conflicts_with = "suspend")]
+        enable: bool,
+
+        /// Stop keeping new versions; versions already kept stay in the bucket
+        #[arg(long)]
+        suspend: bool,
+    },
+}
+
+pub async fn run(client: &S3Store, args: &BucketArgs) -> Result<()> {
+    match &args.action {
+        BucketAction::Create => {
+            create_bucket(client).await?;
+            println!("🪣 Created bucket: {}", client.bucket_name);
+        }
+        BucketAction::Ensure => {
+            if bucket_exists(client).await? {
+                println!("🪣 Bucket already exists: {}", client.bucket_name);
+            } else {
+                match create_bucket(client).await {
+                    Ok(()) => println!("🪣 Created bucket: {}", client.bucket_name),
+                    // Created by someone else since we looked
+                    Err(e) if is_already_owned(&e) => println!("🪣 Bucket already exists: {}", client.bucket_name),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        BucketAction::Info => {
+            if !bucket_exists(client).await? {
+                anyhow::bail!("Bucket does not exist: {}; create it with `bucket ensure`", client.bucket_name);
+            }
+            print_info(client).await?;
+        }
+        BucketAction::Versioning { enable, suspend } => {
+            let status = match (enable, suspend) {
+                (true, _) => BucketVersioningStatus::Enabled,
+                (_, true) => BucketVersioningStatus::Suspended,
+                _ => {
+                    println!("🪣 Versioning: {}", versioning_status(client).await?);
+                    return Ok(());
+                }
+            };
+            client
+                .client
+                .put_bucket_versioning()
+                .bucket(&client.bucket_name)
+                .versioning_configuration(VersioningConfiguration::builder().status(status.clone()).build())
+                .send()
+                .await
+                .with_context(|| format!("Failed to set versioning on bucket: {}", client.bucket_name))?;
+            println!("🪣 Versioning {} on bucket: {}", status.as_str().to_lowercase(), client.bucket_name);
+        }
+    }
+    Ok(())
+}
+
+async fn bucket_exists(client: &S3Store) -> Result<bool> {
+    let head = client.client.head_bucket().bucket(&client.bucket_name).send().await;
+    match head {
+        Ok(_) => Ok(true),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("Failed to look up bucket: {}", client.bucket_name)),
+    }
+}
+
+async fn create_bucket(client: &S3Store) -> Result<()> {
+    client
+        .client
+        .create_bucket()
+        .bucket(&client.bucket_name)
+        .send()
+        .await
+        .with_context(|| format!("Failed to create bucket: {}", client.bucket_name))?;
+    Ok(())
+}
+
+fn is_already_owned(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<aws_sdk_s3::error::SdkError<CreateBucketError>>()
+        .and_then(|e| e.as_service_error())
+        .is_some_and(|e| e.is_bucket_already_owned_by_you())
+}
+
+/// Prints what the endpoint reports about the bucket. Not every
+/// S3-compatible service supports each call, so missing details are shown as
+/// unknown rather than failing.
+async fn print_info(client: &S3Store) -> Result<()> {
+    let region = client
+        .client
+        .get_bucket_location()
+        .bucket(&client.bucket_name)
+        .send()
+        .await
+        .ok()
+        .and_then(|location| location.location_constraint().map(|constraint| constraint.as_str().to_string()))
+        .filter(|region| !region.is_empty());
+    let buckets = client
+        .client
+        .list_buckets()
+        .send()
+        .await
+        .with_context(|| "Failed to list buckets")?;
+    let owner = buckets.owner().map(|owner| {
+        match (owner.display_name(), owner.id()) {
+            (Some(name), Some(id)) => format!("{} ({})", name, id),
+            (name, id) => name.or(id).unwrap_or("unknown").to_string(),
+        }
+    });
+    let created = buckets
+        .buckets()
+        .iter()
+        .find(|bucket| bucket.name() == Some(client.bucket_name.as_str()))
+        .and_then(|bucket| bucket.creation_date());
+
+    println!("🪣 Bucket: {}", client.bucket_name);
+    println!("   Region: {}", region.as_deref().unwrap_or("unknown"));
+    println!("   Owner: {}", owner.as_deref().unwrap_or("unknown"));
+    match created {
+        Some(created) => println!("   Created: {}", created),
+        None => println!("   Created: unknown"),
+    }
+    match versioning_status(client).await {
+        Ok(status) => println!("   Versioning: {}", status),
+        Err(_) => println!("   Versioning: unknown"),
+    }
+    Ok(())
+}
+
+/// Enabled or Suspended, or "never enabled" for a bucket that has never had
+/// versioning turned on
+async fn versioning_status(client: &S3Store) -> Result<String> {
+    let versioning = client
+        .client
+        .get_bucket_versioning()
+        .bucket(&client.bucket_name)
+        .send()
+        .await
+        .with_context(|| format!("Failed to get versioning status of bucket: {}", client.bucket_name))?;
+    Ok(match versioning.status() {
+        Some(status) => status.as_str().to_string(),
+        None => "never enabled".to_string(),
+    })
+}
@@ -0,0 +1,35 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+/// Object metadata key holding the SHA-256 of the uploaded file, sent as
+/// `x-amz-meta-sha256` and checked again on download
+pub const SHA256_METADATA_KEY: &str = "sha256";
+
+/// Hex-encoded SHA-256 of a file's contents, streamed so large files aren't read into memory.
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {:?}", path))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).with_context(|| format!("Failed to hash file: {:?}", path))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Hex-encoded MD5 of a file's contents, to compare with a single-part ETag.
+pub fn file_md5(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {:?}", path))?;
+    let mut hasher = Md5::new();
+    io::copy(&mut file, &mut hasher).with_context(|| format!("Failed to hash file: {:?}", path))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Runs one of the hash functions above off the async runtime.
+pub async fn hash_file(file_path: &Path, hash: fn(&Path) -> Result<String>) -> Result<String> {
+    let file_path = file_path.to_path_buf();
+    tokio::task::spawn_blocking(move || hash(&file_path))
+        .await
+        .context("Hashing task panicked")?
+}
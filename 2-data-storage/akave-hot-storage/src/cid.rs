@@ -0,0 +1,214 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+/// Object metadata key holding the IPFS CID of the uploaded file's contents
+pub const CID_METADATA_KEY: &str = "cid";
+
+const RAW_CODEC: u64 = 0x55;
+const DAG_PB_CODEC: u64 = 0x70;
+
+/// Multihash code for sha2-256
+const SHA2_256: u64 = 0x12;
+
+/// UnixFS `Data.DataType.File`
+const UNIXFS_FILE: u64 = 2;
+
+/// Kubo's defaults for `ipfs add --cid-version 1`, which Pinata follows, so
+/// the CID matches the one the file is pinned under
+const CHUNK_SIZE: usize = 256 * 1024;
+const MAX_LINKS: usize = 174;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A CIDv1 over a sha2-256 digest
+#[derive(Clone, Copy)]
+struct Cid {
+    codec: u64,
+    digest: [u8; 32],
+}
+
+/// A child link while building a layer of the tree
+struct Link {
+    cid: Cid,
+    /// Serialized size of the child and all its descendants
+    tsize: u64,
+    /// Number of file bytes under the child
+    filesize: u64,
+}
+
+/// The CID the file gets when added to IPFS: the root of a balanced UnixFS
+/// DAG over raw leaves, as Kubo and the JS importer build it for CIDv1.
+/// Streamed a chunk at a time so large files aren't read into memory.
+pub fn file_cid(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {:?}", path))?;
+
+    let mut hasher = CidHasher::default();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        let len = read_chunk(&mut file, &mut buffer).with_context(|| format!("Failed to read file: {:?}", path))?;
+        hasher.update(&buffer[..len]);
+        if len < CHUNK_SIZE {
+            break;
+        }
+    }
+    Ok(hasher.finish())
+}
+
+/// Computes the same CID as `file_cid` over content given piece by piece,
+/// e.g. as it is read from a pipe. Only the current leaf is buffered.
+#[derive(Default)]
+pub struct CidHasher {
+    leaves: Vec<Link>,
+    /// Bytes of the leaf not yet full
+    pending: Vec<u8>,
+}
+
+impl CidHasher {
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let take = (CHUNK_SIZE - self.pending.len()).min(bytes.len());
+            self.pending.extend_from_slice(&bytes[..take]);
+            bytes = &bytes[take..];
+            if self.pending.len() == CHUNK_SIZE {
+                self.push_leaf();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        // An empty file is still a single (empty) leaf
+        if !self.pending.is_empty() || self.leaves.is_empty() {
+            self.push_leaf();
+        }
+
+        let mut layer = self.leaves;
+        while layer.len() > 1 {
+            layer = layer
+                .chunks(MAX_LINKS)
+                .map(|children| {
+                    let bytes = encode_file_node(children);
+                    Link {
+                        cid: Cid::sha256(DAG_PB_CODEC, &bytes),
+                        tsize: bytes.len() as u64 + children.iter().map(|child| child.tsize).sum::<u64>(),
+                        filesize: children.iter().map(|child| child.filesize).sum(),
+                    }
+                })
+                .collect();
+        }
+        layer[0].cid.to_string()
+    }
+
+    fn push_leaf(&mut self) {
+        let len = self.pending.len() as u64;
+        self.leaves.push(Link {
+            cid: Cid::sha256(RAW_CODEC, &self.pending),
+            tsize: len,
+            filesize: len,
+        });
+        self.pending.clear();
+    }
+}
+
+impl Cid {
+    fn sha256(codec: u64, bytes: &[u8]) -> Self {
+        Self {
+            codec,
+            digest: Sha256::digest(bytes).into(),
+        }
+    }
+
+    /// Binary form: version, codec and multihash as varints.
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(36);
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, self.codec);
+        write_varint(&mut bytes, SHA2_256);
+        write_varint(&mut bytes, self.digest.len() as u64);
+        bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+}
+
+/// Formats as multibase base32 (`b...`), the default string form for CIDv1.
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.to_bytes();
+        let mut encoded = String::with_capacity(1 + (bytes.len() * 8).div_ceil(5));
+        encoded.push('b');
+
+        let mut buffer = 0u32;
+        let mut bits = 0;
+        for byte in bytes {
+            buffer = (buffer << 8) | byte as u32;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+        }
+
+        f.write_str(&encoded)
+    }
+}
+
+/// Fills the buffer from the reader, returning fewer bytes only at end of file.
+fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Encodes a dag-pb PBNode for a UnixFS file node. dag-pb's canonical form
+/// writes Links (field 2) before Data (field 1).
+fn encode_file_node(children: &[Link]) -> Vec<u8> {
+    let mut node = Vec::new();
+    for child in children {
+        let mut link = Vec::new();
+        write_bytes_field(&mut link, 1, &child.cid.to_bytes());
+        write_bytes_field(&mut link, 2, b"");
+        write_varint_field(&mut link, 3, child.tsize);
+        write_bytes_field(&mut node, 2, &link);
+    }
+
+    let mut data = Vec::new();
+    write_varint_field(&mut data, 1, UNIXFS_FILE);
+    write_varint_field(&mut data, 3, children.iter().map(|child| child.filesize).sum());
+    for child in children {
+        write_varint_field(&mut data, 4, child.filesize);
+    }
+    write_bytes_field(&mut node, 1, &data);
+
+    node
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(out, field << 3);
+    write_varint(out, value);
+}
+
+fn write_bytes_field(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(out, (field << 3) | 2);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
@@ -0,0 +1,30 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Used when a file's type can't be told from its name or contents
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Bytes read from the start of a file to recognize its type; enough for
+/// every signature `infer` knows
+const SNIFF_LEN: u64 = 8192;
+
+/// The Content-Type to store with a file, so gateways and browsers serve it
+/// as what it is. Guessed from the extension; with `sniff`, files whose
+/// extension is missing or unknown are recognized by their magic bytes.
+/// Shard chunks (`.part`) and anything else unrecognized stay binary.
+pub fn detect(file_path: &Path, sniff: bool) -> String {
+    if let Some(mime) = mime_guess::from_path(file_path).first() {
+        return mime.essence_str().to_string();
+    }
+    if sniff && let Some(kind) = sniff_file(file_path) {
+        return kind.mime_type().to_string();
+    }
+    DEFAULT_CONTENT_TYPE.to_string()
+}
+
+fn sniff_file(file_path: &Path) -> Option<infer::Type> {
+    let mut head = Vec::new();
+    File::open(file_path).ok()?.take(SNIFF_LEN).read_to_end(&mut head).ok()?;
+    infer::get(&head)
+}
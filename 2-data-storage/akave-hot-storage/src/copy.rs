@@ -0,0 +1,182 @@
+use anyhow::{Context, Result};
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+use crate::S3Store;
+use crate::encryption::{with_copy_source_customer_key, with_customer_key};
+use crate::multipart::MAX_PARTS;
+
+/// CopyObject copies objects of up to 5 GiB; larger ones are copied part by part
+pub const MAX_COPY_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Characters escaped in a copy source; `/` is kept since it separates the
+/// bucket from the key and the key's folders
+const COPY_SOURCE_ESCAPED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(clap::Args)]
+pub struct CopyArgs {
+    /// Key of the object to copy, in the bucket named by AKAVE_BUCKET
+    #[arg(long)]
+    src_key: String,
+
+    /// Key to copy it to
+    #[arg(long)]
+    dst_key: String,
+
+    /// Bucket to copy it to, e.g. an archive bucket; defaults to the source bucket
+    #[arg(long)]
+    dst_bucket: Option<String>,
+}
+
+/// Copies an object within the endpoint, so its content never passes
+/// through this machine. Its Content-Type and metadata, including the
+/// SHA-256 recorded at upload, are copied with it.
+pub async fn run(client: &S3Store, args: &CopyArgs) -> Result<()> {
+    let dst_bucket = args.dst_bucket.as_deref().unwrap_or(&client.bucket_name);
+    if dst_bucket == client.bucket_name && args.dst_key == args.src_key {
+        anyhow::bail!("--dst-key is the same as --src-key; give --dst-bucket to copy it to another bucket");
+    }
+
+    let request = client.client.head_object().bucket(&client.bucket_name).key(&args.src_key);
+    let head = match with_customer_key!(request, client.encryption).send().await {
+        Ok(head) => head,
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {
+            anyhow::bail!("Object not found: {}", args.src_key);
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to look up object: {}", args.src_key)),
+    };
+    let size = head.content_length().unwrap_or_default().max(0) as u64;
+    let source = copy_source(&client.bucket_name, &args.src_key, None);
+
+    println!("📋 Copying {} ({} bytes) to {}/{}", args.src_key, size, dst_bucket, args.dst_key);
+    let etag = if size > MAX_COPY_OBJECT_SIZE {
+        copy_multipart(client, &head, &source, size, dst_bucket, &args.dst_key).await?
+    } else {
+        let request = client
+            .client
+            .copy_object()
+            .bucket(dst_bucket)
+            .key(&args.dst_key)
+            .copy_source(&source)
+            .set_server_side_encryption(client.encryption.server_side_encryption());
+        let request = with_copy_source_customer_key!(request, client.encryption);
+        let copied = with_customer_key!(request, client.encryption)
+            .send()
+            .await
+            .with_context(|| format!("Failed to copy {} to {}/{}", args.src_key, dst_bucket, args.dst_key))?;
+        copied
+            .copy_object_result()
+            .and_then(|result| result.e_tag())
+            .unwrap_or_default()
+            .to_string()
+    };
+    println!("✅ Copied to {}/{} (ETag: {})", dst_bucket, args.dst_key, etag);
+    Ok(())
+}
+
+/// Copies an object too large for CopyObject as a multipart upload whose
+/// parts are byte ranges of the source, of the client's part size. The
+/// upload is aborted if any part fails. Returns the copy's ETag.
+async fn copy_multipart(
+    client: &S3Store,
+    head: &HeadObjectOutput,
+    source: &str,
+    size: u64,
+    dst_bucket: &str,
+    dst_key: &str,
+) -> Result<String> {
+    // Copies are limited like uploads, so use at least the part size that
+    // keeps the object within S3's part limit
+    let part_size = client.part_size.max(size.div_ceil(MAX_PARTS));
+    let part_count = size.div_ceil(part_size);
+    let request = client
+        .client
+        .create_multipart_upload()
+        .bucket(dst_bucket)
+        .key(dst_key)
+        .set_content_type(head.content_type().map(str::to_string))
+        .set_metadata(head.metadata().cloned())
+        .set_server_side_encryption(client.encryption.server_side_encryption());
+    let created = with_customer_key!(request, client.encryption)
+        .send()
+        .await
+        .with_context(|| format!("Failed to start multipart copy to {}/{}", dst_bucket, dst_key))?;
+    let upload_id = created
+        .upload_id()
+        .context("Multipart copy was created without an upload ID")?
+        .to_string();
+
+    println!("   Multipart copy: {} parts of up to {} bytes", part_count, part_size);
+    let copied = async {
+        let mut parts = Vec::new();
+        for index in 0..part_count {
+            let part_number = index as i32 + 1;
+            let start = index * part_size;
+            let end = (start + part_size).min(size) - 1;
+            let request = client
+                .client
+                .upload_part_copy()
+                .bucket(dst_bucket)
+                .key(dst_key)
+                .upload_id(&upload_id)
+                .part_number(part_number)
+                .copy_source(source)
+                .copy_source_range(format!("bytes={}-{}", start, end));
+            let request = with_copy_source_customer_key!(request, client.encryption);
+            let part = with_customer_key!(request, client.encryption)
+                .send()
+                .await
+                .with_context(|| format!("Failed to copy part {} of {}", part_number, part_count))?;
+            let etag = part
+                .copy_part_result()
+                .and_then(|result| result.e_tag())
+                .with_context(|| format!("Part {} was copied without an ETag", part_number))?;
+            parts.push(CompletedPart::builder().part_number(part_number).e_tag(etag).build());
+            println!("   [{}/{}] Copied bytes {}-{}", part_number, part_count, start, end);
+        }
+
+        let request = client
+            .client
+            .complete_multipart_upload()
+            .bucket(dst_bucket)
+            .key(dst_key)
+            .upload_id(&upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build());
+        let completed = with_customer_key!(request, client.encryption)
+            .send()
+            .await
+            .with_context(|| format!("Failed to complete multipart copy to {}/{}", dst_bucket, dst_key))?;
+        Ok(completed.e_tag().unwrap_or_default().to_string())
+    }
+    .await;
+
+    if copied.is_err() {
+        let abort = client
+            .client
+            .abort_multipart_upload()
+            .bucket(dst_bucket)
+            .key(dst_key)
+            .upload_id(&upload_id)
+            .send()
+            .await;
+        if let Err(e) = abort {
+            println!("⚠️  Failed to abort multipart copy to {}/{}: {}", dst_bucket, dst_key, e);
+        }
+    }
+    copied
+}
+
+/// The `x-amz-copy-source` value naming an object, or one version of it.
+pub fn copy_source(bucket: &str, key: &str, version_id: Option<&str>) -> String {
+    let object = utf8_percent_encode(&format!("{}/{}", bucket, key), COPY_SOURCE_ESCAPED).to_string();
+    match version_id {
+        Some(version_id) => format!("{}?versionId={}", object, utf8_percent_encode(version_id, COPY_SOURCE_ESCAPED)),
+        None => object,
+    }
+}
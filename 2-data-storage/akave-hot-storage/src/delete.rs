@@ -0,0 +1,204 @@
+use std::fs::File;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+use chrono::Utc;
+
+use crate::S3Store;
+use crate::store::ObjectStore;
+
+/// Most keys one DeleteObjects request may name
+const MAX_DELETE_BATCH: usize = 1000;
+
+#[derive(clap::Args)]
+pub struct DeleteArgs {
+    /// Key of the object to delete
+    #[arg(long, required_unless_present = "prefix", conflicts_with = "prefix")]
+    key: Option<String>,
+
+    /// Delete every object whose key starts with this prefix
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// Only list the objects matched by --prefix, without deleting them
+    #[arg(long, requires = "prefix")]
+    dry_run: bool,
+
+    /// Delete the objects matched by --prefix without asking first
+    #[arg(long, alias = "confirm", requires = "prefix", conflicts_with = "dry_run")]
+    yes: bool,
+
+    /// Where to record the keys deleted by --prefix [default: delete-<time>.log]
+    #[arg(long, value_name = "PATH", requires = "prefix")]
+    log: Option<PathBuf>,
+}
+
+pub async fn run(client: &S3Store, args: &DeleteArgs) -> Result<()> {
+    if let Some(key) = &args.key {
+        // S3 reports success for keys that don't exist, so check first to
+        // catch typos instead of claiming a deletion that didn't happen
+        if client.head(key).await?.is_none() {
+            anyhow::bail!("Object not found: {}", key);
+        }
+        client.delete(key).await?;
+        println!("🗑️  Deleted: {}", key);
+        return Ok(());
+    }
+
+    let prefix = args.prefix.as_deref().unwrap_or_default();
+    if prefix.is_empty() {
+        anyhow::bail!("--prefix cannot be empty; it would match the whole bucket");
+    }
+    let keys = client.list_keys(prefix).await?;
+    if keys.is_empty() {
+        println!("No objects found with prefix: {}", prefix);
+        return Ok(());
+    }
+
+    println!("📁 Found {} objects with prefix {}:", keys.len(), prefix);
+    for (index, key) in keys.iter().enumerate() {
+        println!("  [{}] {}", index + 1, key);
+    }
+    if args.dry_run {
+        println!("\nNothing deleted (--dry-run).");
+        return Ok(());
+    }
+    if !args.yes && !confirm(&keys, prefix)? {
+        println!("\nNothing deleted.");
+        return Ok(());
+    }
+
+    let log_path = args
+        .log
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("delete-{}.log", Utc::now().format("%Y%m%dT%H%M%SZ"))));
+    let mut log = DeleteLog::create(&log_path, &client.bucket_name, prefix)?;
+
+    println!();
+    let mut deleted = 0;
+    let mut failed = 0;
+    for batch in keys.chunks(MAX_DELETE_BATCH) {
+        let (batch_deleted, batch_failed) = delete_batch(client, batch).await;
+        for key in &batch_deleted {
+            println!("[{}/{}] 🗑️  Deleted: {}", deleted + failed + 1, keys.len(), key);
+            deleted += 1;
+        }
+        for (key, error) in &batch_failed {
+            println!("[{}/{}] ❌ Failed to delete {}: {}", deleted + failed + 1, keys.len(), key, error);
+            failed += 1;
+        }
+        log.record(&batch_deleted, &batch_failed)?;
+    }
+    log.finish(deleted, failed)?;
+
+    println!("\nDelete Summary:");
+    println!("   🗑️  Deleted: {}", deleted);
+    println!("   ❌ Failed: {}", failed);
+    println!("   📁 Total objects: {}", keys.len());
+    println!("   📝 Log: {:?}", log_path);
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} deletions failed", failed, keys.len());
+    }
+    Ok(())
+}
+
+/// Asks on the terminal whether to delete the listed objects. Refuses when
+/// there is no terminal to ask on, so scripts must pass --yes.
+fn confirm(keys: &[String], prefix: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("Not deleting without confirmation: pass --yes to delete these objects, or --dry-run to only list them");
+    }
+    print!("\nDelete these {} objects with prefix {}? Type 'yes' to continue: ", keys.len(), prefix);
+    std::io::stdout().flush().context("Failed to write prompt")?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).context("Failed to read confirmation")?;
+    Ok(answer.trim() == "yes")
+}
+
+/// Deletes up to `MAX_DELETE_BATCH` keys in one request, returning the keys
+/// deleted and those that failed with why. If the request itself fails,
+/// every key in it counts as failed.
+async fn delete_batch(client: &S3Store, keys: &[String]) -> (Vec<String>, Vec<(String, String)>) {
+    let objects: Result<Vec<ObjectIdentifier>, _> =
+        keys.iter().map(|key| ObjectIdentifier::builder().key(key).build()).collect();
+    let request = objects
+        .and_then(|objects| Delete::builder().set_objects(Some(objects)).build())
+        .map(|delete| client.client.delete_objects().bucket(&client.bucket_name).delete(delete));
+    let output = match request {
+        Ok(request) => request.send().await.map_err(|e| format!("{:#}", anyhow::Error::new(e))),
+        Err(e) => Err(e.to_string()),
+    };
+    let output = match output {
+        Ok(output) => output,
+        Err(error) => return (Vec::new(), keys.iter().map(|key| (key.clone(), error.clone())).collect()),
+    };
+
+    let deleted = output.deleted().iter().filter_map(|object| object.key().map(str::to_string)).collect();
+    let failed = output
+        .errors()
+        .iter()
+        .map(|error| {
+            let reason = match (error.code(), error.message()) {
+                (Some(code), Some(message)) => format!("{}: {}", code, message),
+                (code, message) => code.or(message).unwrap_or("unknown error").to_string(),
+            };
+            (error.key().unwrap_or_default().to_string(), reason)
+        })
+        .collect();
+    (deleted, failed)
+}
+
+/// Record of a prefix delete: a header naming the bucket and prefix, one
+/// line per key (`deleted <key>` or `failed <key> <error>`, tab-separated)
+/// and a closing summary. Written as each batch completes, so it stays
+/// accurate if the run is interrupted.
+struct DeleteLog {
+    path: PathBuf,
+    file: File,
+}
+
+impl DeleteLog {
+    fn create(path: &Path, bucket: &str, prefix: &str) -> Result<Self> {
+        let mut file = File::create(path).with_context(|| format!("Failed to create delete log: {:?}", path))?;
+        writeln!(
+            file,
+            "# Deleting objects from bucket '{}' with prefix '{}', started {}",
+            bucket,
+            prefix,
+            Utc::now().to_rfc3339()
+        )
+        .with_context(|| format!("Failed to write delete log: {:?}", path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    fn record(&mut self, deleted: &[String], failed: &[(String, String)]) -> Result<()> {
+        let mut lines = String::new();
+        for key in deleted {
+            lines.push_str(&format!("deleted\t{}\n", key));
+        }
+        for (key, error) in failed {
+            lines.push_str(&format!("failed\t{}\t{}\n", key, error));
+        }
+        self.file
+            .write_all(lines.as_bytes())
+            .with_context(|| format!("Failed to write delete log: {:?}", self.path))
+    }
+
+    fn finish(mut self, deleted: usize, failed: usize) -> Result<()> {
+        writeln!(
+            self.file,
+            "# Finished {}: {} deleted, {} failed",
+            Utc::now().to_rfc3339(),
+            deleted,
+            failed
+        )
+        .and_then(|()| self.file.sync_all())
+        .with_context(|| format!("Failed to write delete log: {:?}", self.path))
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+
+use crate::S3Store;
+use crate::checksum::SHA256_METADATA_KEY;
+use crate::encryption::with_customer_key;
+use crate::output::{OutputFormat, Record, print_records, status};
+
+#[derive(clap::Args)]
+pub struct DownloadArgs {
+    /// Key of the object to download
+    #[arg(long, required_unless_present = "prefix", conflicts_with = "prefix")]
+    key: Option<String>,
+
+    /// Download every object whose key starts with this prefix
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// File to write with --key (default: the key's file name), or directory
+    /// to write into with --prefix (default: the current directory)
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Only download bytes START-END of --key, both inclusive and counted
+    /// from 0 (END may be left off to read to the end); the slice can't be
+    /// checked against the object's SHA-256
+    #[arg(long, conflicts_with = "prefix")]
+    range: Option<ByteRange>,
+
+    /// How to print the results; JSON and CSV list every object at the end
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+}
+
+/// Whether a downloaded object could be checked against its recorded hash
+#[derive(Clone, Copy)]
+enum Verification {
+    Verified,
+    /// The object was uploaded without a SHA-256 in its metadata
+    Unrecorded,
+    /// Only a byte range was downloaded, which the recorded hash doesn't cover
+    Partial,
+}
+
+/// A slice of an object, as sent in a Range header
+#[derive(Clone, Copy)]
+struct ByteRange {
+    start: u64,
+    /// Inclusive; None reads to the end of the object
+    end: Option<u64>,
+}
+
+impl FromStr for ByteRange {
+    type Err = String;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let (start, end) = range
+            .split_once('-')
+            .ok_or_else(|| format!("expected START-END, e.g. 0-1048575, got: {}", range))?;
+        let start = start.parse().map_err(|_| format!("invalid start offset: {}", start))?;
+        let end = match end {
+            "" => None,
+            end => Some(end.parse().map_err(|_| format!("invalid end offset: {}", end))?),
+        };
+        if end.is_some_and(|end| end < start) {
+            return Err(format!("range ends before it starts: {}", range));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}-{}", self.start, end),
+            None => write!(f, "{}-", self.start),
+        }
+    }
+}
+
+/// An object saved to disk
+struct Downloaded {
+    size: u64,
+    sha256: String,
+    verification: Verification,
+}
+
+/// The outcome of downloading one object
+#[derive(Serialize)]
+struct DownloadRecord {
+    key: String,
+    /// None if the key can't be saved as a file
+    path: Option<PathBuf>,
+    size: Option<u64>,
+    sha256: Option<String>,
+    /// False when no SHA-256 was recorded to check against, or only a range
+    /// was downloaded
+    verified: bool,
+    error: Option<String>,
+}
+
+impl Record for DownloadRecord {
+    const COLUMNS: &'static [&'static str] = &["key", "path", "size", "sha256", "verified", "error"];
+}
+
+impl DownloadRecord {
+    fn new(key: &str, path: Option<&Path>, result: &Result<Downloaded>) -> Self {
+        let downloaded = result.as_ref().ok();
+        Self {
+            key: key.to_string(),
+            path: path.map(Path::to_path_buf),
+            size: downloaded.map(|downloaded| downloaded.size),
+            sha256: downloaded.map(|downloaded| downloaded.sha256.clone()),
+            verified: downloaded.is_some_and(|downloaded| matches!(downloaded.verification, Verification::Verified)),
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+        }
+    }
+}
+
+pub async fn run(client: &S3Store, args: &DownloadArgs) -> Result<()> {
+    if let Some(key) = &args.key {
+        let out = match &args.out {
+            Some(out) => out.clone(),
+            None => PathBuf::from(key.rsplit('/').next().unwrap_or(key)),
+        };
+        let downloaded = download_object(client, key, &out, args.range).await;
+        if downloaded.is_ok() {
+            print_records(args.output, &[DownloadRecord::new(key, Some(&out), &downloaded)])?;
+        }
+        downloaded?;
+        return Ok(());
+    }
+
+    let prefix = args.prefix.as_deref().unwrap_or_default();
+    let out_dir = args.out.clone().unwrap_or_else(|| PathBuf::from("."));
+    let keys = client.list_keys(prefix).await?;
+    if keys.is_empty() {
+        status!("No objects found with prefix: {}", prefix);
+        return Ok(());
+    }
+    status!("📁 Found {} objects to download\n", keys.len());
+
+    let mut verified = 0;
+    let mut unrecorded = 0;
+    let mut failed = 0;
+    let mut records = Vec::new();
+    for (index, key) in keys.iter().enumerate() {
+        status!("[{}/{}] Processing object: {}", index + 1, keys.len(), key);
+        let (out, result) = match key_path(&out_dir, key) {
+            Ok(out) => {
+                let result = download_object(client, key, &out, None).await;
+                (Some(out), result)
+            }
+            Err(e) => (None, Err(e)),
+        };
+        match &result {
+            Ok(downloaded) => match downloaded.verification {
+                Verification::Verified => verified += 1,
+                Verification::Unrecorded | Verification::Partial => unrecorded += 1,
+            },
+            Err(e) => {
+                status!("❌ Failed to download {}: {:#}", key, e);
+                failed += 1;
+            }
+        }
+        records.push(DownloadRecord::new(key, out.as_deref(), &result));
+        status!();
+    }
+
+    status!("Download Summary:");
+    status!("   ✅ Verified: {}", verified);
+    status!("   ⚠️  Unverified (no recorded hash): {}", unrecorded);
+    status!("   ❌ Failed: {}", failed);
+    status!("   📁 Total objects: {}", keys.len());
+    print_records(args.output, &records)?;
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} downloads failed", failed, keys.len());
+    }
+    Ok(())
+}
+
+/// Where an object downloaded into `out_dir` is written, keeping the key's
+/// `/`-separated folders. Keys that would escape `out_dir` are rejected.
+fn key_path(out_dir: &Path, key: &str) -> Result<PathBuf> {
+    let relative = Path::new(key);
+    let is_plain = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if key.is_empty() || key.ends_with('/') || !is_plain {
+        anyhow::bail!("Object key can't be saved as a file: {}", key);
+    }
+    Ok(out_dir.join(relative))
+}
+
+/// Streams an object to `out`, hashing it on the way, and only moves it into
+/// place once the SHA-256 recorded at upload matches. A mismatched or partial
+/// download is removed so it can't be mistaken for a good copy. With a
+/// range, only that slice is saved, unverified.
+async fn download_object(client: &S3Store, key: &str, out: &Path, range: Option<ByteRange>) -> Result<Downloaded> {
+    match range {
+        Some(range) => status!("Downloading bytes {} of object: {} -> {:?}", range, key, out),
+        None => status!("Downloading object: {} -> {:?}", key, out),
+    }
+
+    let request = client
+        .client
+        .get_object()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .set_range(range.map(|range| format!("bytes={}", range)));
+    let response = with_customer_key!(request, client.encryption)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download object: {}", key))?;
+    let recorded_sha256 = response
+        .metadata()
+        .and_then(|metadata| metadata.get(SHA256_METADATA_KEY))
+        .map(|sha256| sha256.to_lowercase());
+    let content_range = response.content_range().map(str::to_string);
+
+    if let Some(parent) = out.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create directory: {:?}", parent))?;
+    }
+    let mut partial_name = out.file_name().unwrap_or_default().to_os_string();
+    partial_name.push(".part");
+    let partial = out.with_file_name(partial_name);
+
+    let result = write_body(response.body, &partial).await;
+    let (sha256, size) = match result {
+        Ok(written) => written,
+        Err(e) => {
+            tokio::fs::remove_file(&partial).await.ok();
+            return Err(e.context(format!("Failed to download object: {}", key)));
+        }
+    };
+
+    let verification = match recorded_sha256 {
+        _ if range.is_some() => Verification::Partial,
+        Some(recorded) if recorded != sha256 => {
+            tokio::fs::remove_file(&partial).await.ok();
+            anyhow::bail!("SHA-256 mismatch for {}: recorded {}, downloaded {}", key, recorded, sha256);
+        }
+        Some(_) => Verification::Verified,
+        None => Verification::Unrecorded,
+    };
+    tokio::fs::rename(&partial, out)
+        .await
+        .with_context(|| format!("Failed to move download into place: {:?}", out))?;
+
+    status!("Successfully downloaded: {}", key);
+    status!("   Saved to: {:?} ({} bytes)", out, size);
+    if let Some(content_range) = content_range {
+        status!("   Range: {}", content_range);
+    }
+    match verification {
+        Verification::Verified => status!("   ✅ SHA-256 verified: {}", sha256),
+        Verification::Unrecorded => status!("   ⚠️  No SHA-256 recorded for this object, saved unverified: {}", sha256),
+        Verification::Partial => status!("   ⚠️  Partial download, not checked against the object's SHA-256: {}", sha256),
+    }
+    Ok(Downloaded {
+        size,
+        sha256,
+        verification,
+    })
+}
+
+/// Writes the object body to `path`, returning its SHA-256 and length.
+async fn write_body(mut body: aws_sdk_s3::primitives::ByteStream, path: &Path) -> Result<(String, u64)> {
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .with_context(|| format!("Failed to create file: {:?}", path))?;
+    let mut hasher = Sha256::new();
+    let mut bytes = 0;
+    while let Some(chunk) = body.try_next().await.context("Failed to read object body")? {
+        hasher.update(&chunk);
+        file.write_all(&chunk)
+            .await
+            .with_context(|| format!("Failed to write file: {:?}", path))?;
+        bytes += chunk.len() as u64;
+    }
+    file.sync_all()
+        .await
+        .with_context(|| format!("Failed to write file: {:?}", path))?;
+    Ok((hex::encode(hasher.finalize()), bytes))
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use indicatif::HumanBytes;
+use serde::Serialize;
+
+use crate::S3Store;
+use crate::output::{OutputFormat, Record, print_records, status};
+use crate::store::ObjectStore;
+
+/// Group name for keys with no delimiter after the prefix
+const UNGROUPED: &str = "(no prefix)";
+
+#[derive(clap::Args)]
+pub struct DuArgs {
+    /// Only count keys starting with this prefix
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// Group keys by what comes before this separator, after --prefix; use _
+    /// for keys uploaded with --name-prefix
+    #[arg(long, default_value = "/")]
+    delimiter: String,
+
+    /// How to print the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+}
+
+/// Usage under one key prefix
+#[derive(Serialize)]
+struct PrefixUsage {
+    prefix: String,
+    objects: u64,
+    bytes: u64,
+}
+
+impl Record for PrefixUsage {
+    const COLUMNS: &'static [&'static str] = &["prefix", "objects", "bytes"];
+}
+
+/// Lists every object under the prefix and reports how many objects and
+/// bytes each group of keys holds, largest first, and the total.
+pub async fn run(client: &S3Store, args: &DuArgs) -> Result<()> {
+    let prefix = args.prefix.as_deref().unwrap_or_default();
+    match prefix {
+        "" => status!("📊 Measuring bucket '{}'...", client.bucket_name),
+        prefix => status!("📊 Measuring bucket '{}' under '{}'...", client.bucket_name, prefix),
+    }
+
+    let mut groups: HashMap<String, PrefixUsage> = HashMap::new();
+    for object in client.list(prefix).await? {
+        let key = &object.key;
+        let group = match key[prefix.len()..].find(&args.delimiter) {
+            Some(end) => &key[..prefix.len() + end + args.delimiter.len()],
+            None => UNGROUPED,
+        };
+        let usage = groups.entry(group.to_string()).or_insert_with(|| PrefixUsage {
+            prefix: group.to_string(),
+            objects: 0,
+            bytes: 0,
+        });
+        usage.objects += 1;
+        usage.bytes += object.size;
+    }
+
+    let mut usage: Vec<PrefixUsage> = groups.into_values().collect();
+    usage.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.prefix.cmp(&b.prefix)));
+    let objects: u64 = usage.iter().map(|usage| usage.objects).sum();
+    let bytes: u64 = usage.iter().map(|usage| usage.bytes).sum();
+
+    if args.output == OutputFormat::Table {
+        for usage in &usage {
+            println!(
+                "  {:>10}  {:>8} objects  {}",
+                HumanBytes(usage.bytes).to_string(),
+                usage.objects,
+                usage.prefix
+            );
+        }
+    }
+    status!("Total: {} objects, {} ({} bytes)", objects, HumanBytes(bytes), bytes);
+    print_records(args.output, &usage)
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::types::ServerSideEncryption;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use clap::ValueEnum;
+use md5::{Digest, Md5};
+
+/// The only algorithm S3 accepts for SSE-C
+pub const SSE_C_ALGORITHM: &str = "AES256";
+
+/// SSE-C keys are 256-bit
+const SSE_C_KEY_LEN: usize = 32;
+
+#[derive(clap::Args)]
+pub struct EncryptionArgs {
+    /// Server-side encryption to request for uploaded objects
+    #[arg(long, global = true, value_enum, default_value_t = SseMode::None)]
+    sse: SseMode,
+
+    /// File holding the 256-bit key for --sse c, as 32 raw bytes or base64
+    /// text; also needed to download, sync or find objects uploaded with it
+    #[arg(long, global = true)]
+    sse_c_key_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SseMode {
+    /// Stored as the bucket's default encryption leaves it
+    None,
+    /// Encrypted at rest with keys the storage service manages (SSE-S3)
+    S3,
+    /// Encrypted with our own key, which the service doesn't keep (SSE-C)
+    C,
+}
+
+/// How uploaded objects are encrypted at rest
+#[derive(Clone)]
+pub enum Encryption {
+    None,
+    ServiceManaged,
+    CustomerKey(CustomerKey),
+}
+
+/// An SSE-C key, encoded as the request headers carry it
+#[derive(Clone)]
+pub struct CustomerKey {
+    pub key: String,
+    pub key_md5: String,
+}
+
+impl Encryption {
+    /// A customer key, if given, is used for every request, since objects
+    /// uploaded with it can't be read back without it.
+    pub fn from_args(args: &EncryptionArgs) -> Result<Self> {
+        match (args.sse, &args.sse_c_key_file) {
+            (SseMode::S3, Some(_)) => anyhow::bail!("--sse-c-key-file can't be combined with --sse s3"),
+            (SseMode::S3, None) => Ok(Self::ServiceManaged),
+            (SseMode::C, None) => anyhow::bail!("--sse c requires --sse-c-key-file"),
+            (_, Some(key_file)) => Ok(Self::CustomerKey(CustomerKey::load(key_file)?)),
+            (SseMode::None, None) => Ok(Self::None),
+        }
+    }
+
+    /// The SSE-S3 header for uploads
+    pub fn server_side_encryption(&self) -> Option<ServerSideEncryption> {
+        match self {
+            Self::ServiceManaged => Some(ServerSideEncryption::Aes256),
+            _ => None,
+        }
+    }
+
+    /// The key every request for an SSE-C object must carry
+    pub fn customer_key(&self) -> Option<&CustomerKey> {
+        match self {
+            Self::CustomerKey(key) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::ServiceManaged => write!(f, "SSE-S3"),
+            Self::CustomerKey(_) => write!(f, "SSE-C"),
+        }
+    }
+}
+
+impl CustomerKey {
+    fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read(path).with_context(|| format!("Failed to read SSE-C key file: {:?}", path))?;
+        let key = match contents.len() {
+            SSE_C_KEY_LEN => contents,
+            _ => BASE64
+                .decode(String::from_utf8_lossy(&contents).trim())
+                .with_context(|| format!("SSE-C key file is neither 32 raw bytes nor base64: {:?}", path))?,
+        };
+        if key.len() != SSE_C_KEY_LEN {
+            anyhow::bail!("SSE-C key must be {} bytes, got {}: {:?}", SSE_C_KEY_LEN, key.len(), path);
+        }
+        Ok(Self {
+            key: BASE64.encode(&key),
+            key_md5: BASE64.encode(Md5::digest(&key)),
+        })
+    }
+}
+
+/// Adds the SSE-C headers to a request builder when a customer key is set.
+/// Every request that writes or reads an SSE-C object's data, including
+/// HEAD and each multipart part, must carry them.
+macro_rules! with_customer_key {
+    ($builder:expr, $encryption:expr) => {{
+        let key = $encryption.customer_key();
+        $builder
+            .set_sse_customer_algorithm(key.map(|_| $crate::encryption::SSE_C_ALGORITHM.to_string()))
+            .set_sse_customer_key(key.map(|key| key.key.clone()))
+            .set_sse_customer_key_md5(key.map(|key| key.key_md5.clone()))
+    }};
+}
+
+pub(crate) use with_customer_key;
+
+/// Adds the headers for reading an SSE-C copy source to a copy request when
+/// a customer key is set. The copy is written with the same key, which
+/// `with_customer_key!` adds separately.
+macro_rules! with_copy_source_customer_key {
+    ($builder:expr, $encryption:expr) => {{
+        let key = $encryption.customer_key();
+        $builder
+            .set_copy_source_sse_customer_algorithm(key.map(|_| $crate::encryption::SSE_C_ALGORITHM.to_string()))
+            .set_copy_source_sse_customer_key(key.map(|key| key.key.clone()))
+            .set_copy_source_sse_customer_key_md5(key.map(|key| key.key_md5.clone()))
+    }};
+}
+
+pub(crate) use with_copy_source_customer_key;
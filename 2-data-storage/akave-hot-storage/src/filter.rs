@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Decides which files under the input directory are uploaded, from
+/// --include/--exclude globs.
+pub struct FileFilter {
+    /// None when no --include patterns were given, so everything is included
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl FileFilter {
+    /// Globs are matched against paths relative to the input directory, and
+    /// `*` also matches `/`, so `*.part` selects chunk files in any subdirectory.
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let include = match include.is_empty() {
+            true => None,
+            false => Some(glob_set(include)?),
+        };
+        Ok(Self {
+            include,
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    /// Whether the file at `path` (under `input_dir`) should be uploaded.
+    pub fn is_selected(&self, input_dir: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(input_dir).unwrap_or(path);
+        if self.include.as_ref().is_some_and(|include| !include.is_match(relative)) {
+            return false;
+        }
+        !self.exclude.is_match(relative)
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))?);
+    }
+    builder.build().with_context(|| "Failed to compile glob patterns")
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use futures_util::{StreamExt, TryStreamExt, stream};
+
+use crate::S3Store;
+use crate::shard::{CHUNK_COUNT_METADATA_KEY, CHUNK_INDEX_METADATA_KEY, SHARD_CID_METADATA_KEY};
+use crate::store::ObjectStore;
+
+/// Objects whose metadata is fetched at the same time while searching
+const LOOKUP_CONCURRENCY: usize = 16;
+
+#[derive(clap::Args)]
+pub struct FindArgs {
+    /// Global CID of the sharded asset, as recorded in its shard metadata
+    #[arg(long)]
+    cid: String,
+
+    /// Only search keys starting with this prefix; much faster in a large bucket
+    #[arg(long)]
+    prefix: Option<String>,
+}
+
+/// An object recorded as part of the asset
+struct Found {
+    key: String,
+    /// None for the shard metadata file
+    chunk_index: Option<usize>,
+    chunk_count: Option<usize>,
+}
+
+/// Lists every object uploaded as part of the sharded asset with the given
+/// CID. S3 can't search by metadata, so each object's metadata is fetched.
+pub async fn run(client: &S3Store, args: &FindArgs) -> Result<()> {
+    let prefix = args.prefix.as_deref().unwrap_or_default();
+    let keys = client.list_keys(prefix).await?;
+    println!("🔎 Searching {} objects for CID {}...", keys.len(), args.cid);
+
+    let mut found: Vec<Found> = stream::iter(keys)
+        .map(|key| lookup(client, key, &args.cid))
+        .buffer_unordered(LOOKUP_CONCURRENCY)
+        .try_filter_map(|found| async move { Ok(found) })
+        .try_collect()
+        .await?;
+    if found.is_empty() {
+        println!("No objects found for CID: {}", args.cid);
+        return Ok(());
+    }
+    // Shard metadata first, then chunks in order
+    found.sort_by_key(|found| (found.chunk_index, found.key.clone()));
+
+    println!("\n📦 Objects for CID {}:", args.cid);
+    for found in &found {
+        match found.chunk_index {
+            Some(index) => println!("  chunk {}: {}", index, found.key),
+            None => println!("  metadata: {}", found.key),
+        }
+    }
+
+    let chunk_count = found.iter().find_map(|found| found.chunk_count);
+    if let Some(chunk_count) = chunk_count {
+        let missing: Vec<String> = (0..chunk_count)
+            .filter(|index| !found.iter().any(|found| found.chunk_index == Some(*index)))
+            .map(|index| index.to_string())
+            .collect();
+        if missing.is_empty() {
+            println!("\n✅ All {} chunks found", chunk_count);
+        } else {
+            println!("\n⚠️  Missing {} of {} chunks: {}", missing.len(), chunk_count, missing.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// The object under `key`, if its metadata records it as part of `cid`.
+async fn lookup(client: &S3Store, key: String, cid: &str) -> Result<Option<Found>> {
+    // Deleted since the listing
+    let Some(head) = client.head(&key).await? else {
+        return Ok(None);
+    };
+    let metadata = &head.metadata;
+    if metadata.get(SHARD_CID_METADATA_KEY).map(String::as_str) != Some(cid) {
+        return Ok(None);
+    }
+    let number = |name: &str| metadata.get(name).and_then(|value| value.parse().ok());
+    Ok(Some(Found {
+        chunk_index: number(CHUNK_INDEX_METADATA_KEY),
+        chunk_count: number(CHUNK_COUNT_METADATA_KEY),
+        key,
+    }))
+}
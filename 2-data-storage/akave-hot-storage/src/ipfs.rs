@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use aws_sdk_s3::primitives::ByteStream;
+use clap::ValueEnum;
+use futures_util::stream;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::profile::optional_env;
+
+const PINATA_API_URL: &str = "https://api.pinata.cloud";
+const DEFAULT_KUBO_API_URL: &str = "http://127.0.0.1:5001";
+
+/// IPFS pinning services objects can be mirrored to, configured from the
+/// same environment variables as the ipfs-pinata uploader
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PinningBackend {
+    /// Pinata, with PINATA_JWT or PINATA_API_KEY and PINATA_API_SECRET
+    Pinata,
+    /// A Kubo node's RPC API at KUBO_API_URL, with optional KUBO_API_AUTH
+    Kubo,
+}
+
+enum Target {
+    Pinata(PinataAuth),
+    Kubo { api_url: String, authorization: Option<String> },
+}
+
+enum PinataAuth {
+    /// Scoped JWT, sent as an `Authorization: Bearer` header
+    Jwt(String),
+    /// Legacy key/secret pair
+    ApiKey { api_key: String, secret: String },
+}
+
+/// Adds content to IPFS through a pinning service and keeps it pinned
+pub struct Pinner {
+    client: reqwest::Client,
+    target: Target,
+}
+
+#[derive(Deserialize)]
+struct PinataResponse {
+    #[serde(rename = "IpfsHash")]
+    ipfs_hash: String,
+}
+
+#[derive(Deserialize)]
+struct KuboAddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+impl Pinner {
+    /// Reads the backend's settings from the environment.
+    pub fn from_env(backend: PinningBackend) -> Result<Self> {
+        let target = match backend {
+            PinningBackend::Pinata => match optional_env("PINATA_JWT") {
+                Some(jwt) => Target::Pinata(PinataAuth::Jwt(jwt)),
+                None => Target::Pinata(PinataAuth::ApiKey {
+                    api_key: optional_env("PINATA_API_KEY")
+                        .context("Set PINATA_JWT, or PINATA_API_KEY and PINATA_API_SECRET, to mirror to Pinata")?,
+                    secret: optional_env("PINATA_API_SECRET").context("PINATA_API_SECRET is required with PINATA_API_KEY")?,
+                }),
+            },
+            PinningBackend::Kubo => Target::Kubo {
+                api_url: optional_env("KUBO_API_URL")
+                    .unwrap_or_else(|| DEFAULT_KUBO_API_URL.to_string())
+                    .trim_end_matches('/')
+                    .to_string(),
+                authorization: optional_env("KUBO_API_AUTH"),
+            },
+        };
+        Ok(Self {
+            client: reqwest::Client::new(),
+            target,
+        })
+    }
+
+    /// Streams `body`, `size` bytes long, to the service under `name` and
+    /// returns its CID. Content is added as CIDv1 with the default chunker,
+    /// so the CID matches the one recorded at upload.
+    pub async fn pin(&self, name: &str, body: ByteStream, size: u64, bucket: &str) -> Result<String> {
+        // Relays the object as it arrives instead of holding it in memory
+        let chunks = stream::try_unfold(body, |mut body| async move {
+            Ok::<_, aws_sdk_s3::primitives::ByteStreamError>(body.try_next().await?.map(|chunk| (chunk, body)))
+        });
+        let part = Part::stream_with_length(reqwest::Body::wrap_stream(chunks), size).file_name(name.to_string());
+
+        let response = match &self.target {
+            Target::Pinata(auth) => {
+                let metadata = json!({ "name": name, "keyvalues": { "bucket": bucket, "key": name } });
+                let form = Form::new()
+                    .part("file", part)
+                    .text("pinataMetadata", metadata.to_string())
+                    .text("pinataOptions", json!({ "cidVersion": 1 }).to_string());
+                let request = self.client.post(format!("{}/pinning/pinFileToIPFS", PINATA_API_URL));
+                let request = match auth {
+                    PinataAuth::Jwt(jwt) => request.bearer_auth(jwt),
+                    PinataAuth::ApiKey { api_key, secret } => request
+                        .header("pinata_api_key", api_key)
+                        .header("pinata_secret_api_key", secret),
+                };
+                request.multipart(form)
+            }
+            Target::Kubo { api_url, authorization } => {
+                let mut request = self
+                    .client
+                    .post(format!("{}/api/v0/add", api_url))
+                    .query(&[("cid-version", "1"), ("pin", "true"), ("quieter", "true")]);
+                if let Some(authorization) = authorization {
+                    request = request.header("Authorization", authorization);
+                }
+                request.multipart(Form::new().part("file", part))
+            }
+        }
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Failed to pin: {}", name))?;
+
+        Ok(match self.target {
+            Target::Pinata(_) => {
+                let pinned: PinataResponse = response.json().await.context("Failed to parse Pinata response")?;
+                pinned.ipfs_hash
+            }
+            Target::Kubo { .. } => {
+                let added: KuboAddResponse = response.json().await.context("Failed to parse Kubo response")?;
+                added.hash
+            }
+        })
+    }
+}
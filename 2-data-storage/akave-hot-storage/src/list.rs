@@ -0,0 +1,272 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::S3Store;
+use crate::output::{OutputFormat, Record, print_records, status};
+
+/// Objects listed unless --max or --all says otherwise
+pub const DEFAULT_LIST_MAX: usize = 100;
+
+#[derive(clap::Args)]
+pub struct ListArgs {
+    /// Only list keys starting with this prefix, e.g. batch-2024-06/
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// Group keys that contain this separator after the prefix into one
+    /// entry per folder, e.g. / to browse one level at a time
+    #[arg(long)]
+    delimiter: Option<String>,
+
+    /// Stop after this many entries
+    #[arg(long, default_value_t = DEFAULT_LIST_MAX, conflicts_with = "all")]
+    max: usize,
+
+    /// List every entry
+    #[arg(long)]
+    all: bool,
+
+    /// List every version of each object, including delete markers, in a
+    /// bucket with versioning enabled
+    #[arg(long, conflicts_with = "delimiter")]
+    versions: bool,
+
+    /// How to print the listing
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+}
+
+/// One listed object or folder
+#[derive(Serialize)]
+struct ListedEntry {
+    key: String,
+    /// "object", or "folder" for a prefix grouped by --delimiter
+    kind: &'static str,
+    size: Option<i64>,
+    last_modified: Option<String>,
+    etag: Option<String>,
+}
+
+impl Record for ListedEntry {
+    const COLUMNS: &'static [&'static str] = &["key", "kind", "size", "last_modified", "etag"];
+}
+
+/// One version of an object, or a delete marker
+#[derive(Serialize)]
+struct VersionEntry {
+    key: String,
+    version_id: String,
+    is_latest: bool,
+    /// Whether this version records the object's deletion rather than content
+    delete_marker: bool,
+    size: Option<i64>,
+    last_modified: Option<String>,
+    etag: Option<String>,
+}
+
+impl Record for VersionEntry {
+    const COLUMNS: &'static [&'static str] =
+        &["key", "version_id", "is_latest", "delete_marker", "size", "last_modified", "etag"];
+}
+
+pub async fn run(client: &S3Store, args: &ListArgs) -> Result<()> {
+    let limit = (!args.all).then_some(args.max);
+    if args.versions {
+        return list_versions(client, args.prefix.as_deref(), limit, args.output).await;
+    }
+    list_objects(client, args.prefix.as_deref(), args.delimiter.as_deref(), limit, args.output).await
+}
+
+/// Lists objects page by page and stops after `limit` entries if given.
+/// With a delimiter, keys sharing a folder under the prefix are shown once
+/// as that folder. The table format prints each page as it arrives; JSON and
+/// CSV print every entry at the end.
+pub async fn list_objects(
+    client: &S3Store,
+    prefix: Option<&str>,
+    delimiter: Option<&str>,
+    limit: Option<usize>,
+    format: OutputFormat,
+) -> Result<()> {
+    match prefix {
+        Some(prefix) => status!("\n🗂️  Listing objects in bucket '{}' under '{}':", client.bucket_name, prefix),
+        None => status!("\n🗂️  Listing objects in bucket '{}':", client.bucket_name),
+    }
+
+    // No point fetching pages larger than we'll print; S3 caps them at 1000
+    let page_size = limit.map(|limit| limit.clamp(1, 1000) as i32);
+    let mut pages = client
+        .client
+        .list_objects_v2()
+        .bucket(&client.bucket_name)
+        .set_prefix(prefix.map(str::to_string))
+        .set_delimiter(delimiter.map(str::to_string))
+        .set_max_keys(page_size)
+        .into_paginator()
+        .send();
+
+    let mut listed = 0;
+    let mut folders = 0;
+    let mut truncated = false;
+    let mut records = Vec::new();
+    'pages: while let Some(page) = pages.next().await {
+        let page = page.with_context(|| "Failed to list objects")?;
+        for folder in page.common_prefixes().iter().filter_map(|folder| folder.prefix()) {
+            if limit.is_some_and(|limit| listed >= limit) {
+                truncated = true;
+                break 'pages;
+            }
+            listed += 1;
+            folders += 1;
+            match format {
+                OutputFormat::Table => status!("  [{}] 📁 {}", listed, folder),
+                _ => records.push(ListedEntry {
+                    key: folder.to_string(),
+                    kind: "folder",
+                    size: None,
+                    last_modified: None,
+                    etag: None,
+                }),
+            }
+        }
+        for object in page.contents() {
+            if limit.is_some_and(|limit| listed >= limit) {
+                truncated = true;
+                break 'pages;
+            }
+            listed += 1;
+            match (format, object.key(), object.size(), object.last_modified()) {
+                (OutputFormat::Table, Some(key), Some(size), Some(modified)) => {
+                    status!("  [{}] {} ({} bytes, modified: {})", listed, key, size, modified)
+                }
+                (OutputFormat::Table, ..) => {}
+                (_, key, size, modified) => records.push(ListedEntry {
+                    key: key.unwrap_or_default().to_string(),
+                    kind: "object",
+                    size,
+                    last_modified: modified.map(|modified| modified.to_string()),
+                    etag: object.e_tag().map(|etag| etag.trim_matches('"').to_string()),
+                }),
+            }
+        }
+    }
+
+    if listed == 0 {
+        status!("  No objects found in bucket.");
+    } else if truncated {
+        status!("Listed the first {} entries; there are more (use --all to list everything)", listed);
+    } else if folders > 0 {
+        status!("Found {} objects and {} folders", listed - folders, folders);
+    } else {
+        status!("Found {} objects", listed);
+    }
+
+    print_records(format, &records)
+}
+
+/// Lists every version of the objects under the prefix, newest first for
+/// each key, and stops after `limit` entries if given. Like `list_objects`,
+/// the table format prints each page as it arrives.
+async fn list_versions(client: &S3Store, prefix: Option<&str>, limit: Option<usize>, format: OutputFormat) -> Result<()> {
+    match prefix {
+        Some(prefix) => status!("\n🗂️  Listing object versions in bucket '{}' under '{}':", client.bucket_name, prefix),
+        None => status!("\n🗂️  Listing object versions in bucket '{}':", client.bucket_name),
+    }
+
+    let page_size = limit.map(|limit| limit.clamp(1, 1000) as i32);
+    let mut key_marker = None;
+    let mut version_id_marker = None;
+    let mut listed = 0;
+    let mut truncated = false;
+    let mut records = Vec::new();
+    'pages: loop {
+        let page = client
+            .client
+            .list_object_versions()
+            .bucket(&client.bucket_name)
+            .set_prefix(prefix.map(str::to_string))
+            .set_max_keys(page_size)
+            .set_key_marker(key_marker.take())
+            .set_version_id_marker(version_id_marker.take())
+            .send()
+            .await
+            .with_context(|| "Failed to list object versions")?;
+
+        // Versions and delete markers come back separately; put them back
+        // in order, newest first within each key
+        let mut entries: Vec<VersionEntry> = page
+            .versions()
+            .iter()
+            .map(|version| VersionEntry {
+                key: version.key().unwrap_or_default().to_string(),
+                version_id: version.version_id().unwrap_or("null").to_string(),
+                is_latest: version.is_latest().unwrap_or_default(),
+                delete_marker: false,
+                size: version.size(),
+                last_modified: version.last_modified().map(|modified| modified.to_string()),
+                etag: version.e_tag().map(|etag| etag.trim_matches('"').to_string()),
+            })
+            .chain(page.delete_markers().iter().map(|marker| VersionEntry {
+                key: marker.key().unwrap_or_default().to_string(),
+                version_id: marker.version_id().unwrap_or("null").to_string(),
+                is_latest: marker.is_latest().unwrap_or_default(),
+                delete_marker: true,
+                size: None,
+                last_modified: marker.last_modified().map(|modified| modified.to_string()),
+                etag: None,
+            }))
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then_with(|| b.last_modified.cmp(&a.last_modified)));
+
+        for entry in entries {
+            if limit.is_some_and(|limit| listed >= limit) {
+                truncated = true;
+                break 'pages;
+            }
+            listed += 1;
+            match format {
+                OutputFormat::Table => {
+                    let state = match (entry.delete_marker, entry.is_latest) {
+                        (true, true) => " 🗑️  deleted (latest)",
+                        (true, false) => " 🗑️  deleted",
+                        (false, true) => " (latest)",
+                        (false, false) => "",
+                    };
+                    let details = match entry.size {
+                        Some(size) => format!("{} bytes, ", size),
+                        None => String::new(),
+                    };
+                    status!(
+                        "  [{}] {} version {}{} ({}modified: {})",
+                        listed,
+                        entry.key,
+                        entry.version_id,
+                        state,
+                        details,
+                        entry.last_modified.as_deref().unwrap_or("unknown")
+                    );
+                }
+                _ => records.push(entry),
+            }
+        }
+
+        if !page.is_truncated().unwrap_or_default() {
+            break;
+        }
+        key_marker = page.next_key_marker().map(str::to_string);
+        version_id_marker = page.next_version_id_marker().map(str::to_string);
+        if key_marker.is_none() {
+            break;
+        }
+    }
+
+    if listed == 0 {
+        status!("  No object versions found in bucket.");
+    } else if truncated {
+        status!("Listed the first {} versions; there are more (use --all to list everything)", listed);
+    } else {
+        status!("Found {} versions", listed);
+    }
+
+    print_records(format, &records)
+}
@@ -1,138 +1,428 @@
+use aws_config::Region;
 use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_s3::{Client, primitives::ByteStream};
+use aws_sdk_s3::{Client, config::Credentials, primitives::ByteStream, types::StorageClass};
 use anyhow::{Context, Result};
-use clap::Parser;
-use dotenv::dotenv;
-use std::path::PathBuf;
-use std::env;
-use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use clap::{Parser, Subcommand};
+use futures_util::StreamExt;
+use futures_util::stream;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+mod audit;
+mod bucket;
+mod checksum;
+mod cid;
+mod content_type;
+mod copy;
+mod delete;
+mod download;
+mod du;
+mod encryption;
+mod filter;
+mod find;
+mod ipfs;
+mod list;
+mod manifest;
+mod mirror;
+mod multipart;
+mod network;
+mod output;
+mod presign;
+mod profile;
+mod progress;
+mod resolve;
+mod restore;
+mod resume;
+mod retry;
+mod shard;
+mod store;
+mod sync;
+mod throttle;
+mod tier;
+mod upload;
+mod verify;
+mod watch;
+
+use audit::AuditArgs;
+use bucket::BucketArgs;
+use checksum::{SHA256_METADATA_KEY, file_sha256, hash_file};
+use cid::{CID_METADATA_KEY, file_cid};
+use copy::CopyArgs;
+use delete::DeleteArgs;
+use download::DownloadArgs;
+use du::DuArgs;
+use encryption::{Encryption, EncryptionArgs};
+use filter::FileFilter;
+use find::FindArgs;
+use list::{DEFAULT_LIST_MAX, ListArgs};
+use manifest::ManifestEntry;
+use mirror::MirrorArgs;
+use multipart::{DEFAULT_PART_SIZE_MB, MAX_PART_SIZE_MB, MIN_PART_SIZE_MB, upload_multipart};
+use network::{NetworkArgs, NetworkPolicy};
+use output::{OutputFormat, status};
+use presign::PresignArgs;
+use profile::{Connection, ProfileArgs};
+use progress::{BatchProgress, FileProgress};
+use resolve::ResolveArgs;
+use restore::RestoreArgs;
+use resume::ResumeState;
+use retry::{RetryArgs, RetryPolicy};
+use shard::{ShardRef, index_shard_files};
+use store::ObjectStore;
+use sync::SyncArgs;
+use throttle::Rate;
+use tier::TierArgs;
+use upload::UploadKeyArgs;
+use verify::{VerificationError, is_already_present, verify_upload};
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
-struct Args {
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    upload: UploadArgs,
+
+    #[command(flatten)]
+    retry: RetryArgs,
+
+    #[command(flatten)]
+    network: NetworkArgs,
+
+    #[command(flatten)]
+    encryption: EncryptionArgs,
+
+    #[command(flatten)]
+    profile: ProfileArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Download objects to disk, verifying each against the SHA-256 recorded at upload
+    Download(DownloadArgs),
+    /// Delete an object, or every object under a prefix, from the bucket
+    Delete(DeleteArgs),
+    /// Upload only the files that are new or changed since they were last uploaded
+    Sync(SyncArgs),
+    /// Create the bucket or show details about it
+    Bucket(BucketArgs),
+    /// List objects in the bucket, optionally under a prefix
+    List(ListArgs),
+    /// Print a time-limited URL for fetching or uploading one object without credentials
+    Presign(PresignArgs),
+    /// Find every object belonging to a sharded asset by its CID
+    Find(FindArgs),
+    /// List objects to move to cold storage, send them to a migration endpoint and mark them
+    Tier(TierArgs),
+    /// Make an earlier version of an object the current one again
+    Restore(RestoreArgs),
+    /// Copy an object to another key or bucket without downloading it
+    Copy(CopyArgs),
+    /// List every object whose content has an IPFS CID
+    Resolve(ResolveArgs),
+    /// Report how many objects and bytes the bucket holds, by key prefix
+    Du(DuArgs),
+    /// Check every chunk in a shard metadata file is in the bucket with the right size and SHA-256
+    Audit(AuditArgs),
+    /// Pin objects to IPFS straight from the bucket, recording the CID of each key
+    Mirror(MirrorArgs),
+    /// Upload stdin to a named key as it is read, e.g. `upload --key KEY -`
+    Upload(UploadKeyArgs),
+}
+
+impl Command {
+    /// The records format asked for, for subcommands that support --output
+    fn output(&self) -> OutputFormat {
+        match self {
+            Command::Download(args) => args.output,
+            Command::Sync(args) => args.output,
+            Command::List(args) => args.output,
+            Command::Du(args) => args.output,
+            Command::Audit(args) => args.output,
+            _ => OutputFormat::Table,
+        }
+    }
+}
+
+// Arguments for the default upload mode
+#[derive(clap::Args)]
+struct UploadArgs {
     /// Input directory containing data shards and metadata to upload
-    #[arg(short, long)]
-    input_dir: PathBuf,
+    #[arg(short, long, required = true)]
+    input_dir: Option<PathBuf>,
 
     /// Optional: Custom name prefix for uploaded files
     #[arg(long)]
     name_prefix: Option<String>,
 
-    /// List files in bucket instead of uploading
+    /// Only upload files matching this glob, relative to the input directory (repeatable)
+    #[arg(long = "include", value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files matching this glob (repeatable)
+    #[arg(long = "exclude", value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Part size in MB for multipart uploads; larger files are uploaded in parts
+    #[arg(long, default_value_t = DEFAULT_PART_SIZE_MB, value_parser = clap::value_parser!(u64).range(MIN_PART_SIZE_MB..=MAX_PART_SIZE_MB))]
+    part_size_mb: u64,
+
+    /// Recognize files whose extension is missing or unknown by their
+    /// contents when choosing their Content-Type
+    #[arg(long)]
+    sniff_content_type: bool,
+
+    /// Maximum number of files to upload in parallel
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    concurrency: u64,
+
+    /// Write a JSON manifest of the uploaded files (path, key, ETag,
+    /// SHA-256, size, time) to this file
     #[arg(long)]
-    list: bool,
+    manifest: Option<PathBuf>,
+
+    /// Add to the entries already in --manifest instead of replacing them
+    #[arg(long, requires = "manifest")]
+    append_manifest: bool,
+
+    /// Record multipart uploads in this file as they progress, and resume
+    /// any that an earlier run left unfinished
+    #[arg(long)]
+    resume_state: Option<PathBuf>,
+
+    /// Send no more than this much per second across all uploads, e.g.
+    /// 20MB or 10MiB, to leave room for other traffic on a shared link
+    #[arg(long, value_name = "RATE")]
+    max_upload_rate: Option<Rate>,
+
+    /// Send no more than this much per second for each file
+    #[arg(long, value_name = "RATE")]
+    max_file_upload_rate: Option<Rate>,
+
+    /// Upload every file, even if an identical object is already under its key
+    #[arg(long)]
+    force: bool,
+
+    /// Storage class to upload objects in, e.g. STANDARD or
+    /// REDUCED_REDUNDANCY; which ones are available depends on the endpoint
+    #[arg(long, default_value = "STANDARD")]
+    storage_class: StorageClass,
+
+    /// After uploading what's there, keep watching the input directory and
+    /// upload files as they are created or changed, until Ctrl-C
+    #[arg(long)]
+    watch: bool,
+
+    /// With --watch, how long a file must go unchanged before it is uploaded
+    #[arg(long, default_value_t = 2000, value_name = "MS", requires = "watch")]
+    watch_debounce_ms: u64,
 }
 
-struct AkaveClient {
+impl UploadArgs {
+    /// The filter built from --include and --exclude.
+    fn file_filter(&self) -> Result<FileFilter> {
+        FileFilter::new(&self.include, &self.exclude)
+    }
+
+    /// The resume state file named by --resume-state, loaded.
+    fn load_resume_state(&self) -> Result<Option<ResumeState>> {
+        self.resume_state.as_deref().map(ResumeState::load).transpose()
+    }
+}
+
+struct S3Store {
     client: Client,
     bucket_name: String,
+    /// Files larger than this are sent as a multipart upload
+    part_size: u64,
+    /// Recognize files by their contents when the extension doesn't say
+    sniff_content_type: bool,
+    /// Leave files alone whose identical object is already in the bucket
+    skip_existing: bool,
+    storage_class: StorageClass,
+    retry: RetryPolicy,
+    /// Requested for every upload; an SSE-C key is also sent when reading objects
+    encryption: Encryption,
+    /// Where unfinished multipart uploads are recorded, if they can be resumed
+    resume: Option<ResumeState>,
 }
 
-impl AkaveClient {
-    async fn new() -> Result<Self> {
-        // Load environment variables from .env file
-        dotenv().ok();
-        
-        let akave_endpoint = env::var("AKAVE_HOSTNAME")
-            .unwrap_or_else(|_| "https://o3-rc1.akave.xyz".to_string());
-        let bucket_name = env::var("AKAVE_BUCKET")
-            .unwrap_or_else(|_| "starling-akave".to_string());
-        let _access_key = env::var("AKAVE_USERNAME")
-            .context("AKAVE_USERNAME environment variable is required")?;
-        let _secret_key = env::var("AKAVE_CREDENTIAL")
-            .context("AKAVE_CREDENTIAL environment variable is required")?;
-
-        // Configure AWS SDK for Akave
-        let region_provider = RegionProviderChain::default_provider()
+/// A file queued for upload
+struct Upload {
+    file_path: PathBuf,
+    object_key: String,
+    /// Set for blob_cid_shard chunks and their shard metadata
+    shard: Option<ShardRef>,
+}
+
+/// Stored with each uploaded object, whether it is sent in one PUT or in parts
+struct ObjectHeaders {
+    content_type: String,
+    metadata: HashMap<String, String>,
+    storage_class: StorageClass,
+    encryption: Encryption,
+}
+
+impl S3Store {
+    async fn new(connection: Connection, retry: RetryPolicy, network: NetworkPolicy, encryption: Encryption) -> Result<Self> {
+        let Connection { endpoint, bucket: bucket_name, username, credential, session_token, region, path_style } = connection;
+
+        // Sign with the connection's credentials rather than whatever AWS
+        // profile or instance role happens to be configured on the machine
+        let credentials = Credentials::new(username, credential, session_token, None, "starling-connection");
+
+        // Akave ignores the region, but AWS S3 and others sign with it
+        let region_provider = RegionProviderChain::first_try(region.map(Region::new))
+            .or_default_provider()
             .or_else("akave-network");
-        let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
             .region(region_provider)
-            .endpoint_url(&akave_endpoint)
-            .load()
-            .await;
+            .endpoint_url(&endpoint)
+            .credentials_provider(credentials)
+            .retry_config(retry.sdk_config())
+            .timeout_config(network.timeout_config());
+        if let Some(http_client) = network.http_client()? {
+            loader = loader.http_client(http_client);
+        }
+        let config = loader.load().await;
 
-        let client = Client::new(&config);
+        // MinIO and most self-hosted endpoints have no bucket subdomains
+        let config = aws_sdk_s3::config::Builder::from(&config).force_path_style(path_style).build();
+        let client = Client::from_conf(config);
 
-        println!("✅ Connected to Akave endpoint: {}", akave_endpoint);
-        println!("📦 Using bucket: {}", bucket_name);
+        status!("✅ Connected to endpoint: {}", endpoint);
+        status!("📦 Using bucket: {}", bucket_name);
 
         Ok(Self {
             client,
             bucket_name,
+            part_size: DEFAULT_PART_SIZE_MB * 1024 * 1024,
+            sniff_content_type: false,
+            skip_existing: false,
+            storage_class: StorageClass::Standard,
+            retry,
+            encryption,
+            resume: None,
         })
     }
 
-    async fn upload_file(&self, file_path: &PathBuf, custom_name: Option<String>) -> Result<String> {
-        let mut file = File::open(file_path)
-            .await
-            .with_context(|| format!("Failed to open file: {:?}", file_path))?;
-
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)
-            .await
-            .with_context(|| format!("Failed to read file: {:?}", file_path))?;
-
-        let object_key = custom_name.unwrap_or_else(|| {
-            file_path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string()
-        });
-
-        println!("Uploading file: {:?} -> {}", file_path, object_key);
-
-        let result = self.client
-            .put_object()
-            .bucket(&self.bucket_name)
-            .key(&object_key)
-            .body(ByteStream::from(buffer))
-            .content_type("application/octet-stream")
-            .send()
-            .await
-            .with_context(|| format!("Failed to upload file: {:?}", file_path))?;
-
-        let etag = result.e_tag().unwrap_or("unknown").to_string();
-        
-        println!("Successfully uploaded: {:?}", file_path);
-        println!("   Object key: {}", object_key);
-        println!("   ETag: {}", etag);
+    fn with_part_size(mut self, part_size: u64) -> Self {
+        self.part_size = part_size;
+        self
+    }
 
-        Ok(object_key)
+    fn with_content_sniffing(mut self, sniff_content_type: bool) -> Self {
+        self.sniff_content_type = sniff_content_type;
+        self
     }
 
-    async fn list_objects(&self) -> Result<()> {
-        println!("\n🗂️  Listing objects in bucket '{}':", self.bucket_name);
-        
-        let result = self.client
-            .list_objects_v2()
-            .bucket(&self.bucket_name)
-            .max_keys(100)
-            .send()
-            .await
-            .with_context(|| "Failed to list objects")?;
-
-        let objects = result.contents();
-        if !objects.is_empty() {
-            println!("Found {} objects:", objects.len());
-            for (index, object) in objects.iter().enumerate() {
-                if let (Some(key), Some(size), Some(modified)) = 
-                    (object.key(), object.size(), object.last_modified()) {
-                    println!("  [{}] {} ({} bytes, modified: {})", 
-                             index + 1, key, size, modified);
-                }
-            }
-        } else {
-            println!("  No objects found in bucket.");
+    fn with_skip_existing(mut self, skip_existing: bool) -> Self {
+        self.skip_existing = skip_existing;
+        self
+    }
+
+    fn with_storage_class(mut self, storage_class: StorageClass) -> Self {
+        self.storage_class = storage_class;
+        self
+    }
+
+    fn with_resume_state(mut self, resume: Option<ResumeState>) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Uploads one file and returns its manifest entry, or None if an
+    /// identical object was already under its key and it wasn't sent again.
+    async fn upload_file(&self, upload: &Upload, progress: &FileProgress) -> Result<Option<ManifestEntry>> {
+        let Upload { file_path, object_key, shard } = upload;
+        let file_size = std::fs::metadata(file_path)
+            .with_context(|| format!("Failed to open file: {:?}", file_path))?
+            .len();
+
+        // Recorded with the object so downloads can be verified against it
+        let sha256 = hash_file(file_path, file_sha256).await?;
+        // Saves sending everything again when re-running after a partial failure
+        if self.skip_existing && is_already_present(self, object_key, file_size, &sha256).await? {
+            status!("⏭️  Already present: {} (same size and SHA-256)", object_key);
+            return Ok(None);
         }
+        status!("Uploading file: {:?} -> {}", file_path, object_key);
+
+        // Lets the object be found by the CID the catalog records for it
+        let cid = hash_file(file_path, file_cid).await?;
+        let mut metadata = HashMap::from([
+            (SHA256_METADATA_KEY.to_string(), sha256.clone()),
+            (CID_METADATA_KEY.to_string(), cid.clone()),
+        ]);
+        if let Some(shard) = shard {
+            metadata.extend(shard.metadata());
+        }
+        let headers = ObjectHeaders {
+            content_type: content_type::detect(file_path, self.sniff_content_type),
+            metadata,
+            storage_class: self.storage_class.clone(),
+            encryption: self.encryption.clone(),
+        };
+
+        let multipart = file_size > self.part_size;
+        let etag = if multipart {
+            upload_multipart(self, object_key, file_path, file_size, &headers, progress)
+                .await
+                .with_context(|| format!("Failed to upload file: {:?}", file_path))?
+        } else {
+            // Streamed from disk as it is sent, so memory use doesn't grow with the file
+            let body = ByteStream::from_path(file_path)
+                .await
+                .with_context(|| format!("Failed to read file: {:?}", file_path))?;
+
+            self.put(object_key, progress.track(body), file_size, &headers)
+                .await
+                .with_context(|| format!("Failed to upload file: {:?}", file_path))?
+        };
+        
+        // A 200 response alone has let truncated uploads through before
+        verify_upload(self, file_path, object_key, file_size, &sha256, multipart).await?;
+
+        // One print so concurrent uploads don't interleave their lines
+        status!(
+            "Successfully uploaded: {:?}\n   Object key: {}\n   Content-Type: {}\n   Storage class: {}\n   Encryption: {}\n   ETag: {}\n   SHA-256: {}\n   CID: {}\n   ✅ Verified size and checksum",
+            file_path, object_key, headers.content_type, headers.storage_class, headers.encryption, etag, sha256, cid
+        );
+
+        Ok(Some(ManifestEntry {
+            local_path: file_path.clone(),
+            bucket: self.bucket_name.clone(),
+            object_key: object_key.clone(),
+            etag: etag.trim_matches('"').to_string(),
+            sha256,
+            size: file_size,
+            content_type: headers.content_type,
+            storage_class: headers.storage_class.to_string(),
+            encryption: headers.encryption.to_string(),
+            uploaded_at: chrono::Utc::now(),
+        }))
+    }
+
+    /// Every key in the bucket starting with `prefix`, following continuation tokens.
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self.list(prefix).await?.into_iter().map(|object| object.key).collect())
+    }
+}
 
-        Ok(())
+/// The key a file is uploaded under: its file name, after `<prefix>_` if a
+/// name prefix is given.
+fn object_key(file_path: &Path, name_prefix: Option<&str>) -> String {
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+    match name_prefix {
+        Some(prefix) => format!("{}_{}", prefix, file_name),
+        None => file_name.to_string(),
     }
 }
 
-async fn find_files(input_dir: &PathBuf) -> Result<Vec<PathBuf>> {
+async fn find_files(input_dir: &Path, filter: &FileFilter) -> Result<Vec<PathBuf>> {
     if !input_dir.exists() {
         anyhow::bail!("Input directory does not exist: {:?}", input_dir);
     }
@@ -142,91 +432,211 @@ async fn find_files(input_dir: &PathBuf) -> Result<Vec<PathBuf>> {
     }
 
     let mut files = Vec::new();
+    let mut filtered = 0;
     
     for entry in WalkDir::new(input_dir) {
         let entry = entry.with_context(|| "Failed to read directory entry")?;
         
-        if entry.file_type().is_file() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if filter.is_selected(input_dir, entry.path()) {
             files.push(entry.path().to_path_buf());
+        } else {
+            filtered += 1;
         }
     }
 
+    if filtered > 0 {
+        status!("🚫 Filtered out {} files (--include, --exclude)", filtered);
+    }
+
     if files.is_empty() {
-        println!("No files found in directory: {:?}", input_dir);
+        status!("No files found in directory: {:?}", input_dir);
     } else {
-        println!("📁 Found {} files to upload", files.len());
+        status!("📁 Found {} files to upload", files.len());
     }
 
     Ok(files)
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
+/// Pairs each file with the key it is uploaded under and, for sharded
+/// assets, where it sits in the asset.
+fn plan_uploads(files: Vec<PathBuf>, name_prefix: Option<&str>) -> Vec<Upload> {
+    let mut shards = index_shard_files(&files);
+    files
+        .into_iter()
+        .map(|file_path| Upload {
+            object_key: object_key(&file_path, name_prefix),
+            shard: shards.remove(&file_path),
+            file_path,
+        })
+        .collect()
+}
 
-    println!("🚀 Starting Akave Hot Storage uploader");
+/// Uploads each file under its key, at most `--concurrency` at a time, then
+/// prints a summary naming every file that failed, writes the manifest if
+/// one was asked for, and lists the bucket. Returns the files uploaded.
+async fn upload_batch(client: &S3Store, uploads: &[Upload], args: &UploadArgs) -> Result<Vec<ManifestEntry>> {
+    let concurrency = args.concurrency;
+    status!("\n📤 Starting uploads (concurrency: {})...", concurrency);
+    if let Some(rate) = args.max_upload_rate {
+        status!("   Limited to {} in total", rate);
+    }
+    if let Some(rate) = args.max_file_upload_rate {
+        status!("   Limited to {} per file", rate);
+    }
+    status!();
+
+    let total = uploads.len();
+    let sizes: Vec<u64> = uploads
+        .iter()
+        .map(|upload| std::fs::metadata(&upload.file_path).map(|metadata| metadata.len()).unwrap_or_default())
+        .collect();
+    let batch_progress = BatchProgress::new(total as u64, sizes.iter().sum())
+        .with_rate_limits(args.max_upload_rate, args.max_file_upload_rate);
+    let batch_progress = &batch_progress;
+    let sizes = &sizes;
+    let mut results: Vec<(usize, Result<Option<ManifestEntry>>)> = stream::iter(uploads.iter().enumerate())
+        .map(|(index, upload)| async move {
+            status!("[{}/{}] Processing file: {:?}", index + 1, total, upload.file_path);
+            let label = format!("{:?}", upload.file_path);
+            let progress = batch_progress.start_file(&upload.object_key, sizes[index]);
+            let result = client
+                .retry
+                .run(&label, || {
+                    // A retry sends the file from the start, or from the resumed parts
+                    progress.restart();
+                    client.upload_file(upload, &progress)
+                })
+                .await;
+            progress.finish();
+            if let Err(e) = &result {
+                status!("❌ Failed to upload {:?}: {}", upload.file_path, e);
+            }
+            status!(); // Add spacing between files
+            (index, result)
+        })
+        .buffer_unordered(concurrency as usize)
+        .collect()
+        .await;
+    batch_progress.finish();
+    // Report in the order the files were found, not the order they finished
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut uploaded = Vec::new();
+    let mut already_present = 0;
+    let mut failures: Vec<(&PathBuf, anyhow::Error)> = Vec::new();
+    for (index, result) in results {
+        match result {
+            Ok(Some(entry)) => uploaded.push(entry),
+            Ok(None) => already_present += 1,
+            Err(e) => failures.push((&uploads[index].file_path, e)),
+        }
+    }
+    let successful_uploads = uploaded.len();
+    let failed_uploads = failures.len();
+    let mismatched_uploads = failures
+        .iter()
+        .filter(|(_, e)| e.downcast_ref::<VerificationError>().is_some())
+        .count();
+
+    status!("Upload Summary:");
+    status!("   ✅ Successful: {}", successful_uploads);
+    if already_present > 0 {
+        status!("   ⏭️  Already present: {}", already_present);
+    }
+    status!("   ❌ Failed: {}", failed_uploads);
+    if mismatched_uploads > 0 {
+        status!("   🔍 Uploaded but failed verification: {}", mismatched_uploads);
+    }
+    status!("   📁 Total files: {}", total);
 
-    // Initialize Akave client
-    let client = AkaveClient::new().await
-        .with_context(|| "Failed to initialize Akave client")?;
+    if failed_uploads > 0 {
+        status!("\n⚠️  Some uploads failed:");
+        for (file_path, e) in &failures {
+            status!("   {:?}: {:#}", file_path, e);
+        }
+    } else {
+        status!("\n🎉 All files uploaded successfully!");
+    }
 
-    // If list flag is set, just list objects and exit
-    if args.list {
-        client.list_objects().await?;
-        return Ok(());
+    // Written even when some uploads failed, covering the ones that didn't
+    if let Some(manifest_path) = &args.manifest {
+        let count = manifest::write(manifest_path, &uploaded, args.append_manifest)?;
+        status!("\n📝 Manifest written: {:?} ({} entries)", manifest_path, count);
     }
 
-    println!("Input directory: {:?}", args.input_dir);
+    // List uploaded objects to verify
+    status!("\n📋 Verification - listing uploaded objects:");
+    list::list_objects(client, None, None, Some(DEFAULT_LIST_MAX), OutputFormat::Table).await?;
 
-    // Find all files in the input directory
-    let files = find_files(&args.input_dir).await?;
+    Ok(uploaded)
+}
 
-    if files.is_empty() {
-        println!("No files to upload. Exiting.");
-        return Ok(());
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let retry = RetryPolicy::from(&cli.retry);
+    let network = NetworkPolicy::try_from(&cli.network)?;
+    let encryption = Encryption::from_args(&cli.encryption)?;
+
+    if let Some(command) = &cli.command {
+        output::reserve_stdout(command.output());
+        let client = S3Store::new(cli.profile.connection()?, retry, network, encryption.clone()).await
+            .with_context(|| "Failed to initialize storage client")?;
+        return match command {
+            Command::Download(download_args) => download::run(&client, download_args).await,
+            Command::Delete(delete_args) => delete::run(&client, delete_args).await,
+            Command::Sync(sync_args) => sync::run(client, sync_args).await,
+            Command::Bucket(bucket_args) => bucket::run(&client, bucket_args).await,
+            Command::List(list_args) => list::run(&client, list_args).await,
+            Command::Presign(presign_args) => presign::run(&client, presign_args).await,
+            Command::Find(find_args) => find::run(&client, find_args).await,
+            Command::Tier(tier_args) => tier::run(&client, tier_args).await,
+            Command::Restore(restore_args) => restore::run(&client, restore_args).await,
+            Command::Copy(copy_args) => copy::run(&client, copy_args).await,
+            Command::Resolve(resolve_args) => resolve::run(&client, resolve_args).await,
+            Command::Du(du_args) => du::run(&client, du_args).await,
+            Command::Audit(audit_args) => audit::run(&client, audit_args).await,
+            Command::Mirror(mirror_args) => mirror::run(&client, mirror_args).await,
+            Command::Upload(upload_args) => upload::run(client, upload_args).await,
+        };
     }
 
-    println!("\n📤 Starting uploads...\n");
+    let mut args = cli.upload;
+    let input_dir = args.input_dir.as_ref().context("--input-dir is required")?;
 
-    let mut successful_uploads = 0;
-    let mut failed_uploads = 0;
+    status!("🚀 Starting Akave Hot Storage uploader");
 
-    // Upload each file
-    for (index, file_path) in files.iter().enumerate() {
-        let custom_name = args.name_prefix.as_ref().map(|prefix| {
-            format!("{}_{}", prefix, 
-                   file_path.file_name().unwrap_or_default().to_string_lossy())
-        });
+    // Initialize storage client
+    let client = S3Store::new(cli.profile.connection()?, retry, network, encryption).await
+        .with_context(|| "Failed to initialize storage client")?
+        .with_part_size(args.part_size_mb * 1024 * 1024)
+        .with_content_sniffing(args.sniff_content_type)
+        .with_storage_class(args.storage_class.clone())
+        .with_skip_existing(!args.force)
+        .with_resume_state(args.load_resume_state()?);
 
-        println!("[{}/{}] Processing file: {:?}", index + 1, files.len(), file_path);
+    status!("Input directory: {:?}", input_dir);
 
-        match client.upload_file(file_path, custom_name).await {
-            Ok(_) => {
-                successful_uploads += 1;
-            }
-            Err(e) => {
-                println!("❌ Failed to upload {:?}: {}", file_path, e);
-                failed_uploads += 1;
-            }
-        }
+    // Find all files in the input directory
+    let filter = args.file_filter()?;
+    let files = find_files(input_dir, &filter).await?;
 
-        println!(); // Add spacing between files
+    if files.is_empty() && !args.watch {
+        status!("No files to upload. Exiting.");
+        return Ok(());
     }
 
-    println!("Upload Summary:");
-    println!("   ✅ Successful: {}", successful_uploads);
-    println!("   ❌ Failed: {}", failed_uploads);
-    println!("   📁 Total files: {}", files.len());
-
-    if failed_uploads > 0 {
-        println!("\n⚠️  Some uploads failed. Check the logs above for details.");
-    } else {
-        println!("\n🎉 All files uploaded successfully!");
+    if !files.is_empty() {
+        let uploads = plan_uploads(files, args.name_prefix.as_deref());
+        upload_batch(&client, &uploads, &args).await?;
+        // Each later batch adds to the manifest this one wrote
+        args.append_manifest = true;
+    }
+    if args.watch {
+        watch::run(&client, &args, input_dir, &filter).await?;
     }
-
-    // List uploaded objects to verify
-    println!("\n📋 Verification - listing uploaded objects:");
-    client.list_objects().await?;
-
     Ok(())
 }
@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One uploaded file, as recorded in the manifest
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub local_path: PathBuf,
+    pub bucket: String,
+    pub object_key: String,
+    pub etag: String,
+    pub sha256: String,
+    pub size: u64,
+    pub content_type: String,
+    /// Entries written before storage classes were recorded were uploaded
+    /// as STANDARD
+    #[serde(default = "standard_storage_class")]
+    pub storage_class: String,
+    /// none, SSE-S3 or SSE-C
+    pub encryption: String,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// Writes the entries to `path` as a JSON array. With `append`, they are
+/// added after the entries already in the file, if it exists. The file is
+/// replaced in one rename, so an interrupted write can't truncate it.
+/// Returns how many entries the manifest holds.
+pub fn write(path: &Path, entries: &[ManifestEntry], append: bool) -> Result<usize> {
+    let mut manifest: Vec<ManifestEntry> = Vec::new();
+    if append && path.exists() {
+        let existing = std::fs::read_to_string(path).with_context(|| format!("Failed to read manifest: {:?}", path))?;
+        manifest = serde_json::from_str(&existing)
+            .with_context(|| format!("Existing manifest is not an upload manifest: {:?}", path))?;
+    }
+    manifest.extend(entries.iter().cloned());
+
+    let json = serde_json::to_string_pretty(&manifest).context("Failed to serialize manifest")?;
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    std::fs::write(&temp_path, json + "\n").with_context(|| format!("Failed to write manifest: {:?}", temp_path))?;
+    std::fs::rename(&temp_path, path).with_context(|| format!("Failed to write manifest: {:?}", path))?;
+    Ok(manifest.len())
+}
+
+fn standard_storage_class() -> String {
+    "STANDARD".to_string()
+}
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::S3Store;
+use crate::cid::CID_METADATA_KEY;
+use crate::ipfs::{Pinner, PinningBackend};
+use crate::store::ObjectStore;
+
+#[derive(clap::Args)]
+pub struct MirrorArgs {
+    /// Mirror every object whose key starts with this prefix
+    #[arg(long)]
+    prefix: String,
+
+    /// Pinning service to add the objects to
+    #[arg(long, value_enum, default_value_t = PinningBackend::Pinata)]
+    backend: PinningBackend,
+
+    /// JSON file of CID-to-key mappings; objects it already lists are
+    /// skipped, so an interrupted mirror can be re-run
+    #[arg(long, value_name = "PATH")]
+    out: PathBuf,
+}
+
+/// One object mirrored to IPFS
+#[derive(Serialize, Deserialize)]
+struct MirrorEntry {
+    bucket: String,
+    key: String,
+    cid: String,
+    size: u64,
+    /// CID in the object's metadata, recorded at upload; absent for objects
+    /// uploaded before CIDs were recorded
+    recorded_cid: Option<String>,
+    pinned_at: DateTime<Utc>,
+}
+
+/// Streams each object under the prefix out of the bucket and pins it, one
+/// at a time, recording its CID as it goes. Objects already pinned by an
+/// earlier run are skipped.
+pub async fn run(client: &S3Store, args: &MirrorArgs) -> Result<()> {
+    let pinner = Pinner::from_env(args.backend)?;
+    let mut entries = load_entries(&args.out)?;
+    let mirrored: HashSet<(String, String)> = entries
+        .iter()
+        .map(|entry| (entry.bucket.clone(), entry.key.clone()))
+        .collect();
+
+    let keys: Vec<String> = client
+        .list_keys(&args.prefix)
+        .await?
+        .into_iter()
+        .filter(|key| !mirrored.contains(&(client.bucket_name.clone(), key.clone())))
+        .collect();
+    println!(
+        "🪞 Mirroring {} objects under '{}' to {:?} ({} already mirrored)",
+        keys.len(),
+        args.prefix,
+        args.backend,
+        mirrored.len()
+    );
+
+    let mut pinned = 0;
+    let mut mismatched = 0;
+    let mut failed = 0;
+    for (index, key) in keys.iter().enumerate() {
+        let progress = format!("[{}/{}]", index + 1, keys.len());
+        match mirror_object(client, &pinner, key).await {
+            Ok(Some(entry)) => {
+                match &entry.recorded_cid {
+                    Some(recorded) if *recorded != entry.cid => {
+                        println!("{} ⚠️  Pinned {} as {}, but its recorded CID is {}", progress, key, entry.cid, recorded);
+                        mismatched += 1;
+                    }
+                    _ => println!("{} 📌 Pinned {} as {}", progress, key, entry.cid),
+                }
+                entries.push(entry);
+                write_entries(&args.out, &entries)?;
+                pinned += 1;
+            }
+            // Deleted since the listing
+            Ok(None) => println!("{} ⏭️  No longer in the bucket: {}", progress, key),
+            Err(e) => {
+                println!("{} ❌ Failed to mirror {}: {:#}", progress, key, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\nMirror Summary:");
+    println!("   📌 Pinned: {}", pinned);
+    if mismatched > 0 {
+        println!("   ⚠️  CID differs from recorded: {}", mismatched);
+    }
+    println!("   ❌ Failed: {}", failed);
+    println!("   📝 Mappings: {:?} ({} objects)", args.out, entries.len());
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} objects failed to mirror; re-run to retry them", failed, keys.len());
+    }
+    Ok(())
+}
+
+/// Pins the object under `key`, or returns None if it's gone.
+async fn mirror_object(client: &S3Store, pinner: &Pinner, key: &str) -> Result<Option<MirrorEntry>> {
+    let Some(head) = client.head(key).await? else {
+        return Ok(None);
+    };
+    let body = client.get(key).await?;
+    let cid = pinner.pin(key, body, head.size, &client.bucket_name).await?;
+    Ok(Some(MirrorEntry {
+        bucket: client.bucket_name.clone(),
+        key: key.to_string(),
+        cid,
+        size: head.size,
+        recorded_cid: head.metadata.get(CID_METADATA_KEY).cloned(),
+        pinned_at: Utc::now(),
+    }))
+}
+
+fn load_entries(path: &Path) -> Result<Vec<MirrorEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read mirror mappings: {:?}", path))?;
+    serde_json::from_str(&json).with_context(|| format!("Existing file is not a mirror mappings file: {:?}", path))
+}
+
+/// Replaces the mappings file in one rename, so an interrupted write can't
+/// truncate it.
+fn write_entries(path: &Path, entries: &[MirrorEntry]) -> Result<()> {
+    let json = serde_json::to_string_pretty(entries).context("Failed to serialize mirror mappings")?;
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    std::fs::write(&temp_path, json + "\n").with_context(|| format!("Failed to write mirror mappings: {:?}", temp_path))?;
+    std::fs::rename(&temp_path, path).with_context(|| format!("Failed to write mirror mappings: {:?}", path))
+}
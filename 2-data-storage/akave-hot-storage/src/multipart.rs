@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::list_parts::ListPartsError;
+use aws_sdk_s3::primitives::{ByteStream, Length};
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+
+use crate::checksum::SHA256_METADATA_KEY;
+use crate::encryption::with_customer_key;
+use crate::output::status;
+use crate::progress::FileProgress;
+use crate::resume::{PendingUpload, ResumeState, UploadedPart};
+use crate::{S3Store, ObjectHeaders};
+
+/// Part size used unless --part-size-mb says otherwise
+pub const DEFAULT_PART_SIZE_MB: u64 = 64;
+
+/// S3 rejects parts smaller than 5 MiB, except the last
+pub const MIN_PART_SIZE_MB: u64 = 5;
+
+/// S3 rejects parts larger than 5 GiB
+pub const MAX_PART_SIZE_MB: u64 = 5 * 1024;
+
+/// S3 allows at most 10,000 parts per upload
+pub const MAX_PARTS: u64 = 10_000;
+
+/// One file being sent as a multipart upload
+struct MultipartUpload<'a> {
+    key: &'a str,
+    upload_id: &'a str,
+    file_path: &'a Path,
+    file_size: u64,
+    part_size: u64,
+    headers: &'a ObjectHeaders,
+    progress: &'a FileProgress,
+}
+
+/// Uploads a file as an S3 multipart upload of the client's part size, each
+/// part read straight from disk. `headers` are stored with the finished
+/// object. Returns the object's ETag.
+///
+/// With a resume state file, the upload is recorded there part by part. An
+/// upload an earlier run left unfinished only sends the missing parts, and a
+/// failed one is left open for the next run. Without one, a failed upload is
+/// aborted so the parts already sent don't linger in the bucket.
+pub async fn upload_multipart(
+    client: &S3Store,
+    key: &str,
+    file_path: &Path,
+    file_size: u64,
+    headers: &ObjectHeaders,
+    progress: &FileProgress,
+) -> Result<String> {
+    let part_size = client.part_size;
+    let part_count = file_size.div_ceil(part_size);
+    if part_count > MAX_PARTS {
+        anyhow::bail!(
+            "{:?} needs {} parts of {} bytes, more than the {} S3 allows; use a larger --part-size-mb",
+            file_path,
+            part_count,
+            part_size,
+            MAX_PARTS
+        );
+    }
+
+    let sha256 = headers.metadata.get(SHA256_METADATA_KEY).cloned().unwrap_or_default();
+    let resumed = match &client.resume {
+        Some(state) => resume(client, state, key, file_size, &sha256).await?,
+        None => None,
+    };
+    let (upload_id, uploaded_parts) = match resumed {
+        Some(resumed) => resumed,
+        None => {
+            let upload_id = create(client, key, headers).await?;
+            if let Some(state) = &client.resume {
+                let pending = PendingUpload {
+                    upload_id: upload_id.clone(),
+                    file_path: file_path.to_path_buf(),
+                    sha256,
+                    file_size,
+                    part_size,
+                    parts: Vec::new(),
+                };
+                state.insert(&client.bucket_name, key, pending)?;
+            }
+            (upload_id, Vec::new())
+        }
+    };
+
+    status!("   Multipart upload of {}: {} parts of up to {} bytes", key, part_count, part_size);
+    let upload = MultipartUpload {
+        key,
+        upload_id: &upload_id,
+        file_path,
+        file_size,
+        part_size,
+        headers,
+        progress,
+    };
+    match upload_parts(client, &upload, uploaded_parts).await {
+        Ok(etag) => {
+            if let Some(state) = &client.resume {
+                state.remove(&client.bucket_name, key)?;
+            }
+            Ok(etag)
+        }
+        Err(e) if client.resume.is_some() => {
+            status!("   Left multipart upload of {} open to resume on the next run", key);
+            Err(e)
+        }
+        Err(e) => {
+            abort(client, key, &upload_id).await;
+            Err(e)
+        }
+    }
+}
+
+/// Starts a multipart upload, returning its ID.
+pub async fn create(client: &S3Store, key: &str, headers: &ObjectHeaders) -> Result<String> {
+    let request = client
+        .client
+        .create_multipart_upload()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .content_type(&headers.content_type)
+        .set_metadata(Some(headers.metadata.clone()))
+        .storage_class(headers.storage_class.clone())
+        .set_server_side_encryption(headers.encryption.server_side_encryption());
+    let created = with_customer_key!(request, headers.encryption)
+        .send()
+        .await
+        .with_context(|| format!("Failed to start multipart upload: {}", key))?;
+    created
+        .upload_id()
+        .map(str::to_string)
+        .context("Multipart upload was created without an upload ID")
+}
+
+/// Aborts a multipart upload, reporting rather than returning any failure.
+pub async fn abort(client: &S3Store, key: &str, upload_id: &str) {
+    let abort = client
+        .client
+        .abort_multipart_upload()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .upload_id(upload_id)
+        .send()
+        .await;
+    match abort {
+        Ok(_) => status!("   Aborted multipart upload of {}", key),
+        Err(abort_error) => status!(
+            "⚠️  Could not abort multipart upload {} of {}: {}",
+            upload_id, key, abort_error
+        ),
+    }
+}
+
+/// The ID and finished parts of an upload of `key` that an earlier run left
+/// unfinished, if it can be continued. The parts are listed from the
+/// service, falling back to the ones recorded locally if it can't list them.
+async fn resume(
+    client: &S3Store,
+    state: &ResumeState,
+    key: &str,
+    file_size: u64,
+    sha256: &str,
+) -> Result<Option<(String, Vec<UploadedPart>)>> {
+    let Some(pending) = state.get(&client.bucket_name, key) else {
+        return Ok(None);
+    };
+    if pending.sha256 != sha256 || pending.file_size != file_size || pending.part_size != client.part_size {
+        status!(
+            "   {:?} or --part-size-mb changed since the upload of {} was interrupted; starting over",
+            pending.file_path, key
+        );
+        abort(client, key, &pending.upload_id).await;
+        state.remove(&client.bucket_name, key)?;
+        return Ok(None);
+    }
+
+    let parts = match list_parts(client, key, &pending.upload_id).await {
+        Ok(parts) => parts,
+        Err(e) if e.as_service_error().and_then(|e| e.code()) == Some("NoSuchUpload") => {
+            status!("   Interrupted upload of {} has expired or been aborted; starting over", key);
+            state.remove(&client.bucket_name, key)?;
+            return Ok(None);
+        }
+        Err(e) => {
+            status!(
+                "⚠️  Could not list the uploaded parts of {} ({}); using the parts recorded locally",
+                key,
+                DisplayErrorContext(&e)
+            );
+            pending.parts
+        }
+    };
+    // A part cut short when the run died has to be sent again
+    let parts: Vec<UploadedPart> = parts
+        .into_iter()
+        .filter(|part| part_length(part.part_number, file_size, client.part_size) == Some(part.size))
+        .collect();
+    status!("   Resuming multipart upload of {}: {} parts already uploaded", key, parts.len());
+    Ok(Some((pending.upload_id, parts)))
+}
+
+/// The parts the service has received for an upload.
+async fn list_parts(
+    client: &S3Store,
+    key: &str,
+    upload_id: &str,
+) -> Result<Vec<UploadedPart>, SdkError<ListPartsError, HttpResponse>> {
+    let mut pages = client
+        .client
+        .list_parts()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .upload_id(upload_id)
+        .into_paginator()
+        .send();
+
+    let mut parts = Vec::new();
+    while let Some(page) = pages.next().await {
+        for part in page?.parts() {
+            if let (Some(part_number), Some(etag)) = (part.part_number(), part.e_tag()) {
+                parts.push(UploadedPart {
+                    part_number,
+                    etag: etag.to_string(),
+                    size: part.size().unwrap_or_default() as u64,
+                });
+            }
+        }
+    }
+    Ok(parts)
+}
+
+/// How many bytes part `part_number` (counting from 1) holds, or None if the
+/// file has no such part.
+fn part_length(part_number: i32, file_size: u64, part_size: u64) -> Option<u64> {
+    let offset = u64::try_from(part_number - 1).ok()? * part_size;
+    (offset < file_size).then(|| part_size.min(file_size - offset))
+}
+
+/// Sends every part not already in `uploaded_parts`, then completes the upload.
+async fn upload_parts(client: &S3Store, upload: &MultipartUpload<'_>, uploaded_parts: Vec<UploadedPart>) -> Result<String> {
+    let MultipartUpload {
+        key,
+        upload_id,
+        file_path,
+        file_size,
+        part_size,
+        headers,
+        progress,
+    } = *upload;
+    let mut etags: HashMap<i32, String> = uploaded_parts
+        .into_iter()
+        .map(|part| (part.part_number, part.etag))
+        .collect();
+
+    // Part numbers start at 1
+    let part_count = file_size.div_ceil(part_size) as i32;
+    for part_number in 1..=part_count {
+        let offset = (part_number - 1) as u64 * part_size;
+        let length = part_size.min(file_size - offset);
+        if etags.contains_key(&part_number) {
+            progress.inc(length);
+            continue;
+        }
+        let body = ByteStream::read_from()
+            .path(file_path)
+            .offset(offset)
+            .length(Length::Exact(length))
+            .build()
+            .await
+            .with_context(|| format!("Failed to read part {} of {:?}", part_number, file_path))?;
+
+        let request = client
+            .client
+            .upload_part()
+            .bucket(&client.bucket_name)
+            .key(key)
+            .upload_id(upload_id);
+        let uploaded = with_customer_key!(request, headers.encryption)
+            .part_number(part_number)
+            .content_length(length as i64)
+            .body(progress.track(body))
+            .send()
+            .await
+            .with_context(|| format!("Failed to upload part {} of {:?}", part_number, file_path))?;
+
+        let etag = uploaded.e_tag().unwrap_or_default().to_string();
+        if let Some(state) = &client.resume {
+            let part = UploadedPart {
+                part_number,
+                etag: etag.clone(),
+                size: length,
+            };
+            state.record_part(&client.bucket_name, key, part)?;
+        }
+        etags.insert(part_number, etag);
+        // Named, since other uploads may be printing at the same time
+        status!("   {}: part {} uploaded ({} bytes)", key, part_number, length);
+    }
+
+    let completed_parts = (1..=part_count)
+        .map(|part_number| {
+            CompletedPart::builder()
+                .part_number(part_number)
+                .set_e_tag(etags.remove(&part_number))
+                .build()
+        })
+        .collect();
+    let request = client
+        .client
+        .complete_multipart_upload()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .upload_id(upload_id);
+    let completed = with_customer_key!(request, headers.encryption)
+        .multipart_upload(
+            CompletedMultipartUpload::builder()
+                .set_parts(Some(completed_parts))
+                .build(),
+        )
+        .send()
+        .await
+        .with_context(|| format!("Failed to complete multipart upload of {:?}", file_path))?;
+
+    Ok(completed.e_tag().unwrap_or("unknown").to_string())
+}
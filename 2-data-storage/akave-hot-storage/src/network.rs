@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::config::timeout::TimeoutConfig;
+use aws_smithy_http_client::pool::{Client as PooledClient, ConnectionPool};
+use aws_smithy_http_client::tls::{self, rustls_provider::CryptoMode};
+
+#[derive(clap::Args)]
+pub struct NetworkArgs {
+    /// Seconds to wait for a connection to the endpoint to open [default: the SDK's, 3.1]
+    #[arg(long, global = true)]
+    connect_timeout_secs: Option<f64>,
+
+    /// Seconds a whole request may take, retries included, before it is
+    /// abandoned; a multipart upload's parts are separate requests [default: no limit]
+    #[arg(long, global = true)]
+    operation_timeout_secs: Option<f64>,
+
+    /// Most connections open to the endpoint at once; further requests wait
+    /// for one to free up [default: no limit]
+    #[arg(long, global = true, value_parser = clap::value_parser!(u64).range(1..))]
+    max_connections: Option<u64>,
+}
+
+/// How the SDK connects to the endpoint. Unset values keep the SDK's
+/// defaults, which are tuned for datacenter links rather than the long
+/// round trips of a satellite connection.
+#[derive(Debug, Clone, Copy)]
+pub struct NetworkPolicy {
+    pub connect_timeout: Option<Duration>,
+    pub operation_timeout: Option<Duration>,
+    pub max_connections: Option<usize>,
+}
+
+impl TryFrom<&NetworkArgs> for NetworkPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(args: &NetworkArgs) -> Result<Self> {
+        let seconds = |flag: &str, secs: Option<f64>| {
+            secs.map(|secs| {
+                Duration::try_from_secs_f64(secs).with_context(|| format!("{} must be a positive number of seconds", flag))
+            })
+            .transpose()
+        };
+        Ok(Self {
+            connect_timeout: seconds("--connect-timeout-secs", args.connect_timeout_secs)?,
+            operation_timeout: seconds("--operation-timeout-secs", args.operation_timeout_secs)?,
+            max_connections: args.max_connections.map(|max| max as usize),
+        })
+    }
+}
+
+impl NetworkPolicy {
+    /// The timeouts to set on the SDK's config; the SDK fills in the rest.
+    pub fn timeout_config(&self) -> TimeoutConfig {
+        let mut timeouts = TimeoutConfig::builder();
+        timeouts
+            .set_connect_timeout(self.connect_timeout)
+            .set_operation_timeout(self.operation_timeout);
+        timeouts.build()
+    }
+
+    /// An HTTP client that keeps to `max_connections`, or None to use the
+    /// SDK's default, which opens as many as there are requests in flight.
+    pub fn http_client(&self) -> Result<Option<PooledClient>> {
+        let Some(max_connections) = self.max_connections else {
+            return Ok(None);
+        };
+        let pool = ConnectionPool::builder()
+            .max_connections_per_host(max_connections)
+            .tls_provider(tls::Provider::Rustls(CryptoMode::AwsLc))
+            .build_https()
+            .context("Failed to build HTTP connection pool")?;
+        Ok(Some(PooledClient::new(&pool).context("Failed to build HTTP client")?))
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Set once records are to be printed to stdout, so progress messages go to
+/// stderr and don't end up mixed into them
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable progress and results
+    Table,
+    /// A JSON array of records on stdout
+    Json,
+    /// CSV records with a header row on stdout
+    Csv,
+}
+
+/// A row of machine-readable output. `COLUMNS` names the serialized fields
+/// in the order CSV prints them.
+pub trait Record: Serialize {
+    const COLUMNS: &'static [&'static str];
+}
+
+/// Keeps stdout for records from now on if the format is machine-readable.
+pub fn reserve_stdout(format: OutputFormat) {
+    if format != OutputFormat::Table {
+        STDOUT_RESERVED.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn stdout_reserved() -> bool {
+    STDOUT_RESERVED.load(Ordering::Relaxed)
+}
+
+/// Prints the records as JSON or CSV. Does nothing for the table format,
+/// where results are printed as they happen.
+pub fn print_records<R: Record>(format: OutputFormat, records: &[R]) -> Result<()> {
+    match format {
+        OutputFormat::Table => {}
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(records).context("Failed to serialize records")?;
+            println!("{}", json);
+        }
+        OutputFormat::Csv => {
+            println!("{}", R::COLUMNS.join(","));
+            for record in records {
+                let value = serde_json::to_value(record).context("Failed to serialize record")?;
+                let fields: Vec<String> = R::COLUMNS.iter().map(|column| csv_field(&value[*column])).collect();
+                println!("{}", fields.join(","));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A CSV field, quoted when it contains a separator, quote or line break.
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// println! for progress messages, which go to stderr instead while stdout
+/// is reserved for records.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::stdout_reserved() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+pub(crate) use status;
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::presigning::PresigningConfig;
+use clap::ValueEnum;
+
+use crate::S3Store;
+
+/// SigV4 presigned URLs are valid for at most seven days
+const MAX_EXPIRES_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(clap::Args)]
+pub struct PresignArgs {
+    /// Key of the object the URL is for
+    #[arg(long)]
+    key: String,
+
+    /// Seconds until the URL stops working (at most 604800, seven days)
+    #[arg(long, default_value_t = 3600, value_parser = clap::value_parser!(u64).range(1..=MAX_EXPIRES_SECS))]
+    expires: u64,
+
+    /// Whether the URL downloads the object or uploads to its key
+    #[arg(long, value_enum, default_value_t = PresignMethod::Get)]
+    method: PresignMethod,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PresignMethod {
+    Get,
+    Put,
+}
+
+/// Prints a URL that grants GET or PUT access to one key until it expires,
+/// signed with our credentials so whoever holds it doesn't need their own.
+pub async fn run(client: &S3Store, args: &PresignArgs) -> Result<()> {
+    let config = PresigningConfig::expires_in(Duration::from_secs(args.expires))
+        .context("Invalid presigned URL expiry")?;
+    let context = || format!("Failed to presign URL for: {}", args.key);
+    let request = match args.method {
+        PresignMethod::Get => client
+            .client
+            .get_object()
+            .bucket(&client.bucket_name)
+            .key(&args.key)
+            .presigned(config)
+            .await
+            .with_context(context)?,
+        PresignMethod::Put => client
+            .client
+            .put_object()
+            .bucket(&client.bucket_name)
+            .key(&args.key)
+            .presigned(config)
+            .await
+            .with_context(context)?,
+    };
+
+    let method = match args.method {
+        PresignMethod::Get => "GET",
+        PresignMethod::Put => "PUT",
+    };
+    println!("🔗 Presigned {} URL for {} (expires in {} seconds):", method, args.key, args.expires);
+    println!("{}", request.uri());
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::output::status;
+
+/// Profiles file path under the user's config directory
+const PROFILES_FILE: &str = "starling/akave.toml";
+
+const DEFAULT_ENDPOINT: &str = "https://o3-rc1.akave.xyz";
+const DEFAULT_BUCKET: &str = "starling-akave";
+
+#[derive(clap::Args)]
+pub struct ProfileArgs {
+    /// Endpoint profile from the profiles file, in place of the AKAVE_*
+    /// environment variables
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Endpoint profiles file [default: ~/.config/starling/akave.toml]
+    #[arg(long, global = true)]
+    profiles_file: Option<PathBuf>,
+
+    /// S3-compatible endpoint URL, overriding AKAVE_HOSTNAME or the profile's
+    #[arg(long, global = true, value_name = "URL")]
+    endpoint: Option<String>,
+
+    /// Address buckets in the URL path instead of as a subdomain, as MinIO
+    /// and most self-hosted endpoints need
+    #[arg(long, global = true)]
+    path_style: bool,
+}
+
+/// Named endpoints, so Akave staging and production, or another
+/// S3-compatible store, can be selected with --profile instead of swapping
+/// .env files. Credentials stay in the environment; a profile names the
+/// variables holding them:
+///
+/// ```toml
+/// [profiles.staging]
+/// endpoint = "https://o3-rc1.akave.xyz"
+/// bucket = "starling-staging"
+/// username_env = "AKAVE_STAGING_USERNAME"
+/// credential_env = "AKAVE_STAGING_CREDENTIAL"
+///
+/// [profiles.minio]
+/// endpoint = "http://minio.internal:9000"
+/// bucket = "starling"
+/// path_style = true
+/// username_env = "MINIO_ACCESS_KEY"
+/// credential_env = "MINIO_SECRET_KEY"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfilesFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Profile {
+    endpoint: String,
+    bucket: String,
+    #[serde(default = "default_username_env")]
+    username_env: String,
+    #[serde(default = "default_credential_env")]
+    credential_env: String,
+    #[serde(default = "default_session_token_env")]
+    session_token_env: String,
+    /// Needed by AWS S3 and others that check the signing region
+    region: Option<String>,
+    #[serde(default)]
+    path_style: bool,
+}
+
+fn default_username_env() -> String {
+    "AKAVE_USERNAME".to_string()
+}
+
+fn default_credential_env() -> String {
+    "AKAVE_CREDENTIAL".to_string()
+}
+
+fn default_session_token_env() -> String {
+    "AKAVE_SESSION_TOKEN".to_string()
+}
+
+/// Where to connect and the credentials to sign requests with
+pub struct Connection {
+    pub endpoint: String,
+    pub bucket: String,
+    pub username: String,
+    pub credential: String,
+    pub session_token: Option<String>,
+    /// Signing region, if the endpoint needs one; else AWS_REGION or a
+    /// placeholder Akave accepts
+    pub region: Option<String>,
+    /// Address the bucket in the URL path rather than as a subdomain
+    pub path_style: bool,
+}
+
+impl ProfileArgs {
+    /// The connection named by --profile, or else the one the AKAVE_*
+    /// environment variables describe, with --endpoint and --path-style
+    /// applied on top. A `.env` file is loaded first either way, so it can
+    /// hold the variables a profile refers to.
+    pub fn connection(&self) -> Result<Connection> {
+        dotenv::dotenv().ok();
+        let mut connection = match &self.profile {
+            Some(name) => {
+                let path = self
+                    .profiles_file
+                    .clone()
+                    .or_else(default_profiles_path)
+                    .context("Cannot locate the profiles file: HOME is not set, pass --profiles-file")?;
+                status!("🔑 Using profile {:?} from {:?}", name, path);
+                load_profile(&path, name)?
+            }
+            None => Connection {
+                endpoint: env::var("AKAVE_HOSTNAME").unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string()),
+                bucket: env::var("AKAVE_BUCKET").unwrap_or_else(|_| DEFAULT_BUCKET.to_string()),
+                username: required_env("AKAVE_USERNAME")?,
+                credential: required_env("AKAVE_CREDENTIAL")?,
+                session_token: optional_env("AKAVE_SESSION_TOKEN"),
+                region: None,
+                path_style: false,
+            },
+        };
+        if let Some(endpoint) = &self.endpoint {
+            connection.endpoint = endpoint.clone();
+        }
+        connection.path_style |= self.path_style;
+        Ok(connection)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/starling/akave.toml`, falling back to `~/.config`.
+fn default_profiles_path() -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join(PROFILES_FILE))
+}
+
+/// Reads profile `name` from the profiles file at `path` and looks up the
+/// credentials it refers to.
+fn load_profile(path: &Path, name: &str) -> Result<Connection> {
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read profiles: {:?}", path))?;
+    let mut file: ProfilesFile =
+        toml::from_str(&contents).with_context(|| format!("Failed to parse profiles: {:?}", path))?;
+
+    let Some(profile) = file.profiles.remove(name) else {
+        let known: Vec<&str> = file.profiles.keys().map(String::as_str).collect();
+        anyhow::bail!("No profile named {:?} in {:?} (available: {})", name, path, known.join(", "));
+    };
+    let source = format!("Profile {:?} in {:?}", name, path);
+    if profile.endpoint.is_empty() || profile.bucket.is_empty() {
+        anyhow::bail!("{}: endpoint and bucket cannot be empty", source);
+    }
+    Ok(Connection {
+        username: required_env(&profile.username_env).with_context(|| format!("{} needs its username", source))?,
+        credential: required_env(&profile.credential_env)
+            .with_context(|| format!("{} needs its credential", source))?,
+        session_token: optional_env(&profile.session_token_env),
+        endpoint: profile.endpoint,
+        bucket: profile.bucket,
+        region: profile.region,
+        path_style: profile.path_style,
+    })
+}
+
+fn required_env(name: &str) -> Result<String> {
+    let value = env::var(name).with_context(|| format!("{} environment variable is required", name))?;
+    if value.is_empty() {
+        anyhow::bail!("{} cannot be empty", name);
+    }
+    Ok(value)
+}
+
+pub fn optional_env(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
@@ -0,0 +1,192 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, ready};
+
+use aws_sdk_s3::primitives::{ByteStream, SdkBody};
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use tokio::time::{Sleep, sleep_until};
+
+use crate::output;
+use crate::throttle::{Rate, Throttle};
+
+const FILE_TEMPLATE: &str = "{msg:32!} [{bar:30.cyan/blue}] {bytes}/{total_bytes} {binary_bytes_per_sec}";
+const FILES_TEMPLATE: &str = "Files  [{bar:30.green/white}] {pos}/{len}";
+const BYTES_TEMPLATE: &str = "Bytes  [{bar:30.green/white}] {bytes}/{total_bytes} {binary_bytes_per_sec} ETA {eta}";
+
+/// Aggregate progress for a batch: files processed and bytes sent. Per-file
+/// bars are drawn above the two batch bars while their upload is in flight.
+/// Bars draw to stderr and are hidden when it isn't a terminal or with
+/// --output json or csv.
+#[derive(Clone)]
+pub struct BatchProgress {
+    multi: MultiProgress,
+    files: ProgressBar,
+    bytes: ProgressBar,
+    /// Paces every upload in the batch together
+    throttle: Option<Arc<Throttle>>,
+    /// Paces each file's upload on its own
+    file_rate: Option<Rate>,
+}
+
+impl BatchProgress {
+    pub fn new(total_files: u64, total_bytes: u64) -> Self {
+        let multi = match output::stdout_reserved() {
+            true => MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+            false => MultiProgress::new(),
+        };
+        let files = multi.add(ProgressBar::new(total_files).with_style(style(FILES_TEMPLATE)));
+        let bytes = multi.add(ProgressBar::new(total_bytes).with_style(style(BYTES_TEMPLATE)));
+        Self {
+            multi,
+            files,
+            bytes,
+            throttle: None,
+            file_rate: None,
+        }
+    }
+
+    /// Limits how fast the batch as a whole, and each file in it, is sent.
+    pub fn with_rate_limits(mut self, batch_rate: Option<Rate>, file_rate: Option<Rate>) -> Self {
+        self.throttle = batch_rate.map(|rate| Arc::new(Throttle::new(rate)));
+        self.file_rate = file_rate;
+        self
+    }
+
+    /// Adds a bar for one file of `len` bytes.
+    pub fn start_file(&self, name: &str, len: u64) -> FileProgress {
+        let bar = self.multi.insert_before(&self.files, file_bar(name, len));
+        let throttles = self
+            .throttle
+            .iter()
+            .cloned()
+            .chain(self.file_rate.map(|rate| Arc::new(Throttle::new(rate))))
+            .collect();
+        FileProgress {
+            bar,
+            batch: self.clone(),
+            throttles,
+        }
+    }
+
+    pub fn finish(&self) {
+        self.files.finish();
+        self.bytes.finish();
+    }
+}
+
+/// Progress of a single upload, also counted toward its batch.
+#[derive(Clone)]
+pub struct FileProgress {
+    bar: ProgressBar,
+    batch: BatchProgress,
+    /// The batch's rate limit and the file's own, if set
+    throttles: Vec<Arc<Throttle>>,
+}
+
+impl FileProgress {
+    pub fn inc(&self, bytes: u64) {
+        self.bar.inc(bytes);
+        self.batch.bytes.inc(bytes);
+    }
+
+    /// Takes back bytes counted for a request that is being sent again.
+    fn dec(&self, bytes: u64) {
+        for bar in [&self.bar, &self.batch.bytes] {
+            bar.set_position(bar.position().saturating_sub(bytes));
+        }
+    }
+
+    /// Rewinds to zero before the whole file is uploaded again.
+    pub fn restart(&self) {
+        self.dec(self.bar.position());
+    }
+
+    /// Reserves sent bytes with each rate limit, returning when sending may
+    /// go on if any limit has been reached.
+    fn throttle(&self, bytes: u64) -> Option<tokio::time::Instant> {
+        self.throttles.iter().map(|throttle| throttle.reserve(bytes)).max()
+    }
+
+    /// Wraps a request body so bytes are counted as the HTTP client sends
+    /// them, and sent no faster than the rate limits allow. If the SDK
+    /// retries the request, the bytes counted for the failed attempt are
+    /// taken back off.
+    pub fn track(&self, body: ByteStream) -> ByteStream {
+        let progress = self.clone();
+        let counted = Arc::new(AtomicU64::new(0));
+        ByteStream::new(body.into_inner().map(move |inner| {
+            progress.dec(counted.swap(0, Ordering::Relaxed));
+            SdkBody::from_body_1_x(ProgressBody {
+                inner,
+                progress: progress.clone(),
+                counted: counted.clone(),
+                delay: None,
+            })
+        }))
+    }
+
+    /// Removes the bar once the file is done, successfully or not. The batch
+    /// counts the whole file as processed so its bars end at 100%.
+    pub fn finish(self) {
+        let remaining = self.bar.length().unwrap_or_default().saturating_sub(self.bar.position());
+        self.batch.bytes.inc(remaining);
+        self.batch.files.inc(1);
+        self.bar.finish_and_clear();
+    }
+}
+
+/// A request body that reports each chunk to a file's progress as it is read
+struct ProgressBody {
+    inner: SdkBody,
+    progress: FileProgress,
+    /// Bytes of this body counted so far
+    counted: Arc<AtomicU64>,
+    /// Holds back the next chunk until the rate limits allow it
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl Body for ProgressBody {
+    type Data = Bytes;
+    type Error = <SdkBody as Body>::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        if let Some(delay) = self.delay.as_mut() {
+            ready!(delay.as_mut().poll(cx));
+            self.delay = None;
+        }
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &polled
+            && let Some(data) = frame.data_ref()
+        {
+            let len = data.len() as u64;
+            self.counted.fetch_add(len, Ordering::Relaxed);
+            self.progress.inc(len);
+            self.delay = self.progress.throttle(len).map(|until| Box::pin(sleep_until(until)));
+        }
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        Body::size_hint(&self.inner)
+    }
+}
+
+fn file_bar(name: &str, len: u64) -> ProgressBar {
+    ProgressBar::new(len)
+        .with_style(style(FILE_TEMPLATE).progress_chars("=> "))
+        .with_message(name.to_string())
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .expect("progress template is valid")
+        .progress_chars("=> ")
+}
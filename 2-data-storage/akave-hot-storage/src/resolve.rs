@@ -0,0 +1,58 @@
+use anyhow::Result;
+use futures_util::{StreamExt, TryStreamExt, stream};
+
+use crate::S3Store;
+use crate::cid::CID_METADATA_KEY;
+use crate::store::ObjectStore;
+
+/// Objects whose metadata is fetched at the same time while searching
+const LOOKUP_CONCURRENCY: usize = 16;
+
+#[derive(clap::Args)]
+pub struct ResolveArgs {
+    /// IPFS CID of the content, as recorded at upload and in the asset catalog
+    #[arg(long)]
+    cid: String,
+
+    /// Only search keys starting with this prefix; much faster in a large bucket
+    #[arg(long)]
+    prefix: Option<String>,
+}
+
+/// Lists every object whose content has the given CID. S3 can't search by
+/// metadata, so each object's metadata is fetched.
+pub async fn run(client: &S3Store, args: &ResolveArgs) -> Result<()> {
+    let prefix = args.prefix.as_deref().unwrap_or_default();
+    let keys = client.list_keys(prefix).await?;
+    println!("🔎 Searching {} objects for CID {}...", keys.len(), args.cid);
+
+    let mut found: Vec<(String, u64)> = stream::iter(keys)
+        .map(|key| lookup(client, key, &args.cid))
+        .buffer_unordered(LOOKUP_CONCURRENCY)
+        .try_filter_map(|found| async move { Ok(found) })
+        .try_collect()
+        .await?;
+    if found.is_empty() {
+        println!("No objects found for CID: {}", args.cid);
+        return Ok(());
+    }
+    found.sort();
+
+    println!("\n📦 Objects with CID {}:", args.cid);
+    for (key, size) in &found {
+        println!("  {} ({} bytes)", key, size);
+    }
+    Ok(())
+}
+
+/// The key and size of the object under `key`, if its metadata records `cid`.
+async fn lookup(client: &S3Store, key: String, cid: &str) -> Result<Option<(String, u64)>> {
+    // Deleted since the listing
+    let Some(head) = client.head(&key).await? else {
+        return Ok(None);
+    };
+    if head.metadata.get(CID_METADATA_KEY).map(String::as_str) != Some(cid) {
+        return Ok(None);
+    }
+    Ok(Some((key, head.size)))
+}
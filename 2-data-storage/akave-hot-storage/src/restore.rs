@@ -0,0 +1,41 @@
+use anyhow::{Context, Result};
+
+use crate::S3Store;
+use crate::copy::copy_source;
+use crate::encryption::{with_copy_source_customer_key, with_customer_key};
+
+#[derive(clap::Args)]
+pub struct RestoreArgs {
+    /// Key of the object to restore
+    #[arg(long)]
+    key: String,
+
+    /// Version to bring back, as shown by `list --versions`
+    #[arg(long)]
+    version_id: String,
+}
+
+/// Makes an earlier version of an object current again by copying it over
+/// its own key, metadata included. The version it replaces is kept.
+pub async fn run(client: &S3Store, args: &RestoreArgs) -> Result<()> {
+    let source = copy_source(&client.bucket_name, &args.key, Some(&args.version_id));
+    // An SSE-C object is decrypted and encrypted again with the same key
+    let request = client
+        .client
+        .copy_object()
+        .bucket(&client.bucket_name)
+        .key(&args.key)
+        .copy_source(source)
+        .set_server_side_encryption(client.encryption.server_side_encryption());
+    let request = with_copy_source_customer_key!(request, client.encryption);
+    let copied = with_customer_key!(request, client.encryption)
+        .send()
+        .await
+        .with_context(|| format!("Failed to restore version {} of {}", args.version_id, args.key))?;
+
+    println!("♻️  Restored {} to version {}", args.key, args.version_id);
+    if let Some(version_id) = copied.version_id() {
+        println!("   New current version: {}", version_id);
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Multipart uploads that haven't completed yet, kept in a local file so a
+/// later run can pick them up where they stopped. Saved after every change,
+/// since the run may die at any point.
+pub struct ResumeState {
+    path: PathBuf,
+    /// By `<bucket>/<key>`
+    uploads: Mutex<HashMap<String, PendingUpload>>,
+}
+
+/// A multipart upload started from a local file
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingUpload {
+    pub upload_id: String,
+    pub file_path: PathBuf,
+    /// Of the whole file, so a file changed since the upload started isn't
+    /// resumed with parts of its old contents
+    pub sha256: String,
+    pub file_size: u64,
+    pub part_size: u64,
+    pub parts: Vec<UploadedPart>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UploadedPart {
+    pub part_number: i32,
+    pub etag: String,
+    pub size: u64,
+}
+
+impl ResumeState {
+    /// Reads the state file, or starts empty if it doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        let uploads = match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Resume state file is not valid: {:?}", path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read resume state file: {:?}", path)),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            uploads: Mutex::new(uploads),
+        })
+    }
+
+    pub fn get(&self, bucket: &str, key: &str) -> Option<PendingUpload> {
+        self.lock().get(&state_key(bucket, key)).cloned()
+    }
+
+    pub fn insert(&self, bucket: &str, key: &str, upload: PendingUpload) -> Result<()> {
+        let mut uploads = self.lock();
+        uploads.insert(state_key(bucket, key), upload);
+        self.save(&uploads)
+    }
+
+    pub fn record_part(&self, bucket: &str, key: &str, part: UploadedPart) -> Result<()> {
+        let mut uploads = self.lock();
+        if let Some(upload) = uploads.get_mut(&state_key(bucket, key)) {
+            upload.parts.retain(|existing| existing.part_number != part.part_number);
+            upload.parts.push(part);
+        }
+        self.save(&uploads)
+    }
+
+    pub fn remove(&self, bucket: &str, key: &str) -> Result<()> {
+        let mut uploads = self.lock();
+        if uploads.remove(&state_key(bucket, key)).is_some() {
+            self.save(&uploads)?;
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, PendingUpload>> {
+        // The map is only written whole, so it's still usable if a holder panicked
+        self.uploads.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Replaces the file in one rename, so a crash mid-write can't corrupt it.
+    fn save(&self, uploads: &HashMap<String, PendingUpload>) -> Result<()> {
+        let json = serde_json::to_string_pretty(uploads).context("Failed to serialize resume state")?;
+        let mut temp_path = self.path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        std::fs::write(&temp_path, json + "\n")
+            .with_context(|| format!("Failed to write resume state file: {:?}", temp_path))?;
+        std::fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("Failed to write resume state file: {:?}", self.path))
+    }
+}
+
+fn state_key(bucket: &str, key: &str) -> String {
+    format!("{}/{}", bucket, key)
+}
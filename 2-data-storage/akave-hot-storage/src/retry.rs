@@ -0,0 +1,107 @@
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use anyhow::Result;
+use aws_sdk_s3::config::retry::RetryConfig;
+use aws_sdk_s3::error::ConnectorError;
+
+use crate::output::status;
+
+#[derive(clap::Args)]
+pub struct RetryArgs {
+    /// Number of times to retry a failed request, and then a failed upload if the connection dropped
+    #[arg(long, global = true, default_value_t = 3)]
+    retries: u32,
+
+    /// Delay in milliseconds before the first retry; it doubles with each attempt
+    #[arg(long, global = true, default_value_t = 1000)]
+    retry_base_ms: u64,
+
+    /// Longest delay in milliseconds between retries
+    #[arg(long, global = true, default_value_t = 20_000)]
+    retry_max_ms: u64,
+}
+
+/// How failed requests are retried: by the SDK for each request, and again
+/// around whole uploads when the connection to the endpoint drops.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl From<&RetryArgs> for RetryPolicy {
+    fn from(args: &RetryArgs) -> Self {
+        Self {
+            max_retries: args.retries,
+            base_delay: Duration::from_millis(args.retry_base_ms),
+            max_delay: Duration::from_millis(args.retry_max_ms.max(args.retry_base_ms)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The SDK's own policy for each request: throttling, 5xx responses,
+    /// timeouts and I/O errors, with jittered exponential backoff.
+    pub fn sdk_config(&self) -> RetryConfig {
+        RetryConfig::standard()
+            .with_max_attempts(self.max_retries + 1)
+            .with_initial_backoff(self.base_delay)
+            .with_max_backoff(self.max_delay)
+    }
+
+    /// Delay before retry number `attempt` (0-based): base * 2^attempt, capped at the max.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+
+    /// Runs `operation` again when it fails because the connection broke,
+    /// which the SDK gives up on mid-upload once its own retries run out.
+    /// Other failures are returned straight away.
+    pub async fn run<T, F, Fut>(&self, label: &str, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match operation().await {
+                Err(e) if attempt < self.max_retries && is_connection_error(&e) => {
+                    let delay = self.backoff(attempt);
+                    attempt += 1;
+                    status!(
+                        "⚠️  Connection error on {} ({:#}); retrying in {:?} (attempt {}/{})",
+                        label, e, delay, attempt, self.max_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Whether an error comes from the connection itself (reset, dropped or
+/// timed out) rather than from the endpoint rejecting the request.
+fn is_connection_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(connector) = cause.downcast_ref::<ConnectorError>() {
+            return connector.is_io() || connector.is_timeout();
+        }
+        cause.downcast_ref::<io::Error>().is_some_and(|io| {
+            matches!(
+                io.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::UnexpectedEof
+            )
+        })
+    })
+}
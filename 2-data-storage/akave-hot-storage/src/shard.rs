@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// Object metadata key holding the global CID of the sharded asset a chunk
+/// or shard metadata file belongs to
+pub const SHARD_CID_METADATA_KEY: &str = "shard-cid";
+
+/// Object metadata key holding a chunk's 0-based position in its asset
+pub const CHUNK_INDEX_METADATA_KEY: &str = "chunk-index";
+
+/// Object metadata key holding how many chunks the asset was split into
+pub const CHUNK_COUNT_METADATA_KEY: &str = "chunk-count";
+
+/// Where a file sits within a sharded asset produced by blob_cid_shard
+#[derive(Debug, Clone)]
+pub struct ShardRef {
+    pub shard_cid: String,
+    /// None for the asset's `*_metadata.json` itself
+    pub chunk_index: Option<usize>,
+    pub chunk_count: usize,
+}
+
+impl ShardRef {
+    /// The object metadata recording this reference.
+    pub fn metadata(&self) -> Vec<(String, String)> {
+        let mut metadata = vec![
+            (SHARD_CID_METADATA_KEY.to_string(), self.shard_cid.clone()),
+            (CHUNK_COUNT_METADATA_KEY.to_string(), self.chunk_count.to_string()),
+        ];
+        if let Some(chunk_index) = self.chunk_index {
+            metadata.push((CHUNK_INDEX_METADATA_KEY.to_string(), chunk_index.to_string()));
+        }
+        metadata
+    }
+}
+
+/// The subset of blob_cid_shard's ShardMetadata needed to identify chunks
+#[derive(Debug, Deserialize)]
+struct ShardManifest {
+    cid: String,
+    chunks: Vec<ShardManifestChunk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShardManifestChunk {
+    filename: String,
+}
+
+/// Maps chunk and shard metadata paths to their asset's CID, using any
+/// `*_metadata.json` among the given files. Manifests that can't be parsed
+/// (e.g. encrypted stubs) are skipped.
+pub fn index_shard_files(files: &[PathBuf]) -> HashMap<PathBuf, ShardRef> {
+    let mut refs = HashMap::new();
+
+    for manifest_path in files.iter().filter(|path| is_shard_manifest(path)) {
+        let Some(manifest) = std::fs::read_to_string(manifest_path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<ShardManifest>(&json).ok())
+        else {
+            continue;
+        };
+
+        let chunk_count = manifest.chunks.len();
+        let dir = manifest_path.parent().unwrap_or(Path::new(""));
+        for (chunk_index, chunk) in manifest.chunks.iter().enumerate() {
+            refs.insert(
+                dir.join(&chunk.filename),
+                ShardRef {
+                    shard_cid: manifest.cid.clone(),
+                    chunk_index: Some(chunk_index),
+                    chunk_count,
+                },
+            );
+        }
+        refs.insert(
+            manifest_path.clone(),
+            ShardRef {
+                shard_cid: manifest.cid,
+                chunk_index: None,
+                chunk_count,
+            },
+        );
+    }
+
+    refs
+}
+
+/// Whether the file is named like blob_cid_shard's metadata output.
+fn is_shard_manifest(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().ends_with("_metadata.json"))
+        .unwrap_or(false)
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::primitives::{ByteStream, DateTime as SdkDateTime};
+use chrono::{DateTime, Utc};
+
+use crate::encryption::with_customer_key;
+use crate::{ObjectHeaders, S3Store};
+
+/// An object's key, size and what the endpoint reports about it
+pub struct ObjectInfo {
+    pub key: String,
+    pub size: u64,
+    /// Without the surrounding quotes
+    pub e_tag: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+    /// User metadata (`x-amz-meta-*`); only filled in by `head`
+    pub metadata: HashMap<String, String>,
+}
+
+/// The object operations most commands need, in terms any S3-compatible
+/// endpoint supports: Akave, MinIO, Wasabi or AWS S3 itself. Features only
+/// some endpoints have, like versioning or tiering, use the SDK client
+/// directly.
+pub trait ObjectStore {
+    /// Stores `body`, `size` bytes long, under `key` and returns its ETag.
+    async fn put(&self, key: &str, body: ByteStream, size: u64, headers: &ObjectHeaders) -> Result<String>;
+
+    /// Streams the contents of the object under `key`.
+    async fn get(&self, key: &str) -> Result<ByteStream>;
+
+    /// The object under `key`, or None if there isn't one.
+    async fn head(&self, key: &str) -> Result<Option<ObjectInfo>>;
+
+    /// Every object whose key starts with `prefix`, following continuation
+    /// tokens. Listings don't include metadata.
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>>;
+
+    /// Removes the object under `key`. Succeeds if there was none.
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+impl ObjectStore for S3Store {
+    async fn put(&self, key: &str, body: ByteStream, size: u64, headers: &ObjectHeaders) -> Result<String> {
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .content_length(size as i64)
+            .body(body)
+            .content_type(&headers.content_type)
+            .set_metadata(Some(headers.metadata.clone()))
+            .storage_class(headers.storage_class.clone())
+            .set_server_side_encryption(headers.encryption.server_side_encryption());
+        let output = with_customer_key!(request, headers.encryption)
+            .send()
+            .await
+            .with_context(|| format!("Failed to put object: {}", key))?;
+        Ok(output.e_tag().unwrap_or("unknown").to_string())
+    }
+
+    async fn get(&self, key: &str) -> Result<ByteStream> {
+        let request = self.client.get_object().bucket(&self.bucket_name).key(key);
+        let output = with_customer_key!(request, self.encryption)
+            .send()
+            .await
+            .with_context(|| format!("Failed to download object: {}", key))?;
+        Ok(output.body)
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<ObjectInfo>> {
+        let request = self.client.head_object().bucket(&self.bucket_name).key(key);
+        let head = match with_customer_key!(request, self.encryption).send().await {
+            Ok(head) => head,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to look up object: {}", key)),
+        };
+        Ok(Some(ObjectInfo {
+            key: key.to_string(),
+            size: head.content_length().unwrap_or_default().max(0) as u64,
+            e_tag: head.e_tag().map(|etag| etag.trim_matches('"').to_string()),
+            last_modified: head.last_modified().and_then(to_chrono),
+            metadata: head.metadata().cloned().unwrap_or_default(),
+        }))
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>> {
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket_name)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+
+        let mut objects = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page.with_context(|| format!("Failed to list objects with prefix: {}", prefix))?;
+            objects.extend(page.contents().iter().filter_map(|object| {
+                Some(ObjectInfo {
+                    key: object.key()?.to_string(),
+                    size: object.size().unwrap_or_default().max(0) as u64,
+                    e_tag: object.e_tag().map(|etag| etag.trim_matches('"').to_string()),
+                    last_modified: object.last_modified().and_then(to_chrono),
+                    metadata: HashMap::new(),
+                })
+            }));
+        }
+        Ok(objects)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to delete object: {}", key))?;
+        Ok(())
+    }
+}
+
+fn to_chrono(time: &SdkDateTime) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(time.secs(), time.subsec_nanos())
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::checksum::{SHA256_METADATA_KEY, file_md5, file_sha256, hash_file};
+use crate::output::{OutputFormat, Record, print_records, status};
+use crate::store::ObjectStore;
+use crate::{S3Store, UploadArgs, find_files, plan_uploads, upload_batch};
+
+#[derive(clap::Args)]
+pub struct SyncArgs {
+    #[command(flatten)]
+    upload: UploadArgs,
+
+    /// Print the plan without uploading anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// How to print the plan and results; JSON and CSV list every file at the end
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+}
+
+/// What sync did with one local file
+#[derive(Serialize)]
+struct SyncRecord {
+    key: String,
+    local_path: PathBuf,
+    /// new, changed or unchanged
+    change: &'static str,
+    /// Why a changed file differs
+    reason: Option<&'static str>,
+    /// skipped, planned (with --dry-run), uploaded or failed
+    outcome: &'static str,
+}
+
+impl Record for SyncRecord {
+    const COLUMNS: &'static [&'static str] = &["key", "local_path", "change", "reason", "outcome"];
+}
+
+/// An object already in the bucket, as listed
+struct RemoteObject {
+    size: u64,
+    etag: Option<String>,
+}
+
+/// How a local file compares with the bucket
+enum Change {
+    New,
+    /// Differs from the object under its key, for the given reason
+    Changed(&'static str),
+    Unchanged,
+}
+
+pub async fn run(client: S3Store, args: &SyncArgs) -> Result<()> {
+    let upload = &args.upload;
+    let input_dir = upload.input_dir.as_ref().context("--input-dir is required")?;
+    let client = client
+        .with_part_size(upload.part_size_mb * 1024 * 1024)
+        .with_content_sniffing(upload.sniff_content_type)
+        .with_storage_class(upload.storage_class.clone())
+        .with_resume_state(upload.load_resume_state()?);
+
+    status!("Input directory: {:?}", input_dir);
+    let files = find_files(input_dir, &upload.file_filter()?).await?;
+    if files.is_empty() {
+        status!("No files to sync. Exiting.");
+        return Ok(());
+    }
+
+    // Only objects that could share a key with one of our files
+    let list_prefix = upload
+        .name_prefix
+        .as_ref()
+        .map(|prefix| format!("{}_", prefix))
+        .unwrap_or_default();
+    let remote = list_remote(&client, &list_prefix).await?;
+
+    status!("\n📋 Sync plan:");
+    let mut uploads = Vec::new();
+    let mut unchanged = 0;
+    let mut records = Vec::new();
+    for planned in plan_uploads(files, upload.name_prefix.as_deref()) {
+        let key = &planned.object_key;
+        let change = compare(&client, &planned.file_path, key, remote.get(key))
+            .await
+            .with_context(|| format!("Failed to compare {:?} with {}", planned.file_path, key))?;
+        // --force sends unchanged files again as well
+        let is_unchanged = matches!(change, Change::Unchanged) && !upload.force;
+        let (change, reason) = match change {
+            Change::New => {
+                status!("  + {} (new)", key);
+                ("new", None)
+            }
+            Change::Changed(reason) => {
+                status!("  ~ {} ({})", key, reason);
+                ("changed", Some(reason))
+            }
+            Change::Unchanged if upload.force => {
+                status!("  = {} (unchanged, uploading with --force)", key);
+                ("unchanged", None)
+            }
+            Change::Unchanged => ("unchanged", None),
+        };
+        records.push(SyncRecord {
+            key: key.clone(),
+            local_path: planned.file_path.clone(),
+            change,
+            reason,
+            outcome: if is_unchanged { "skipped" } else { "planned" },
+        });
+        if is_unchanged {
+            unchanged += 1;
+            continue;
+        }
+        uploads.push(planned);
+    }
+    status!("  = {} unchanged", unchanged);
+    status!("\n{} to upload, {} already up to date", uploads.len(), unchanged);
+
+    if uploads.is_empty() {
+        status!("\n🎉 Bucket is already in sync!");
+        return print_records(args.output, &records);
+    }
+    if args.dry_run {
+        status!("\nDry run: nothing uploaded.");
+        return print_records(args.output, &records);
+    }
+
+    let uploaded = upload_batch(&client, &uploads, upload).await?;
+    for record in records.iter_mut().filter(|record| record.outcome == "planned") {
+        let succeeded = uploaded.iter().any(|entry| entry.object_key == record.key);
+        record.outcome = if succeeded { "uploaded" } else { "failed" };
+    }
+    print_records(args.output, &records)
+}
+
+/// Size and ETag of every object under `prefix`, by key.
+async fn list_remote(client: &S3Store, prefix: &str) -> Result<HashMap<String, RemoteObject>> {
+    let objects = client.list(prefix).await?;
+    Ok(objects
+        .into_iter()
+        .map(|object| {
+            let remote = RemoteObject {
+                size: object.size,
+                etag: object.e_tag.map(|etag| etag.to_lowercase()),
+            };
+            (object.key, remote)
+        })
+        .collect())
+}
+
+/// Compares a file with the object under its key: by size first, then by the
+/// SHA-256 recorded at upload, falling back to the ETag, which is the MD5 of
+/// the contents for objects not sent as a multipart upload. Files are only
+/// hashed when their size matches.
+async fn compare(client: &S3Store, file_path: &Path, key: &str, remote: Option<&RemoteObject>) -> Result<Change> {
+    let Some(remote) = remote else {
+        return Ok(Change::New);
+    };
+    let size = std::fs::metadata(file_path)
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?
+        .len();
+    if size != remote.size {
+        return Ok(Change::Changed("size differs"));
+    }
+
+    // Deleted since the listing
+    let Some(head) = client.head(key).await? else {
+        return Ok(Change::New);
+    };
+    if let Some(recorded) = head.metadata.get(SHA256_METADATA_KEY) {
+        let sha256 = hash_file(file_path, file_sha256).await?;
+        return Ok(match sha256 == recorded.to_lowercase() {
+            true => Change::Unchanged,
+            false => Change::Changed("SHA-256 differs"),
+        });
+    }
+
+    // Multipart ETags look like `<md5 of part md5s>-<part count>`
+    match &remote.etag {
+        Some(etag) if etag.len() == 32 && !etag.contains('-') => {
+            let md5 = hash_file(file_path, file_md5).await?;
+            Ok(match &md5 == etag {
+                true => Change::Unchanged,
+                false => Change::Changed("ETag differs"),
+            })
+        }
+        _ => Ok(Change::Changed("no checksum to compare")),
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// How far behind the rate a sender may fall and still catch up, e.g. after
+/// waking late from a sleep. Anything longer counts as idle time.
+const CATCH_UP: Duration = Duration::from_millis(250);
+
+/// A transfer rate in bytes per second, written like `500KB`, `10MiB` or
+/// `2GB/s`. Units without an `i` are decimal, those with one binary, and
+/// a bare number is bytes.
+#[derive(Clone, Copy)]
+pub struct Rate(u64);
+
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let text = text.strip_suffix("/s").unwrap_or(text);
+        let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("expected a number followed by a unit such as KB or MiB, got '{}'", text))?;
+        let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "b" => 1.0,
+            "kb" => 1e3,
+            "mb" => 1e6,
+            "gb" => 1e9,
+            "kib" => 1024.0,
+            "mib" => 1024.0 * 1024.0,
+            "gib" => 1024.0 * 1024.0 * 1024.0,
+            other => return Err(format!("unknown unit '{}'; use B, KB, MB, GB, KiB, MiB or GiB", other)),
+        };
+        let bytes_per_sec = (number * multiplier) as u64;
+        if bytes_per_sec == 0 {
+            return Err("rate must be at least 1 byte per second".to_string());
+        }
+        Ok(Self(bytes_per_sec))
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/s", indicatif::HumanBytes(self.0))
+    }
+}
+
+/// Paces bytes sent to a rate, shared by every body it is given to. Each
+/// send reserves the time those bytes take at the rate, after any reserved
+/// before it, and the sender waits until then.
+pub struct Throttle {
+    rate: Rate,
+    /// When the bytes reserved so far will have gone out at the rate
+    next_free: Mutex<Instant>,
+}
+
+impl Throttle {
+    pub fn new(rate: Rate) -> Self {
+        Self {
+            rate,
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserves `bytes` and returns when the sender may go on. An idle
+    /// throttle only saves up `CATCH_UP` worth of bytes, so a burst after a
+    /// pause is paced as well.
+    pub fn reserve(&self, bytes: u64) -> Instant {
+        let mut next_free = self.next_free.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        let start = (*next_free).max(now.checked_sub(CATCH_UP).unwrap_or(now));
+        *next_free = start + Duration::from_secs_f64(bytes as f64 / self.rate.0 as f64);
+        *next_free
+    }
+}
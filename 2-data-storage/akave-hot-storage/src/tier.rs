@@ -0,0 +1,224 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::types::{Tag, Tagging};
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use serde::Serialize;
+
+use crate::S3Store;
+use crate::checksum::SHA256_METADATA_KEY;
+use crate::shard::{CHUNK_INDEX_METADATA_KEY, SHARD_CID_METADATA_KEY};
+use crate::store::ObjectStore;
+
+/// Object tag recording that an object has been handed off for cold storage
+const TIER_TAG_KEY: &str = "storage-tier";
+const COLD_TIER: &str = "cold";
+
+/// Objects looked up or tagged at the same time
+const TIER_CONCURRENCY: usize = 16;
+
+/// SigV4 presigned URLs are valid for at most seven days
+const MAX_URL_EXPIRES_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(clap::Args)]
+pub struct TierArgs {
+    /// Only consider keys starting with this prefix
+    #[arg(long)]
+    prefix: String,
+
+    /// Only consider objects last modified at least this many days ago
+    #[arg(long)]
+    older_than_days: Option<u32>,
+
+    /// Where to write the list of objects to migrate, as JSON
+    #[arg(long)]
+    out: PathBuf,
+
+    /// POST the list as JSON to this migration endpoint; objects are only
+    /// marked if it responds with success
+    #[arg(long)]
+    migration_endpoint: Option<String>,
+
+    /// Tag each listed object storage-tier=cold, so later runs skip it
+    #[arg(long)]
+    mark: bool,
+
+    /// Seconds the download URL of each object stays valid (at most 604800, seven days)
+    #[arg(long, default_value_t = MAX_URL_EXPIRES_SECS, value_parser = clap::value_parser!(u64).range(1..=MAX_URL_EXPIRES_SECS))]
+    url_expires: u64,
+}
+
+/// Objects ready to be stored elsewhere, e.g. in a Filecoin deal
+#[derive(Serialize)]
+struct MigrationList {
+    bucket: String,
+    generated_at: DateTime<Utc>,
+    objects: Vec<MigrationObject>,
+}
+
+#[derive(Serialize)]
+struct MigrationObject {
+    key: String,
+    size: u64,
+    last_modified: Option<DateTime<Utc>>,
+    /// SHA-256 recorded at upload, to verify the cold copy against
+    sha256: Option<String>,
+    shard_cid: Option<String>,
+    chunk_index: Option<usize>,
+    /// Presigned GET URL the migration can fetch the object from
+    url: String,
+}
+
+/// An object under the prefix, as listed
+struct Candidate {
+    key: String,
+    size: u64,
+    last_modified: Option<DateTime<Utc>>,
+}
+
+/// Lists the objects under a prefix not yet handed off for cold storage,
+/// writes them out with what a migration needs to fetch and verify them,
+/// optionally sends the list to a migration endpoint, and marks them.
+pub async fn run(client: &S3Store, args: &TierArgs) -> Result<()> {
+    let cutoff = args
+        .older_than_days
+        .map(|days| Utc::now() - chrono::Duration::days(days.into()));
+    let candidates = list_candidates(client, &args.prefix, cutoff).await?;
+    println!("🔎 Checking {} objects under '{}'...", candidates.len(), args.prefix);
+
+    let url_expires = Duration::from_secs(args.url_expires);
+    let mut objects: Vec<MigrationObject> = stream::iter(candidates)
+        .map(|candidate| describe(client, candidate, url_expires))
+        .buffer_unordered(TIER_CONCURRENCY)
+        .try_filter_map(|object| async move { Ok(object) })
+        .try_collect()
+        .await?;
+    if objects.is_empty() {
+        println!("No objects to migrate.");
+        return Ok(());
+    }
+    objects.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let list = MigrationList {
+        bucket: client.bucket_name.clone(),
+        generated_at: Utc::now(),
+        objects,
+    };
+    let total_size: u64 = list.objects.iter().map(|object| object.size).sum();
+    let json = serde_json::to_string_pretty(&list).context("Failed to serialize migration list")?;
+    std::fs::write(&args.out, json + "\n").with_context(|| format!("Failed to write migration list: {:?}", args.out))?;
+    println!("📝 Migration list written: {:?} ({} objects, {} bytes)", args.out, list.objects.len(), total_size);
+
+    if let Some(endpoint) = &args.migration_endpoint {
+        reqwest::Client::new()
+            .post(endpoint)
+            .json(&list)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Migration endpoint rejected the list: {}", endpoint))?;
+        println!("📨 Sent to migration endpoint: {}", endpoint);
+    }
+
+    if args.mark {
+        stream::iter(&list.objects)
+            .map(|object| mark_cold(client, &object.key))
+            .buffer_unordered(TIER_CONCURRENCY)
+            .try_collect::<Vec<()>>()
+            .await?;
+        println!("🏷️  Tagged {} objects {}={}", list.objects.len(), TIER_TAG_KEY, COLD_TIER);
+    }
+    Ok(())
+}
+
+/// Objects under `prefix`, last modified before `cutoff` if given.
+async fn list_candidates(client: &S3Store, prefix: &str, cutoff: Option<DateTime<Utc>>) -> Result<Vec<Candidate>> {
+    let objects = client.list(prefix).await?;
+    Ok(objects
+        .into_iter()
+        .filter(|object| match cutoff {
+            Some(cutoff) => object.last_modified.is_some_and(|modified| modified <= cutoff),
+            None => true,
+        })
+        .map(|object| Candidate {
+            key: object.key,
+            size: object.size,
+            last_modified: object.last_modified,
+        })
+        .collect())
+}
+
+/// The object's migration entry, or None if it is already tagged cold.
+async fn describe(client: &S3Store, candidate: Candidate, url_expires: Duration) -> Result<Option<MigrationObject>> {
+    let key = &candidate.key;
+    let tagging = client
+        .client
+        .get_object_tagging()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .send()
+        .await
+        .with_context(|| format!("Failed to read tags of object: {}", key))?;
+    let is_cold = tagging
+        .tag_set()
+        .iter()
+        .any(|tag| tag.key() == TIER_TAG_KEY && tag.value() == COLD_TIER);
+    if is_cold {
+        return Ok(None);
+    }
+
+    let head = client
+        .head(key)
+        .await?
+        .with_context(|| format!("Object not found: {}", key))?;
+    let metadata = |name: &str| head.metadata.get(name).cloned();
+
+    let config = PresigningConfig::expires_in(url_expires).context("Invalid presigned URL expiry")?;
+    let url = client
+        .client
+        .get_object()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .presigned(config)
+        .await
+        .with_context(|| format!("Failed to presign URL for: {}", key))?
+        .uri()
+        .to_string();
+
+    Ok(Some(MigrationObject {
+        sha256: metadata(SHA256_METADATA_KEY),
+        shard_cid: metadata(SHARD_CID_METADATA_KEY),
+        chunk_index: metadata(CHUNK_INDEX_METADATA_KEY).and_then(|index| index.parse().ok()),
+        url,
+        key: candidate.key,
+        size: candidate.size,
+        last_modified: candidate.last_modified,
+    }))
+}
+
+/// Tags the object as handed off for cold storage. This replaces any tags
+/// it already had; the uploader doesn't set any.
+async fn mark_cold(client: &S3Store, key: &str) -> Result<()> {
+    let tag = Tag::builder()
+        .key(TIER_TAG_KEY)
+        .value(COLD_TIER)
+        .build()
+        .context("Failed to build storage tier tag")?;
+    let tagging = Tagging::builder()
+        .tag_set(tag)
+        .build()
+        .context("Failed to build storage tier tag")?;
+    client
+        .client
+        .put_object_tagging()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .tagging(tagging)
+        .send()
+        .await
+        .with_context(|| format!("Failed to tag object: {}", key))?;
+    Ok(())
+}
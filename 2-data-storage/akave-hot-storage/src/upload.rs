@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, MetadataDirective, StorageClass};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::checksum::SHA256_METADATA_KEY;
+use crate::cid::{CID_METADATA_KEY, CidHasher};
+use crate::copy::{MAX_COPY_OBJECT_SIZE, copy_source};
+use crate::encryption::{with_copy_source_customer_key, with_customer_key};
+use crate::multipart::{self, DEFAULT_PART_SIZE_MB, MAX_PART_SIZE_MB, MAX_PARTS, MIN_PART_SIZE_MB};
+use crate::output::status;
+use crate::store::ObjectStore;
+use crate::verify::verify_streamed_upload;
+use crate::{ObjectHeaders, S3Store};
+
+/// Stored for data read from stdin unless --content-type says otherwise,
+/// since there's no file name to guess from
+const STDIN_CONTENT_TYPE: &str = "application/octet-stream";
+
+#[derive(clap::Args)]
+pub struct UploadKeyArgs {
+    /// Key to upload the object under
+    #[arg(long)]
+    key: String,
+
+    /// Where to read the object from; only - (stdin) is supported, upload
+    /// files with --input-dir
+    #[arg(value_name = "SOURCE")]
+    source: String,
+
+    /// Content-Type to store with the object
+    #[arg(long, default_value = STDIN_CONTENT_TYPE)]
+    content_type: String,
+
+    /// Part size in MB; input longer than this is sent as a multipart
+    /// upload, buffering one part at a time
+    #[arg(long, default_value_t = DEFAULT_PART_SIZE_MB, value_parser = clap::value_parser!(u64).range(MIN_PART_SIZE_MB..=MAX_PART_SIZE_MB))]
+    part_size_mb: u64,
+
+    /// Storage class to upload the object in
+    #[arg(long, default_value = "STANDARD")]
+    storage_class: StorageClass,
+}
+
+/// The SHA-256 and CID of a stream, computed as it is sent
+#[derive(Default)]
+struct StreamHashes {
+    sha256: Sha256,
+    cid: CidHasher,
+    size: u64,
+}
+
+impl StreamHashes {
+    fn update(&mut self, bytes: &[u8]) {
+        self.sha256.update(bytes);
+        self.cid.update(bytes);
+        self.size += bytes.len() as u64;
+    }
+
+    /// The object metadata recording these hashes.
+    fn finish(self) -> (u64, HashMap<String, String>) {
+        let metadata = HashMap::from([
+            (SHA256_METADATA_KEY.to_string(), hex::encode(self.sha256.finalize())),
+            (CID_METADATA_KEY.to_string(), self.cid.finish()),
+        ]);
+        (self.size, metadata)
+    }
+}
+
+/// Uploads stdin under the key as it is read, so pipeline stages can push
+/// data without staging a temporary file. Input that fits in one part is
+/// sent in a single PUT; anything longer as a multipart upload.
+pub async fn run(client: S3Store, args: &UploadKeyArgs) -> Result<()> {
+    if args.source != "-" {
+        anyhow::bail!("Only - (stdin) can be uploaded to a named key; upload files with --input-dir");
+    }
+    let client = client
+        .with_part_size(args.part_size_mb * 1024 * 1024)
+        .with_storage_class(args.storage_class.clone());
+    let mut headers = ObjectHeaders {
+        content_type: args.content_type.clone(),
+        metadata: HashMap::new(),
+        storage_class: client.storage_class.clone(),
+        encryption: client.encryption.clone(),
+    };
+
+    status!("📥 Uploading stdin -> {}", args.key);
+    let mut stdin = tokio::io::stdin();
+    let mut hashes = StreamHashes::default();
+    let first = read_part(&mut stdin, client.part_size).await?;
+    let (size, etag, metadata_recorded) = if (first.len() as u64) < client.part_size {
+        hashes.update(&first);
+        let (size, metadata) = hashes.finish();
+        headers.metadata = metadata;
+        let etag = client.put(&args.key, ByteStream::from(first), size, &headers).await?;
+        (size, etag, true)
+    } else {
+        let etag = upload_stream_multipart(&client, &args.key, first, &mut stdin, &headers, &mut hashes).await?;
+        let (size, metadata) = hashes.finish();
+        headers.metadata = metadata;
+        // The hashes are only known once everything has been sent, after the
+        // multipart upload fixed the object's metadata, so they are added by
+        // copying the object over itself
+        if size <= MAX_COPY_OBJECT_SIZE {
+            (size, record_metadata(&client, &args.key, &headers).await?, true)
+        } else {
+            status!("⚠️  Over 5 GiB, too large to add its SHA-256 and CID to the object's metadata");
+            (size, etag, false)
+        }
+    };
+
+    let sha256 = &headers.metadata[SHA256_METADATA_KEY];
+    if metadata_recorded {
+        verify_streamed_upload(&client, &args.key, size, sha256).await?;
+    } else {
+        let head = client.head(&args.key).await?.context("Uploaded object not found")?;
+        if head.size != size {
+            anyhow::bail!("{} is {} bytes, but {} were sent", args.key, head.size, size);
+        }
+    }
+    status!(
+        "Successfully uploaded: stdin ({} bytes)\n   Object key: {}\n   Content-Type: {}\n   Storage class: {}\n   Encryption: {}\n   ETag: {}\n   SHA-256: {}\n   CID: {}\n   ✅ Verified {}",
+        size,
+        args.key,
+        headers.content_type,
+        headers.storage_class,
+        headers.encryption,
+        etag,
+        sha256,
+        headers.metadata[CID_METADATA_KEY],
+        if metadata_recorded { "size and checksum" } else { "size" }
+    );
+    Ok(())
+}
+
+/// Reads up to `part_size` bytes, fewer only at the end of the input.
+async fn read_part(reader: &mut (impl AsyncRead + Unpin), part_size: u64) -> Result<Vec<u8>> {
+    let mut part = Vec::new();
+    reader
+        .take(part_size)
+        .read_to_end(&mut part)
+        .await
+        .context("Failed to read stdin")?;
+    Ok(part)
+}
+
+/// Sends `first` and the rest of `reader` as parts of a multipart upload,
+/// one part in memory at a time, and returns the object's ETag. The upload
+/// is aborted if anything fails, since input can't be read again to resume.
+async fn upload_stream_multipart(
+    client: &S3Store,
+    key: &str,
+    first: Vec<u8>,
+    reader: &mut (impl AsyncRead + Unpin),
+    headers: &ObjectHeaders,
+    hashes: &mut StreamHashes,
+) -> Result<String> {
+    let upload_id = multipart::create(client, key, headers).await?;
+    let uploaded = async {
+        let mut parts = Vec::new();
+        let mut part = first;
+        // Input ending on a part boundary leaves an empty read after the last part
+        while !part.is_empty() {
+            let part_number = parts.len() as i32 + 1;
+            if part_number as u64 > MAX_PARTS {
+                anyhow::bail!("Input needs more than {} parts; use a larger --part-size-mb", MAX_PARTS);
+            }
+            hashes.update(&part);
+            let length = part.len();
+            let request = client
+                .client
+                .upload_part()
+                .bucket(&client.bucket_name)
+                .key(key)
+                .upload_id(&upload_id);
+            let uploaded = with_customer_key!(request, headers.encryption)
+                .part_number(part_number)
+                .content_length(length as i64)
+                .body(ByteStream::from(part))
+                .send()
+                .await
+                .with_context(|| format!("Failed to upload part {} of stdin", part_number))?;
+            parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(uploaded.e_tag().map(str::to_string))
+                    .build(),
+            );
+            status!("   {}: part {} uploaded ({} bytes)", key, part_number, length);
+            if (length as u64) < client.part_size {
+                break;
+            }
+            part = read_part(reader, client.part_size).await?;
+        }
+
+        let request = client
+            .client
+            .complete_multipart_upload()
+            .bucket(&client.bucket_name)
+            .key(key)
+            .upload_id(&upload_id);
+        let completed = with_customer_key!(request, headers.encryption)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+            .send()
+            .await
+            .with_context(|| format!("Failed to complete multipart upload of {}", key))?;
+        Ok(completed.e_tag().unwrap_or("unknown").to_string())
+    }
+    .await;
+
+    if uploaded.is_err() {
+        multipart::abort(client, key, &upload_id).await;
+    }
+    uploaded
+}
+
+/// Replaces the object's metadata with `headers`' by copying it over
+/// itself, returning its new ETag.
+async fn record_metadata(client: &S3Store, key: &str, headers: &ObjectHeaders) -> Result<String> {
+    let request = client
+        .client
+        .copy_object()
+        .bucket(&client.bucket_name)
+        .key(key)
+        .copy_source(copy_source(&client.bucket_name, key, None))
+        .metadata_directive(MetadataDirective::Replace)
+        .content_type(&headers.content_type)
+        .set_metadata(Some(headers.metadata.clone()))
+        .storage_class(headers.storage_class.clone())
+        .set_server_side_encryption(headers.encryption.server_side_encryption());
+    let request = with_copy_source_customer_key!(request, headers.encryption);
+    let copied = with_customer_key!(request, headers.encryption)
+        .send()
+        .await
+        .with_context(|| format!("Failed to record SHA-256 and CID of {}", key))?;
+    Ok(copied
+        .copy_object_result()
+        .and_then(|result| result.e_tag())
+        .unwrap_or("unknown")
+        .to_string())
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::S3Store;
+use crate::checksum::{SHA256_METADATA_KEY, file_md5, hash_file};
+use crate::store::{ObjectInfo, ObjectStore};
+
+/// An upload the service accepted, but whose object doesn't match the local
+/// file when read back
+#[derive(Debug)]
+pub struct VerificationError {
+    key: String,
+    problems: Vec<String>,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} does not match what was uploaded: {}", self.key, self.problems.join("; "))
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+/// Fetches the metadata of the object just uploaded under `key` and checks
+/// its size and recorded SHA-256 against the local file. Single-part
+/// uploads are also checked by ETag, which is then the MD5 of the contents,
+/// except under SSE-C where it isn't.
+pub async fn verify_upload(
+    client: &S3Store,
+    file_path: &Path,
+    key: &str,
+    file_size: u64,
+    sha256: &str,
+    multipart: bool,
+) -> Result<()> {
+    let (head, mut problems) = check_object(client, key, file_size, sha256).await?;
+    if !multipart
+        && client.encryption.customer_key().is_none()
+        && let Some(etag) = &head.e_tag
+    {
+        let etag = etag.to_lowercase();
+        let md5 = hash_file(file_path, file_md5).await?;
+        if etag != md5 {
+            problems.push(format!("ETag is {}, expected MD5 {}", etag, md5));
+        }
+    }
+    into_result(key, problems)
+}
+
+/// Checks the size and recorded SHA-256 of an object uploaded from a stream
+/// rather than a file, which has no file to check the ETag against.
+pub async fn verify_streamed_upload(client: &S3Store, key: &str, size: u64, sha256: &str) -> Result<()> {
+    let (_, problems) = check_object(client, key, size, sha256).await?;
+    into_result(key, problems)
+}
+
+/// The uploaded object, and how its size and recorded SHA-256 differ from
+/// what was sent.
+async fn check_object(client: &S3Store, key: &str, size: u64, sha256: &str) -> Result<(ObjectInfo, Vec<String>)> {
+    let head = client
+        .head(key)
+        .await?
+        .with_context(|| format!("Uploaded object not found: {}", key))?;
+
+    let mut problems = Vec::new();
+    if head.size != size {
+        problems.push(format!("size is {} bytes, expected {}", head.size, size));
+    }
+    match head.metadata.get(SHA256_METADATA_KEY) {
+        Some(recorded) if recorded.eq_ignore_ascii_case(sha256) => {}
+        Some(recorded) => problems.push(format!("SHA-256 is {}, expected {}", recorded, sha256)),
+        None => problems.push("SHA-256 metadata missing".to_string()),
+    }
+    Ok((head, problems))
+}
+
+fn into_result(key: &str, problems: Vec<String>) -> Result<()> {
+    if !problems.is_empty() {
+        return Err(VerificationError {
+            key: key.to_string(),
+            problems,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Whether an object identical to the local file is already under `key`:
+/// same size and same SHA-256 recorded at upload. Objects uploaded before
+/// hashes were recorded never count as identical.
+pub async fn is_already_present(client: &S3Store, key: &str, file_size: u64, sha256: &str) -> Result<bool> {
+    let Some(head) = client.head(key).await? else {
+        return Ok(false);
+    };
+    let recorded = head.metadata.get(SHA256_METADATA_KEY);
+    Ok(head.size == file_size && recorded.is_some_and(|recorded| recorded.eq_ignore_ascii_case(sha256)))
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::filter::FileFilter;
+use crate::output::status;
+use crate::shard::index_shard_files;
+use crate::{S3Store, Upload, UploadArgs, object_key, upload_batch};
+
+/// How often files waiting out the debounce period are checked
+const TICK: Duration = Duration::from_millis(250);
+
+/// Watches the input directory and uploads each file that is created or
+/// changed once it has gone `--watch-debounce-ms` without changing again,
+/// so files still being written aren't sent half-finished. Runs until
+/// interrupted with Ctrl-C.
+pub async fn run(client: &S3Store, args: &UploadArgs, input_dir: &Path, filter: &FileFilter) -> Result<()> {
+    let debounce = Duration::from_millis(args.watch_debounce_ms);
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        // Only fails once the receiver is gone, when we're shutting down
+        let _ = sender.send(event);
+    })
+    .context("Failed to start watching for file changes")?;
+    watcher
+        .watch(input_dir, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch directory: {:?}", input_dir))?;
+    status!("\n👀 Watching {:?} for new or changed files (Ctrl-C to stop)...", input_dir);
+
+    // The manifest and resume state may live in the input directory, and
+    // uploading them would rewrite them and trigger another upload
+    let own_files = own_files(args);
+
+    // When each changed file last changed
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+    let mut ticks = tokio::time::interval(TICK);
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event: notify::Event = match event.context("File watcher stopped")? {
+                    Ok(event) => event,
+                    Err(e) => {
+                        status!("⚠️  File watcher error: {}", e);
+                        continue;
+                    }
+                };
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    for path in event.paths {
+                        if filter.is_selected(input_dir, &path) && !own_files.contains(&resolve(&path)) {
+                            pending.insert(path, Instant::now());
+                        }
+                    }
+                }
+            }
+            _ = ticks.tick() => {
+                let settled: Vec<PathBuf> = pending
+                    .iter()
+                    .filter(|(_, changed)| changed.elapsed() >= debounce)
+                    .map(|(path, _)| path.clone())
+                    .collect();
+                for path in &settled {
+                    pending.remove(path);
+                }
+                // Deleted or renamed away while settling, or a directory
+                let files: Vec<PathBuf> = settled.into_iter().filter(|path| path.is_file()).collect();
+                if files.is_empty() {
+                    continue;
+                }
+                status!("\n📥 {} new or changed files", files.len());
+                let uploads = plan_watched_uploads(files, args.name_prefix.as_deref());
+                if let Err(e) = upload_batch(client, &uploads, args).await {
+                    status!("❌ Upload batch failed: {:#}", e);
+                }
+                status!("\n👀 Watching {:?}...", input_dir);
+            }
+            _ = tokio::signal::ctrl_c() => {
+                if !pending.is_empty() {
+                    status!("\n⚠️  {} changed files were not uploaded yet", pending.len());
+                }
+                status!("🛑 Stopped watching {:?}", input_dir);
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The files this tool writes while watching: `--manifest` and
+/// `--resume-state`, and the temporary files each is written through.
+fn own_files(args: &UploadArgs) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in [&args.manifest, &args.resume_state].into_iter().flatten() {
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+        files.push(resolve(path));
+        files.push(resolve(Path::new(&temp_path)));
+    }
+    files
+}
+
+/// Resolves the directory of `path`, so paths given relative to the working
+/// directory compare equal to those the watcher reports. The file itself
+/// may not exist yet, or may already be renamed away.
+fn resolve(path: &Path) -> PathBuf {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (parent.canonicalize(), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Like `plan_uploads`, but also reads any shard metadata next to the
+/// files, so chunks arriving after their `*_metadata.json` are still
+/// recorded as part of their asset.
+fn plan_watched_uploads(files: Vec<PathBuf>, name_prefix: Option<&str>) -> Vec<Upload> {
+    let mut known = files.clone();
+    for dir in files.iter().filter_map(|path| path.parent()) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            if path.to_string_lossy().ends_with("_metadata.json") && !known.contains(&path) {
+                known.push(path);
+            }
+        }
+    }
+
+    let mut shards = index_shard_files(&known);
+    files
+        .into_iter()
+        .map(|file_path| Upload {
+            object_key: object_key(&file_path, name_prefix),
+            shard: shards.remove(&file_path),
+            file_path,
+        })
+        .collect()
+}
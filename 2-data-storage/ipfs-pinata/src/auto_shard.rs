@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use blob_cid_shard::{ChunkSize, FileSharder};
+use serde_json::Value;
+
+use crate::manifest::ManifestChunk;
+use crate::output::{self, status};
+use crate::pin_metadata::{PinataMetadata, SHA256_KEY};
+use crate::progress::FileProgress;
+use crate::service::{PinnedFile, PinningService};
+
+/// When and how to split files that are too large to pin whole
+pub struct ShardSettings {
+    /// Files larger than this many bytes are sharded
+    pub threshold: u64,
+    pub chunk_size: ChunkSize,
+    /// Where chunks are written before upload; each file gets its own subdirectory
+    pub work_dir: PathBuf,
+}
+
+/// A file pinned as chunks plus the blob_cid_shard metadata describing them
+pub struct ShardedUpload {
+    /// Pin of the metadata JSON, which identifies the file as a whole
+    pub metadata_pin: PinnedFile,
+    pub chunks: Vec<ManifestChunk>,
+}
+
+/// Splits a file with blob_cid_shard, pins each chunk and then the metadata
+/// (with each chunk's CID recorded in it), and removes the local chunks once
+/// everything is pinned. Chunk pins are named `<name>_<chunk file>` and carry
+/// the same `shard_cid` and `chunk_index` keyvalues as chunks produced by
+/// running the sharder directly.
+pub async fn pin_sharded(
+    client: &dyn PinningService,
+    file_path: &Path,
+    sha256: &str,
+    metadata: &PinataMetadata,
+    settings: &ShardSettings,
+    progress: &FileProgress,
+) -> Result<ShardedUpload> {
+    // Keyed by content so two inputs with the same name don't share a directory
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+    let work_dir = settings.work_dir.join(format!("{}-{}", file_name, &sha256[..16]));
+
+    let sharder = FileSharder::new(settings.chunk_size, &work_dir, true)
+        .with_context(|| format!("Failed to create shard directory: {:?}", work_dir))?
+        .quiet(output::is_json());
+    let mut shard = sharder
+        .shard_file(file_path)
+        .with_context(|| format!("Failed to shard file: {:?}", file_path))?;
+
+    // Chunks get their own hidden bars; the file's bar advances per chunk
+    let chunk_progress = FileProgress::hidden();
+    let mut chunks = Vec::with_capacity(shard.chunks.len());
+    for (index, chunk) in shard.chunks.iter_mut().enumerate() {
+        let mut keyvalues = metadata.keyvalues.clone();
+        keyvalues.insert("shard_cid".to_string(), Value::from(shard.cid.clone()));
+        keyvalues.insert("chunk_index".to_string(), Value::from(index));
+        // Each chunk records its own hash; the metadata pin keeps the whole file's
+        keyvalues.insert(SHA256_KEY.to_string(), Value::from(chunk.sha256.clone()));
+        let chunk_metadata = PinataMetadata::new(format!("{}_{}", metadata.name, chunk.filename), keyvalues)?;
+
+        let pinned = client
+            .pin_file(&work_dir.join(&chunk.filename), &chunk_metadata, &chunk_progress)
+            .await
+            .with_context(|| format!("Failed to pin chunk {} of {:?}", index, file_path))?;
+        progress.inc(chunk.size);
+
+        chunk.ipfs_hash = Some(pinned.ipfs_hash.clone());
+        chunks.push(ManifestChunk {
+            filename: chunk.filename.clone(),
+            size: chunk.size,
+            sha256: chunk.sha256.clone(),
+            ipfs_hash: pinned.ipfs_hash,
+        });
+    }
+
+    let metadata_path = sharder
+        .save_metadata(&shard, false)
+        .with_context(|| format!("Failed to write shard metadata for {:?}", file_path))?;
+
+    let mut keyvalues = metadata.keyvalues.clone();
+    keyvalues.insert("shard_cid".to_string(), Value::from(shard.cid.clone()));
+    let shard_metadata = PinataMetadata::new(metadata.name.clone(), keyvalues)?;
+    let metadata_pin = client.pin_file(&metadata_path, &shard_metadata, &chunk_progress).await?;
+
+    if let Err(e) = std::fs::remove_dir_all(&work_dir) {
+        status!("⚠️  Could not remove shard directory {:?}: {}", work_dir, e);
+    }
+
+    Ok(ShardedUpload { metadata_pin, chunks })
+}
@@ -0,0 +1,83 @@
+use crate::cid::Cid;
+use crate::unixfs::write_varint;
+
+/// Multicodec for CAR files, used to address uploaded shards
+pub const CAR_CODEC: u64 = 0x0202;
+
+/// Accumulates blocks into a CARv1 file in memory.
+pub struct CarWriter {
+    blocks: Vec<u8>,
+}
+
+impl CarWriter {
+    pub fn new() -> Self {
+        Self { blocks: Vec::new() }
+    }
+
+    /// Size of the blocks written so far, excluding the header
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn write_block(&mut self, cid: &Cid, data: &[u8]) {
+        let cid_bytes = cid.to_bytes();
+        write_varint(&mut self.blocks, (cid_bytes.len() + data.len()) as u64);
+        self.blocks.extend_from_slice(&cid_bytes);
+        self.blocks.extend_from_slice(data);
+    }
+
+    /// Prepends the header listing `roots` and returns the complete CAR.
+    pub fn finish(self, roots: &[Cid]) -> Vec<u8> {
+        let header = encode_header(roots);
+        let mut car = Vec::with_capacity(header.len() + 10 + self.blocks.len());
+        write_varint(&mut car, header.len() as u64);
+        car.extend_from_slice(&header);
+        car.extend_from_slice(&self.blocks);
+        car
+    }
+}
+
+/// DAG-CBOR encoding of `{"roots": [...], "version": 1}`; keys are in
+/// canonical (length-first) order.
+fn encode_header(roots: &[Cid]) -> Vec<u8> {
+    let mut header = vec![0xa2];
+    write_cbor_head(&mut header, 3, 5);
+    header.extend_from_slice(b"roots");
+    write_cbor_head(&mut header, 4, roots.len() as u64);
+    for root in roots {
+        // Tag 42 wraps a CID as a byte string with a leading 0x00
+        header.extend_from_slice(&[0xd8, 0x2a]);
+        let cid_bytes = root.to_bytes();
+        write_cbor_head(&mut header, 2, cid_bytes.len() as u64 + 1);
+        header.push(0x00);
+        header.extend_from_slice(&cid_bytes);
+    }
+    write_cbor_head(&mut header, 3, 7);
+    header.extend_from_slice(b"version");
+    header.push(0x01);
+    header
+}
+
+fn write_cbor_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => out.push(major | value as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::manifest::ManifestEntry;
+
+/// Pipeline stage recorded with each pin, to tell our records apart from
+/// those the sharding and Akave stages add to the same catalog
+const STAGE: &str = "ipfs";
+
+/// One pin in the shared asset catalog
+#[derive(Serialize)]
+struct CatalogRecord<'a> {
+    stage: &'static str,
+    path: &'a Path,
+    sha256: &'a str,
+    cid: &'a str,
+    /// `<root cid>/<relative path>` when pinned as part of a directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ipfs_path: Option<&'a str>,
+    provider: &'a str,
+    /// ISO 8601 time the pin was created
+    timestamp: &'a str,
+}
+
+/// A JSON Lines asset catalog shared by the pipeline stages. Records are only
+/// ever appended, one line each, so several stages can write to it.
+pub struct Catalog {
+    path: PathBuf,
+}
+
+impl Catalog {
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+
+    /// Appends a record for each provider that pinned the file: every
+    /// successful replica, or the single service it was uploaded to.
+    pub fn append(&self, provider: &str, entry: &ManifestEntry) -> Result<()> {
+        let (Some(sha256), Some(cid), Some(timestamp)) = (&entry.sha256, &entry.ipfs_hash, &entry.timestamp) else {
+            return Ok(());
+        };
+        // Other stages run from other directories, so record where the file really is
+        let path = std::fs::canonicalize(&entry.path).unwrap_or_else(|_| entry.path.clone());
+        let record = |provider, cid| CatalogRecord {
+            stage: STAGE,
+            path: &path,
+            sha256,
+            cid,
+            ipfs_path: entry.ipfs_path.as_deref(),
+            provider,
+            timestamp,
+        };
+        let records: Vec<CatalogRecord> = match entry.replicas.is_empty() {
+            true => vec![record(provider, cid)],
+            false => entry
+                .replicas
+                .iter()
+                .filter_map(|replica| Some(record(&replica.provider, replica.ipfs_hash.as_deref()?)))
+                .collect(),
+        };
+
+        // One write for all lines so they don't interleave with other writers
+        let mut lines = String::new();
+        for record in &records {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open catalog: {:?}", self.path))?;
+        file.write_all(lines.as_bytes())
+            .and_then(|_| file.sync_data())
+            .with_context(|| format!("Failed to write catalog: {:?}", self.path))
+    }
+}
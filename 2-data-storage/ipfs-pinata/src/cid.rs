@@ -0,0 +1,63 @@
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::unixfs::write_varint;
+
+/// Multihash code for sha2-256
+const SHA2_256: u64 = 0x12;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A CIDv1 over a sha2-256 digest, the only kind this tool produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cid {
+    codec: u64,
+    digest: [u8; 32],
+}
+
+impl Cid {
+    /// Hashes `bytes` and addresses them with the given multicodec.
+    pub fn sha256(codec: u64, bytes: &[u8]) -> Self {
+        Self {
+            codec,
+            digest: Sha256::digest(bytes).into(),
+        }
+    }
+
+    /// Binary form: version, codec and multihash as varints.
+    pub fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(36);
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, self.codec);
+        write_varint(&mut bytes, SHA2_256);
+        write_varint(&mut bytes, self.digest.len() as u64);
+        bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+}
+
+/// Formats as multibase base32 (`b...`), the default string form for CIDv1.
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.to_bytes();
+        let mut encoded = String::with_capacity(1 + (bytes.len() * 8).div_ceil(5));
+        encoded.push('b');
+
+        let mut buffer = 0u32;
+        let mut bits = 0;
+        for byte in bytes {
+            buffer = (buffer << 8) | byte as u32;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+        }
+
+        f.write_str(&encoded)
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use futures_util::TryStreamExt;
+use futures_util::stream::{self, BoxStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::filter::{FileFilter, IGNORE_FILE_NAME};
+use crate::output::{Event, emit, status};
+
+/// Directories read at the same time while scanning
+const SCAN_CONCURRENCY: usize = 16;
+
+/// Directory listings the scan may run ahead of the uploads
+const SCAN_BUFFER: usize = 64;
+
+/// The files selected for upload in one directory
+pub struct Listing {
+    /// Sorted by path
+    pub files: Vec<PathBuf>,
+    /// Total size of `files`
+    pub bytes: u64,
+    /// Files left out by --include, --exclude or the ignore file
+    pub filtered: usize,
+}
+
+/// Running totals of a scan, reported once it finishes
+#[derive(Default)]
+struct ScanTally {
+    files: usize,
+    filtered: usize,
+    bytes: u64,
+    failed: bool,
+}
+
+/// Scans the input directory in the background, reading several directories
+/// at once, and yields each directory's files as soon as it has been read so
+/// uploads can start before a large tree is fully scanned. Files within a
+/// directory are sorted; directories arrive in roughly path order, depending
+/// on which reads finish first. The scan stops after the first error, and the
+/// totals are reported when the last listing has been taken.
+pub fn scan(input_dir: &Path, filter: FileFilter, exclude: Vec<PathBuf>) -> Result<BoxStream<'static, Result<Listing>>> {
+    if !input_dir.exists() {
+        anyhow::bail!("Input directory does not exist: {:?}", input_dir);
+    }
+
+    if !input_dir.is_dir() {
+        anyhow::bail!("Input path is not a directory: {:?}", input_dir);
+    }
+
+    let (sender, receiver) = mpsc::channel(SCAN_BUFFER);
+    let root = Arc::new(input_dir.to_path_buf());
+    let filter = Arc::new(filter);
+    let exclude = Arc::new(exclude);
+    tokio::spawn(async move {
+        let mut pending = vec![root.as_ref().clone()];
+        let mut reads = JoinSet::new();
+        loop {
+            while reads.len() < SCAN_CONCURRENCY
+                && let Some(dir) = pending.pop()
+            {
+                let (input_dir, filter, exclude) = (root.clone(), filter.clone(), exclude.clone());
+                reads.spawn_blocking(move || read_directory(&input_dir, &dir, &filter, &exclude));
+            }
+            let Some(joined) = reads.join_next().await else {
+                break;
+            };
+            let listing = match joined.context("Directory scan panicked").and_then(|result| result) {
+                Ok((listing, subdirs)) => {
+                    pending.extend(subdirs);
+                    Ok(listing)
+                }
+                Err(e) => Err(e),
+            };
+            let failed = listing.is_err();
+            // Stop when the uploads no longer want listings
+            if sender.send(listing).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let state = (receiver, ScanTally::default(), input_dir.to_path_buf());
+    Ok(Box::pin(stream::unfold(state, |(mut receiver, mut tally, input_dir)| async move {
+        let Some(listing) = receiver.recv().await else {
+            if !tally.failed {
+                report(&input_dir, &tally);
+            }
+            return None;
+        };
+        match &listing {
+            Ok(listing) => {
+                tally.files += listing.files.len();
+                tally.filtered += listing.filtered;
+                tally.bytes += listing.bytes;
+            }
+            Err(_) => tally.failed = true,
+        }
+        Some((listing, (receiver, tally, input_dir)))
+    })))
+}
+
+/// Waits for the whole scan, returning every file sorted by path.
+pub async fn collect(listings: BoxStream<'static, Result<Listing>>) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = listings
+        .map_ok(|listing| listing.files)
+        .try_concat()
+        .await?;
+    files.sort();
+    Ok(files)
+}
+
+/// A file list gathered up front, as a single listing.
+pub fn listing(files: Vec<PathBuf>) -> BoxStream<'static, Result<Listing>> {
+    let bytes = files
+        .iter()
+        .map(|file_path| std::fs::metadata(file_path).map(|metadata| metadata.len()).unwrap_or_default())
+        .sum();
+    Box::pin(stream::iter([Ok(Listing {
+        files,
+        bytes,
+        filtered: 0,
+    })]))
+}
+
+fn report(input_dir: &Path, tally: &ScanTally) {
+    if tally.files == 0 {
+        status!("No files found in directory: {:?}", input_dir);
+    } else {
+        status!("📁 Found {} files to upload", tally.files);
+    }
+    if tally.filtered > 0 {
+        status!("🚫 Filtered out {} files (--include, --exclude, {})", tally.filtered, IGNORE_FILE_NAME);
+    }
+    emit(&Event::ScanCompleted {
+        files: tally.files,
+        filtered: tally.filtered,
+        total_bytes: tally.bytes,
+    });
+}
+
+/// Lists one directory, returning its selected files and its subdirectories.
+/// Symlinks are not followed.
+fn read_directory(
+    input_dir: &Path,
+    dir: &Path,
+    filter: &FileFilter,
+    exclude: &[PathBuf],
+) -> Result<(Listing, Vec<PathBuf>)> {
+    let mut entries = std::fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read directory: {:?}", dir))?;
+    entries.sort_by_key(|entry| entry.path());
+
+    let mut listing = Listing {
+        files: Vec::new(),
+        bytes: 0,
+        filtered: 0,
+    };
+    let mut subdirs = Vec::new();
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("Failed to read directory entry: {:?}", path))?;
+        if file_type.is_dir() {
+            subdirs.push(path);
+        } else if !file_type.is_file() || exclude.contains(&path) {
+            continue;
+        } else if filter.is_selected(input_dir, &path) {
+            listing.bytes += entry.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+            listing.files.push(path);
+        } else {
+            listing.filtered += 1;
+        }
+    }
+
+    // Pending directories are popped from the end, so the first comes out first
+    subdirs.reverse();
+    Ok((listing, subdirs))
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::list::{PinStatus, filter_name_prefix};
+use crate::pin_metadata::parse_keyvalue;
+use crate::pinata::{PinListQuery, PinListRow, PinataClient};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// One row per pin, with keyvalues as a JSON object in the last column
+    Csv,
+    /// An array of pin objects
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct ExportArgs {
+    /// File to write the inventory to
+    #[arg(short, long)]
+    file: PathBuf,
+
+    /// Inventory format [default: from the file extension, otherwise csv]
+    #[arg(long, value_enum)]
+    format: Option<ExportFormat>,
+
+    /// Only export pins whose name starts with this prefix
+    #[arg(long)]
+    name_prefix: Option<String>,
+
+    /// Only export pins with this status
+    #[arg(long, value_enum, default_value_t = PinStatus::Pinned)]
+    status: PinStatus,
+
+    /// Only export pins whose metadata has this keyvalue (repeatable)
+    #[arg(long = "keyvalue", value_name = "KEY=VALUE", value_parser = parse_keyvalue)]
+    keyvalues: Vec<(String, String)>,
+}
+
+/// One pin in the exported inventory
+#[derive(Serialize)]
+struct InventoryRecord<'a> {
+    name: &'a str,
+    cid: &'a str,
+    size: u64,
+    date_pinned: &'a str,
+    date_unpinned: &'a str,
+    keyvalues: &'a BTreeMap<String, Value>,
+}
+
+/// CSV has no nested values, so keyvalues are written as a JSON string
+#[derive(Serialize)]
+struct CsvRecord<'a> {
+    name: &'a str,
+    cid: &'a str,
+    size: u64,
+    date_pinned: &'a str,
+    date_unpinned: &'a str,
+    keyvalues: String,
+}
+
+pub async fn run(client: &PinataClient, args: &ExportArgs) -> Result<()> {
+    let query = PinListQuery {
+        status: Some(args.status.as_str().to_string()),
+        name: args.name_prefix.clone(),
+        keyvalues: args.keyvalues.iter().cloned().collect(),
+        ..Default::default()
+    };
+
+    println!("📋 Fetching pin list...");
+    let rows = filter_name_prefix(client.list_all_pins(&query).await?, args.name_prefix.as_deref());
+
+    let format = args.format.unwrap_or_else(|| format_from_extension(&args.file));
+    let file = File::create(&args.file).with_context(|| format!("Failed to create {:?}", args.file))?;
+    let mut writer = BufWriter::new(file);
+    match format {
+        ExportFormat::Csv => write_csv(&mut writer, &rows)?,
+        ExportFormat::Json => write_json(&mut writer, &rows)?,
+    }
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write {:?}", args.file))?;
+
+    let total_size: u64 = rows.iter().map(|row| row.size).sum();
+    println!("📦 Exported {} pins ({} bytes) to {:?}", rows.len(), total_size, args.file);
+    Ok(())
+}
+
+fn format_from_extension(path: &Path) -> ExportFormat {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("json") => ExportFormat::Json,
+        _ => ExportFormat::Csv,
+    }
+}
+
+fn record(row: &PinListRow) -> InventoryRecord<'_> {
+    static NO_KEYVALUES: BTreeMap<String, Value> = BTreeMap::new();
+    InventoryRecord {
+        name: row.metadata.name.as_deref().unwrap_or_default(),
+        cid: &row.ipfs_pin_hash,
+        size: row.size,
+        date_pinned: row.date_pinned.as_deref().unwrap_or_default(),
+        date_unpinned: row.date_unpinned.as_deref().unwrap_or_default(),
+        keyvalues: row.metadata.keyvalues.as_ref().unwrap_or(&NO_KEYVALUES),
+    }
+}
+
+fn write_csv(writer: impl Write, rows: &[PinListRow]) -> Result<()> {
+    // Headers are written up front so an empty inventory still has them
+    let mut csv = csv::WriterBuilder::new().has_headers(false).from_writer(writer);
+    csv.write_record(["name", "cid", "size", "date_pinned", "date_unpinned", "keyvalues"])
+        .with_context(|| "Failed to write CSV inventory")?;
+    for row in rows {
+        let record = record(row);
+        csv.serialize(CsvRecord {
+            name: record.name,
+            cid: record.cid,
+            size: record.size,
+            date_pinned: record.date_pinned,
+            date_unpinned: record.date_unpinned,
+            keyvalues: serde_json::to_string(record.keyvalues)?,
+        })
+        .with_context(|| "Failed to write CSV inventory")?;
+    }
+    csv.flush().with_context(|| "Failed to write CSV inventory")?;
+    Ok(())
+}
+
+fn write_json(writer: impl Write, rows: &[PinListRow]) -> Result<()> {
+    let records: Vec<InventoryRecord> = rows.iter().map(record).collect();
+    serde_json::to_writer_pretty(writer, &records).with_context(|| "Failed to write JSON inventory")
+}
@@ -0,0 +1,92 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::output::status;
+
+/// Default failed-upload queue filename, written to the input directory
+pub const DEFAULT_FAILED_QUEUE_NAME: &str = ".pinata-failed.jsonl";
+
+/// A file that failed to upload, as recorded in the queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedUpload {
+    pub path: PathBuf,
+    pub error: String,
+    /// ISO 8601 time of the failure
+    pub failed_at: String,
+}
+
+/// Appends a failure to the queue as one JSON line, synced to disk so the
+/// record survives the process being killed mid-batch.
+pub fn append(queue_path: &Path, path: &Path, error: &anyhow::Error) -> Result<()> {
+    let entry = FailedUpload {
+        path: path.to_path_buf(),
+        error: format!("{:#}", error),
+        failed_at: chrono::Utc::now().to_rfc3339(),
+    };
+    // One write per line so concurrent uploads' lines don't interleave
+    let line = format!("{}\n", serde_json::to_string(&entry)?);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(queue_path)
+        .with_context(|| format!("Failed to open failed-upload queue: {:?}", queue_path))?;
+    file.write_all(line.as_bytes())
+        .and_then(|_| file.sync_data())
+        .with_context(|| format!("Failed to write failed-upload queue: {:?}", queue_path))
+}
+
+/// Reads the queue, keeping the latest failure of each file. A line cut off
+/// by a crash is skipped with a warning.
+pub fn load(queue_path: &Path) -> Result<BTreeMap<PathBuf, FailedUpload>> {
+    if !queue_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let contents = std::fs::read_to_string(queue_path)
+        .with_context(|| format!("Failed to read failed-upload queue: {:?}", queue_path))?;
+
+    let mut queue = BTreeMap::new();
+    for (index, line) in contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        match serde_json::from_str::<FailedUpload>(line) {
+            Ok(entry) => {
+                queue.insert(entry.path.clone(), entry);
+            }
+            Err(e) => status!("⚠️  Skipping unreadable line {} of {:?}: {}", index + 1, queue_path, e),
+        }
+    }
+    Ok(queue)
+}
+
+/// Removes files that no longer need retrying, deleting the queue once it is
+/// empty. Rewritten through a temporary file like the upload state.
+pub fn remove(queue_path: &Path, done: &HashSet<&Path>) -> Result<()> {
+    let queue = load(queue_path)?;
+    if !queue.keys().any(|path| done.contains(path.as_path())) {
+        return Ok(());
+    }
+
+    let remaining: Vec<&FailedUpload> = queue
+        .values()
+        .filter(|entry| !done.contains(entry.path.as_path()))
+        .collect();
+    if remaining.is_empty() {
+        return std::fs::remove_file(queue_path)
+            .with_context(|| format!("Failed to remove failed-upload queue: {:?}", queue_path));
+    }
+
+    let mut contents = String::new();
+    for entry in remaining {
+        contents.push_str(&serde_json::to_string(entry)?);
+        contents.push('\n');
+    }
+    let tmp_path = queue_path.with_extension("jsonl.tmp");
+    std::fs::write(&tmp_path, contents)
+        .with_context(|| format!("Failed to write failed-upload queue: {:?}", tmp_path))?;
+    std::fs::rename(&tmp_path, queue_path)
+        .with_context(|| format!("Failed to write failed-upload queue: {:?}", queue_path))
+}
@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use reqwest::multipart::Part;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio_util::codec::{BytesCodec, FramedRead};
+
+use crate::progress::FileProgress;
+
+/// Files up to this size are read into memory instead of streamed
+pub const BUFFERED_UPLOAD_LIMIT: u64 = 1024 * 1024;
+
+/// Builds a multipart part for a file with its length set, so the request is
+/// sent with a Content-Length rather than chunked, which some proxies reject.
+/// Small files are buffered; larger ones are streamed from disk, counting bytes
+/// in `progress` as they are sent.
+pub async fn file_part(file_path: &Path, file_name: String, progress: &FileProgress) -> Result<Part> {
+    let mut file = File::open(file_path)
+        .await
+        .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+    let len = file
+        .metadata()
+        .await
+        .with_context(|| format!("Failed to read file metadata: {:?}", file_path))?
+        .len();
+
+    let part = match len <= BUFFERED_UPLOAD_LIMIT {
+        true => {
+            let mut bytes = Vec::with_capacity(len as usize);
+            file.read_to_end(&mut bytes)
+                .await
+                .with_context(|| format!("Failed to read file: {:?}", file_path))?;
+            progress.inc(bytes.len() as u64);
+            Part::bytes(bytes)
+        }
+        false => {
+            let stream = progress.track(FramedRead::new(file, BytesCodec::new()));
+            Part::stream_with_length(reqwest::Body::wrap_stream(stream), len)
+        }
+    };
+    Ok(part.file_name(file_name))
+}
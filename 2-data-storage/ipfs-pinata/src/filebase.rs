@@ -0,0 +1,213 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use futures_util::future::BoxFuture;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::manifest::file_sha256;
+use crate::output::status;
+use crate::pin_metadata::{PinataMetadata, SHA256_KEY};
+use crate::progress::FileProgress;
+use crate::retry::{ApiError, RetryPolicy, TransientError};
+use crate::service::{PinnedFile, PinningService, required_env};
+
+const FILEBASE_ENDPOINT: &str = "https://s3.filebase.com";
+const FILEBASE_REGION: &str = "us-east-1";
+
+/// Object metadata key under which Filebase reports the pinned CID
+const CID_METADATA_KEY: &str = "cid";
+
+/// Object metadata key holding the pin name, since objects are keyed by content
+const NAME_METADATA_KEY: &str = "name";
+
+/// S3 error codes for throttling, which Filebase may send with a status other than 503
+const THROTTLING_CODES: [&str; 2] = ["SlowDown", "Throttling"];
+
+/// Turns an SDK error into one `RetryPolicy` can classify: throttling and
+/// 5xx responses become an `ApiError` with their status, and failures
+/// without a response, such as connection errors and timeouts, a
+/// `TransientError`.
+fn sdk_error<E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static>(
+    error: SdkError<E, aws_sdk_s3::config::http::HttpResponse>,
+) -> anyhow::Error {
+    let message = DisplayErrorContext(&error).to_string();
+    match &error {
+        SdkError::DispatchFailure(_) | SdkError::TimeoutError(_) => TransientError { service: "Filebase", message }.into(),
+        SdkError::ServiceError(_) | SdkError::ResponseError(_) => {
+            let throttled = error.code().is_some_and(|code| THROTTLING_CODES.contains(&code));
+            let status = match error.raw_response() {
+                _ if throttled => StatusCode::SERVICE_UNAVAILABLE,
+                Some(response) => StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
+                None => StatusCode::BAD_GATEWAY,
+            };
+            ApiError { service: "Filebase", status, message, retry_after: None }.into()
+        }
+        _ => error.into(),
+    }
+}
+/// Pins files by uploading them to a Filebase IPFS bucket through its
+/// S3-compatible API. Filebase pins every object it stores and reports the
+/// CID as object metadata.
+pub struct FilebaseClient {
+    client: Client,
+    bucket: String,
+    retry: RetryPolicy,
+}
+
+impl FilebaseClient {
+    /// Reads FILEBASE_ACCESS_KEY, FILEBASE_SECRET_KEY and FILEBASE_BUCKET.
+    pub fn from_env(retry: RetryPolicy) -> Result<Self> {
+        let credentials = Credentials::new(
+            required_env("FILEBASE_ACCESS_KEY")?,
+            required_env("FILEBASE_SECRET_KEY")?,
+            None,
+            None,
+            "filebase-env",
+        );
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(FILEBASE_ENDPOINT)
+            .region(Region::new(FILEBASE_REGION))
+            .credentials_provider(credentials)
+            .force_path_style(true)
+            .build();
+
+        Ok(Self {
+            client: Client::from_conf(config),
+            bucket: required_env("FILEBASE_BUCKET")?,
+            retry,
+        })
+    }
+
+    /// Looks up the CID Filebase recorded for an object, if the object exists.
+    async fn object_cid(&self, key: &str) -> Result<Option<PinnedFile>> {
+        let head = match self.client.head_object().bucket(&self.bucket).key(key).send().await {
+            Ok(head) => head,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => return Ok(None),
+            Err(e) => return Err(sdk_error(e)).with_context(|| format!("Failed to look up object: {}", key)),
+        };
+
+        let cid = head
+            .metadata()
+            .and_then(|metadata| metadata.get(CID_METADATA_KEY))
+            .with_context(|| format!("Filebase returned no CID for object: {}", key))?;
+        Ok(Some(PinnedFile {
+            ipfs_hash: cid.clone(),
+            pin_size: head.content_length().unwrap_or_default() as u64,
+            timestamp: head.last_modified().map(|date| date.to_string()).unwrap_or_default(),
+            replicas: Vec::new(),
+            regions: Vec::new(),
+        }))
+    }
+
+    async fn try_upload(&self, file_path: &Path, key: &str, metadata: &PinataMetadata) -> Result<PinnedFile> {
+        let body = ByteStream::from_path(file_path)
+            .await
+            .with_context(|| format!("Failed to open file: {:?}", file_path))?;
+
+        // Keyvalues travel as S3 user metadata, which only holds strings
+        let mut request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .metadata(NAME_METADATA_KEY, &metadata.name)
+            .body(body);
+        for (key, value) in &metadata.keyvalues {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                other => other.to_string(),
+            };
+            request = request.metadata(key, value);
+        }
+
+        request
+            .send()
+            .await
+            .map_err(sdk_error)
+            .with_context(|| format!("Failed to upload file: {:?}", file_path))?;
+
+        self.object_cid(key)
+            .await?
+            .with_context(|| format!("Uploaded object not found: {}", key))
+    }
+
+    async fn upload(&self, file_path: &Path, metadata: &PinataMetadata, progress: &FileProgress) -> Result<PinnedFile> {
+        status!("Uploading file: {:?}", file_path);
+
+        // Keyed by the SHA-256 the metadata records, so the key is what
+        // lookups search by; sharded files record the whole file's on the
+        // pin of their shard metadata
+        let key = match metadata.keyvalues.get(SHA256_KEY).and_then(Value::as_str) {
+            Some(sha256) => sha256.to_string(),
+            None => file_sha256(file_path)?,
+        };
+        let pinned = self.retry.run(|| self.try_upload(file_path, &key, metadata)).await?;
+        // The SDK streams the body itself, so progress is reported once it's stored
+        progress.inc(pinned.pin_size);
+
+        status!("Successfully uploaded: {:?}", file_path);
+        status!("   IPFS Hash: {}", pinned.ipfs_hash);
+        status!("   Size: {} bytes", pinned.pin_size);
+
+        Ok(pinned)
+    }
+}
+
+impl PinningService for FilebaseClient {
+    fn name(&self) -> &'static str {
+        "Filebase"
+    }
+
+    fn test_authentication(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            status!("Testing Filebase bucket access...");
+            self.client
+                .head_bucket()
+                .bucket(&self.bucket)
+                .send()
+                .await
+                .map_err(sdk_error)
+                .with_context(|| format!("Authentication failed for bucket: {}", self.bucket))?;
+            status!("✅ Authentication successful!");
+            Ok(())
+        })
+    }
+
+    /// Objects are keyed by the SHA-256 of their content, so files with the
+    /// same name but different content are kept apart, and a re-upload of
+    /// the same content replaces the previous object.
+    fn pin_file<'a>(
+        &'a self,
+        file_path: &'a Path,
+        metadata: &'a PinataMetadata,
+        progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>> {
+        Box::pin(self.upload(file_path, metadata, progress))
+    }
+
+    /// Filebase can only be searched by object key, the content's SHA-256,
+    /// so CID-only lookups return None.
+    fn find_pinned<'a>(&'a self, cid: Option<&'a str>, sha256: Option<&'a str>) -> BoxFuture<'a, Result<Option<PinnedFile>>> {
+        Box::pin(async move {
+            let Some(sha256) = sha256 else {
+                return Ok(None);
+            };
+            Ok(self
+                .object_cid(sha256)
+                .await?
+                .filter(|pinned| cid.is_none_or(|cid| pinned.ipfs_hash == cid)))
+        })
+    }
+
+    /// Filebase pins objects as part of the upload, so there is nothing to wait for.
+    fn wait_for_pin<'a>(&'a self, _cid: &'a str, _timeout: Duration) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(async { Ok(true) })
+    }
+}
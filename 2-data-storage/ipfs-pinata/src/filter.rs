@@ -0,0 +1,65 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Ignore file read from the root of the input directory
+pub const IGNORE_FILE_NAME: &str = ".starlingignore";
+
+/// Decides which files under the input directory are uploaded, from
+/// --include/--exclude globs and the directory's `.starlingignore`.
+pub struct FileFilter {
+    /// None when no --include patterns were given, so everything is included
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    ignore: Gitignore,
+}
+
+impl FileFilter {
+    /// Globs are matched against paths relative to `input_dir`, and `*` also
+    /// matches `/`, so `*.part` selects chunk files in any subdirectory.
+    pub fn new(input_dir: &Path, include: &[String], exclude: &[String]) -> Result<Self> {
+        let include = match include.is_empty() {
+            true => None,
+            false => Some(glob_set(include)?),
+        };
+
+        let mut ignore = GitignoreBuilder::new(input_dir);
+        let ignore_path = input_dir.join(IGNORE_FILE_NAME);
+        if ignore_path.exists()
+            && let Some(e) = ignore.add(&ignore_path)
+        {
+            return Err(e).with_context(|| format!("Failed to parse {:?}", ignore_path));
+        }
+        let ignore = ignore
+            .build()
+            .with_context(|| format!("Failed to parse {:?}", ignore_path))?;
+
+        Ok(Self {
+            include,
+            exclude: glob_set(exclude)?,
+            ignore,
+        })
+    }
+
+    /// Whether the file at `path` (under the input directory) should be uploaded.
+    pub fn is_selected(&self, input_dir: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(input_dir).unwrap_or(path);
+        if self.include.as_ref().is_some_and(|include| !include.is_match(relative)) {
+            return false;
+        }
+        if self.exclude.is_match(relative) {
+            return false;
+        }
+        !self.ignore.matched_path_or_any_parents(path, false).is_ignore()
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))?);
+    }
+    builder.build().with_context(|| "Failed to compile glob patterns")
+}
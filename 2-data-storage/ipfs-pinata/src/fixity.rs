@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use blob_cid_shard::ShardMetadata;
+
+use crate::list::{PinStatus, filter_name_prefix};
+use crate::manifest::{DEFAULT_MANIFEST_NAME, UploadManifest, file_sha256};
+use crate::pin_metadata::SHA256_KEY;
+use crate::pinata::{PinListQuery, PinListRow, PinataClient};
+
+#[derive(clap::Args)]
+#[command(group(clap::ArgGroup::new("source").required(true)))]
+pub struct FixityArgs {
+    /// Check the files of an uploaded batch against their pins, using its upload manifest
+    #[arg(short, long, group = "source")]
+    input_dir: Option<PathBuf>,
+
+    /// Upload manifest of the batch [default: <input-dir>/pinata-manifest.json]
+    #[arg(long, requires = "input_dir")]
+    manifest: Option<PathBuf>,
+
+    /// Check the chunks recorded in a blob_cid_shard metadata file against their pins
+    #[arg(long, group = "source")]
+    shard_metadata: Option<PathBuf>,
+
+    /// Only fetch pins whose name starts with this prefix
+    #[arg(long)]
+    name_prefix: Option<String>,
+}
+
+/// A pinned item and the hash it should have
+struct Check {
+    /// Local path, or `<metadata>#<chunk>` for chunks
+    label: String,
+    cid: String,
+    /// Hash of the local copy; None when the local file is missing
+    expected_sha256: Option<String>,
+}
+
+enum Finding {
+    Ok,
+    /// The recorded and local hashes differ
+    Drift { recorded: String, local: String },
+    NotPinned,
+    /// The pin predates hashes being recorded in its metadata
+    Unrecorded,
+    MissingFile,
+}
+
+pub async fn run(client: &PinataClient, args: &FixityArgs) -> Result<()> {
+    let checks = match (&args.input_dir, &args.shard_metadata) {
+        (Some(input_dir), _) => {
+            let manifest_path = args
+                .manifest
+                .clone()
+                .unwrap_or_else(|| input_dir.join(DEFAULT_MANIFEST_NAME));
+            manifest_checks(&manifest_path)?
+        }
+        (None, Some(metadata_path)) => shard_checks(metadata_path)?,
+        (None, None) => unreachable!("clap requires a source"),
+    };
+
+    println!("📋 Fetching pin list...");
+    let query = PinListQuery {
+        status: Some(PinStatus::Pinned.as_str().to_string()),
+        name: args.name_prefix.clone(),
+        ..Default::default()
+    };
+    let rows = filter_name_prefix(client.list_all_pins(&query).await?, args.name_prefix.as_deref());
+    let pins: HashMap<&str, &PinListRow> = rows.iter().map(|row| (row.ipfs_pin_hash.as_str(), row)).collect();
+
+    println!("🔍 Checking {} items against {} pins\n", checks.len(), pins.len());
+    let (mut ok, mut drift, mut not_pinned, mut unrecorded, mut missing) = (0, 0, 0, 0, 0);
+    for check in &checks {
+        match check_fixity(check, &pins) {
+            Finding::Ok => ok += 1,
+            Finding::Drift { recorded, local } => {
+                drift += 1;
+                println!("🚨 Drift: {} ({})", check.label, check.cid);
+                println!("      pinned sha256: {}", recorded);
+                println!("      local sha256:  {}", local);
+            }
+            Finding::NotPinned => {
+                not_pinned += 1;
+                println!("❌ Not pinned: {} ({})", check.label, check.cid);
+            }
+            Finding::Unrecorded => {
+                unrecorded += 1;
+                println!("⚠️  No sha256 recorded in pin metadata: {} ({})", check.label, check.cid);
+            }
+            Finding::MissingFile => {
+                missing += 1;
+                println!("❌ Local file missing: {} ({})", check.label, check.cid);
+            }
+        }
+    }
+
+    println!("\nFixity Summary:");
+    println!("   ✅ Matching: {}", ok);
+    println!("   🚨 Drift: {}", drift);
+    println!("   ❌ Not pinned: {}", not_pinned);
+    println!("   ❌ Local file missing: {}", missing);
+    println!("   ⚠️  No recorded hash: {}", unrecorded);
+
+    if drift + not_pinned + missing > 0 {
+        anyhow::bail!("{} items failed the fixity check", drift + not_pinned + missing);
+    }
+    Ok(())
+}
+
+fn check_fixity(check: &Check, pins: &HashMap<&str, &PinListRow>) -> Finding {
+    let Some(row) = pins.get(check.cid.as_str()) else {
+        return Finding::NotPinned;
+    };
+    let recorded = row
+        .metadata
+        .keyvalues
+        .as_ref()
+        .and_then(|keyvalues| keyvalues.get(SHA256_KEY))
+        .and_then(|value| value.as_str());
+    match (recorded, &check.expected_sha256) {
+        (None, _) => Finding::Unrecorded,
+        (Some(_), None) => Finding::MissingFile,
+        (Some(recorded), Some(local)) if recorded.eq_ignore_ascii_case(local) => Finding::Ok,
+        (Some(recorded), Some(local)) => Finding::Drift {
+            recorded: recorded.to_string(),
+            local: local.clone(),
+        },
+    }
+}
+
+/// Every pinned file in the manifest, hashed as it is on disk now. Files that
+/// were auto-sharded are checked through their chunks too, against the chunk
+/// hashes recorded at upload time. Files uploaded as part of a directory share
+/// its pin and have no hash of their own, so they are left out.
+fn manifest_checks(manifest_path: &Path) -> Result<Vec<Check>> {
+    let manifest = UploadManifest::read(manifest_path)?
+        .with_context(|| format!("No upload manifest found at {:?}", manifest_path))?;
+
+    let mut checks = Vec::new();
+    for entry in manifest.files.iter().filter(|entry| entry.ipfs_path.is_none()) {
+        let Some(cid) = &entry.ipfs_hash else {
+            continue;
+        };
+        checks.push(Check {
+            label: entry.path.display().to_string(),
+            cid: cid.clone(),
+            expected_sha256: match entry.path.exists() {
+                true => Some(file_sha256(&entry.path)?),
+                false => None,
+            },
+        });
+        for chunk in &entry.chunks {
+            checks.push(Check {
+                label: format!("{}#{}", entry.path.display(), chunk.filename),
+                cid: chunk.ipfs_hash.clone(),
+                expected_sha256: Some(chunk.sha256.clone()),
+            });
+        }
+    }
+    Ok(checks)
+}
+
+/// The chunks of a sharded file that have a CID recorded, with the hashes
+/// blob_cid_shard computed when it wrote them.
+fn shard_checks(metadata_path: &Path) -> Result<Vec<Check>> {
+    let json = std::fs::read_to_string(metadata_path)
+        .with_context(|| format!("Failed to read shard metadata: {:?}", metadata_path))?;
+    let metadata: ShardMetadata = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse shard metadata (is it encrypted?): {:?}", metadata_path))?;
+
+    let unpinned = metadata.chunks.iter().filter(|chunk| chunk.ipfs_hash.is_none()).count();
+    if unpinned > 0 {
+        println!("⚠️  {} chunks have no CID recorded in {:?} and are skipped", unpinned, metadata_path);
+    }
+
+    Ok(metadata
+        .chunks
+        .iter()
+        .filter_map(|chunk| {
+            Some(Check {
+                label: format!("{}#{}", metadata_path.display(), chunk.filename),
+                cid: chunk.ipfs_hash.clone()?,
+                expected_sha256: Some(chunk.sha256.clone()),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+
+/// Fetches pinned content back from an IPFS HTTP gateway to check that the
+/// bytes are actually retrievable and match the local file.
+pub struct GatewayVerifier {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl GatewayVerifier {
+    pub fn new(base_url: &str, timeout: Duration) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .with_context(|| "Failed to build gateway HTTP client")?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Downloads `/ipfs/<ipfs_path>` and returns whether its SHA-256 matches.
+    /// The body is hashed as it streams in rather than buffered.
+    pub async fn verify(&self, ipfs_path: &str, expected_sha256: &str) -> Result<bool> {
+        let url = format!("{}/ipfs/{}", self.base_url, ipfs_path);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {}", url))?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Gateway returned {} for {}", response.status(), url);
+        }
+
+        let mut hasher = Sha256::new();
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.with_context(|| format!("Failed to read {}", url))?;
+            hasher.update(&chunk);
+        }
+
+        Ok(hex::encode(hasher.finalize()) == expected_sha256)
+    }
+}
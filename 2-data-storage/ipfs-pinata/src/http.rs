@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// Timeouts and connection reuse for the pinning services' HTTP clients, so
+/// uploads over slow or flaky links aren't cut off by the defaults.
+#[derive(Debug, Clone, Copy)]
+pub struct HttpSettings {
+    pub connect_timeout: Duration,
+    /// Limit on a whole request including its body; None for no limit
+    pub request_timeout: Option<Duration>,
+    /// How long an unused connection is kept open for reuse
+    pub pool_idle_timeout: Duration,
+    /// Interval of TCP keep-alive probes; None leaves them off
+    pub tcp_keepalive: Option<Duration>,
+    /// Whether connections are kept open between requests at all
+    pub reuse_connections: bool,
+}
+
+impl HttpSettings {
+    pub fn client(&self, service: &str) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(timeout) = self.request_timeout {
+            builder = builder.timeout(timeout);
+        }
+        if !self.reuse_connections {
+            builder = builder.pool_max_idle_per_host(0);
+        }
+        builder
+            .build()
+            .with_context(|| format!("Failed to build {} HTTP client", service))
+    }
+}
@@ -0,0 +1,185 @@
+use std::env;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use reqwest::multipart;
+use serde::Deserialize;
+
+use crate::file_part::file_part;
+use crate::http::HttpSettings;
+use crate::output::status;
+use crate::pin_metadata::PinataMetadata;
+use crate::progress::FileProgress;
+use crate::retry::{ApiError, RetryPolicy};
+use crate::service::{PinnedFile, PinningService};
+use crate::unixfs::{DagParams, KUBO_DAG_PARAMS};
+
+/// RPC address of a local Kubo daemon
+const DEFAULT_KUBO_API_URL: &str = "http://127.0.0.1:5001";
+
+/// Response to /api/v0/add for a single file
+#[derive(Debug, Deserialize)]
+struct AddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+    #[serde(rename = "Size")]
+    size: String,
+}
+
+/// Pins files on a Kubo (go-ipfs) node we run ourselves, through its RPC API.
+pub struct KuboClient {
+    client: reqwest::Client,
+    api_url: String,
+    /// Authorization header for nodes behind an authenticating proxy
+    authorization: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl KuboClient {
+    /// Reads the RPC address from KUBO_API_URL (default http://127.0.0.1:5001)
+    /// and an optional Authorization header value from KUBO_API_AUTH.
+    pub fn from_env(retry: RetryPolicy, http: &HttpSettings) -> Result<Self> {
+        let api_url = env::var("KUBO_API_URL").unwrap_or_else(|_| DEFAULT_KUBO_API_URL.to_string());
+        Ok(Self {
+            client: http.client("Kubo")?,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            authorization: env::var("KUBO_API_AUTH").ok().filter(|value| !value.is_empty()),
+            retry,
+        })
+    }
+
+    /// Every RPC endpoint is a POST, with arguments in the query string.
+    fn rpc(&self, endpoint: &str) -> reqwest::RequestBuilder {
+        let request = self.client.post(format!("{}/api/v0/{}", self.api_url, endpoint));
+        match &self.authorization {
+            Some(authorization) => request.header("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    async fn try_add(&self, file_path: &Path, metadata: &PinataMetadata, progress: &FileProgress) -> Result<PinnedFile> {
+        progress.restart();
+        let form = multipart::Form::new().part("file", file_part(file_path, metadata.name.clone(), progress).await?);
+
+        // CIDv1 with Kubo's default chunker gives the same CID as Pinata
+        let response = self
+            .rpc("add")
+            .query(&[("cid-version", "1"), ("pin", "true"), ("quieter", "true")])
+            .multipart(form)
+            .send()
+            .await
+            .with_context(|| "Failed to send request to Kubo")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let message = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(ApiError::new("Kubo", status, &headers, message).into());
+        }
+
+        let added: AddResponse = response.json().await.with_context(|| "Failed to parse Kubo response")?;
+        Ok(PinnedFile {
+            ipfs_hash: added.hash,
+            pin_size: added.size.parse().unwrap_or_default(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            replicas: Vec::new(),
+            regions: Vec::new(),
+        })
+    }
+
+    async fn add(&self, file_path: &Path, metadata: &PinataMetadata, progress: &FileProgress) -> Result<PinnedFile> {
+        status!("Uploading file: {:?}", file_path);
+
+        let pinned = self.retry.run(|| self.try_add(file_path, metadata, progress)).await?;
+
+        status!("Successfully uploaded: {:?}", file_path);
+        status!("   IPFS Hash: {}", pinned.ipfs_hash);
+        status!("   Size: {} bytes", pinned.pin_size);
+
+        Ok(pinned)
+    }
+
+    async fn is_pinned(&self, cid: &str) -> Result<bool> {
+        let response = self
+            .rpc("pin/ls")
+            .query(&[("arg", cid), ("type", "recursive")])
+            .send()
+            .await
+            .with_context(|| "Failed to send request to Kubo")?;
+
+        // Kubo answers 500 with "is not pinned" for CIDs it doesn't pin
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if status.is_success() {
+            return Ok(true);
+        }
+        if body.contains("not pinned") {
+            return Ok(false);
+        }
+        Err(ApiError {
+            service: "Kubo",
+            status,
+            message: body,
+            retry_after: None,
+        }
+        .into())
+    }
+}
+
+impl PinningService for KuboClient {
+    fn name(&self) -> &'static str {
+        "Kubo"
+    }
+
+    fn dag_params(&self) -> Option<DagParams> {
+        Some(KUBO_DAG_PARAMS)
+    }
+
+    fn test_authentication(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            status!("Testing connection to Kubo at {}...", self.api_url);
+            let response = self
+                .rpc("id")
+                .send()
+                .await
+                .with_context(|| format!("Failed to connect to Kubo at {}", self.api_url))?;
+            if !response.status().is_success() {
+                anyhow::bail!("Kubo RPC returned {}", response.status());
+            }
+            status!("✅ Connected to Kubo!");
+            Ok(())
+        })
+    }
+
+    /// Kubo has no pin names or keyvalues; the name is only used as the filename.
+    fn pin_file<'a>(
+        &'a self,
+        file_path: &'a Path,
+        metadata: &'a PinataMetadata,
+        progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>> {
+        Box::pin(self.add(file_path, metadata, progress))
+    }
+
+    fn find_pinned<'a>(&'a self, cid: Option<&'a str>, _sha256: Option<&'a str>) -> BoxFuture<'a, Result<Option<PinnedFile>>> {
+        Box::pin(async move {
+            let Some(cid) = cid else {
+                return Ok(None);
+            };
+            Ok(self.is_pinned(cid).await?.then(|| PinnedFile {
+                ipfs_hash: cid.to_string(),
+                pin_size: 0,
+                timestamp: String::new(),
+                replicas: Vec::new(),
+                regions: Vec::new(),
+            }))
+        })
+    }
+
+    /// `add` pins before it returns, so there is nothing to wait for.
+    fn wait_for_pin<'a>(&'a self, _cid: &'a str, _timeout: Duration) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(async { Ok(true) })
+    }
+}
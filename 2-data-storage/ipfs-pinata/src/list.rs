@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::pin_metadata::parse_keyvalue;
+use crate::pinata::{MAX_PAGE_LIMIT, PinListQuery, PinListRow, PinataClient};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PinStatus {
+    Pinned,
+    Unpinned,
+    All,
+}
+
+impl PinStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PinStatus::Pinned => "pinned",
+            PinStatus::Unpinned => "unpinned",
+            PinStatus::All => "all",
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct ListArgs {
+    /// Only show pins whose name starts with this prefix
+    #[arg(long)]
+    name_prefix: Option<String>,
+
+    /// Only show pins with this status
+    #[arg(long, value_enum, default_value_t = PinStatus::Pinned)]
+    status: PinStatus,
+
+    /// Only show pins pinned on or after this ISO 8601 date (e.g. 2025-06-01)
+    #[arg(long)]
+    pinned_after: Option<String>,
+
+    /// Only show pins pinned on or before this ISO 8601 date
+    #[arg(long)]
+    pinned_before: Option<String>,
+
+    /// Only show pins whose metadata has this keyvalue (repeatable)
+    #[arg(long = "keyvalue", value_name = "KEY=VALUE", value_parser = parse_keyvalue)]
+    keyvalues: Vec<(String, String)>,
+
+    /// Maximum number of pins to fetch
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..=MAX_PAGE_LIMIT as i64))]
+    limit: u32,
+
+    /// Number of pins to skip, for paging through results
+    #[arg(long, default_value_t = 0)]
+    offset: u32,
+
+    /// Print results as JSON instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+pub async fn run(client: &PinataClient, args: &ListArgs) -> Result<()> {
+    let query = PinListQuery {
+        status: Some(args.status.as_str().to_string()),
+        name: args.name_prefix.clone(),
+        pinned_after: args.pinned_after.clone(),
+        pinned_before: args.pinned_before.clone(),
+        keyvalues: args.keyvalues.iter().cloned().collect::<BTreeMap<_, _>>(),
+        page_limit: args.limit,
+        page_offset: args.offset,
+        ..Default::default()
+    };
+
+    let response = client.list_pins(&query).await?;
+
+    let rows = filter_name_prefix(response.rows, args.name_prefix.as_deref());
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        print_table(&rows);
+        println!("\nShowing {} of {} matching pins", rows.len(), response.count);
+    }
+
+    Ok(())
+}
+
+/// Pinata matches names by substring, so narrow results down to true prefix matches.
+pub fn filter_name_prefix(rows: Vec<PinListRow>, prefix: Option<&str>) -> Vec<PinListRow> {
+    match prefix {
+        Some(prefix) => rows
+            .into_iter()
+            .filter(|row| row.metadata.name.as_deref().unwrap_or("").starts_with(prefix))
+            .collect(),
+        None => rows,
+    }
+}
+
+pub fn print_table(rows: &[PinListRow]) {
+    if rows.is_empty() {
+        println!("No pins found.");
+        return;
+    }
+
+    let cid_width = rows.iter().map(|row| row.ipfs_pin_hash.len()).max().unwrap_or(0).max(3);
+    let size_width = rows.iter().map(|row| row.size.to_string().len()).max().unwrap_or(0).max(4);
+    let date_width = rows
+        .iter()
+        .map(|row| row.date_pinned.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+
+    println!(
+        "{:<cid_width$}  {:>size_width$}  {:<date_width$}  NAME",
+        "CID", "SIZE", "PINNED"
+    );
+    for row in rows {
+        println!(
+            "{:<cid_width$}  {:>size_width$}  {:<date_width$}  {}",
+            row.ipfs_pin_hash,
+            row.size,
+            row.date_pinned.as_deref().unwrap_or("-"),
+            row.metadata.name.as_deref().unwrap_or("-")
+        );
+    }
+}
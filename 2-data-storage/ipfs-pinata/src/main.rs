@@ -1,165 +1,596 @@
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::env;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use dotenv::dotenv;
-use reqwest::multipart;
-use serde::{Deserialize, Serialize};
-use tokio::fs::File;
-use tokio_util::codec::{BytesCodec, FramedRead};
-use walkdir::WalkDir;
+use futures_util::stream::{self, StreamExt};
+use serde_json::Value;
+
+mod auto_shard;
+mod car;
+mod catalog;
+mod cid;
+mod discover;
+mod export;
+mod failed_queue;
+mod filebase;
+mod file_part;
+mod filter;
+mod fixity;
+mod gateway;
+mod http;
+mod kubo;
+mod list;
+mod manifest;
+mod notify;
+mod output;
+mod pin_cid;
+mod pin_metadata;
+mod pinata;
+mod profile;
+mod progress;
+mod replication;
+mod retry;
+mod service;
+mod shard_link;
+mod state;
+mod storacha;
+mod unixfs;
+mod unpin;
+use auto_shard::{ShardSettings, pin_sharded};
+use blob_cid_shard::ChunkSize;
+use export::ExportArgs;
+use catalog::Catalog;
+use failed_queue::DEFAULT_FAILED_QUEUE_NAME;
+use filebase::FilebaseClient;
+use filter::{FileFilter, IGNORE_FILE_NAME};
+use fixity::FixityArgs;
+use gateway::GatewayVerifier;
+use http::HttpSettings;
+use kubo::KuboClient;
+use list::ListArgs;
+use manifest::{DEFAULT_MANIFEST_NAME, ManifestChunk, ManifestEntry, UploadManifest, UploadStatus, file_sha256};
+use notify::{BatchSummary, FailedFile, Notifier};
+use output::{Event, OutputFormat, emit, status};
+use pin_cid::PinCidArgs;
+use pin_metadata::{
+    PinPolicy, PinPolicyRegion, PinataMetadata, PinataOptions, SHA256_KEY, ShardChunkRef, index_shard_chunks,
+    is_shard_manifest, load_keyvalues_file, parse_keyvalue, parse_region,
+};
+use pinata::{PinataAuth, PinataClient};
+use profile::{default_profiles_path, load_credentials_file, load_profile};
+use progress::{BatchProgress, FileProgress};
+use replication::{ReplicatedService, ReplicationPolicy};
+use retry::RetryPolicy;
+use service::{Backend, PinnedFile, PinningService};
+use shard_link::link_chunk_cids;
+use state::{DEFAULT_STATE_NAME, UploadState};
+use storacha::StorachaClient;
+use unixfs::{build_directory_dag, build_file_dag};
+use unpin::{UnpinArgs, UnpinPrefixArgs};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
-struct Args {
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    upload: UploadArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List pins in the Pinata account
+    List(ListArgs),
+    /// Remove pins from the Pinata account
+    Unpin(UnpinArgs),
+    /// Remove every pin whose name starts with a prefix, e.g. to roll back a batch
+    UnpinPrefix(UnpinPrefixArgs),
+    /// Pin content already available on IPFS by its CID
+    PinCid(PinCidArgs),
+    /// Write the full pin list to a CSV or JSON inventory file
+    Export(ExportArgs),
+    /// Compare the SHA-256 recorded in pin metadata with local files or shard metadata
+    Fixity(FixityArgs),
+    /// Check that the Pinata credentials are valid
+    AuthTest,
+    /// Upload only the files recorded in the failed-upload queue, with the same options as an upload
+    RetryFailed(Box<UploadArgs>),
+}
+
+// Arguments for the default upload mode
+#[derive(clap::Args)]
+struct UploadArgs {
     /// Input directory containing BLOBs to upload
-    #[arg(short, long)]
-    input_dir: PathBuf,
+    #[arg(short, long, required = true)]
+    input_dir: Option<PathBuf>,
+
+    /// Pinata credential profile from the profiles file, in place of .env credentials
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Credential profiles file [default: ~/.config/starling/pinata.toml]
+    #[arg(long, global = true)]
+    profiles_file: Option<PathBuf>,
+
+    /// Keyfile with Pinata credentials (mode 0600), in place of .env credentials
+    #[arg(long, global = true, conflicts_with = "profile")]
+    credentials_file: Option<PathBuf>,
+
+    /// Service to upload to
+    #[arg(long, value_enum, default_value_t = Backend::Pinata)]
+    backend: Backend,
+
+    /// JSON replication policy pinning every file to several services, in place of --backend
+    #[arg(long, conflicts_with_all = ["backend", "wrap_with_directory", "as_directory"])]
+    replication_config: Option<PathBuf>,
+
+    /// Only upload files matching this glob, relative to the input directory (repeatable)
+    #[arg(long = "include", value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files matching this glob, relative to the input directory (repeatable)
+    #[arg(long = "exclude", value_name = "GLOB")]
+    exclude: Vec<String>,
 
     /// Optional: Custom name prefix for uploaded files
     #[arg(long)]
     name_prefix: Option<String>,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-struct PinataResponse {
-    #[serde(rename = "IpfsHash")]
-    ipfs_hash: String,
-    #[serde(rename = "PinSize")]
-    pin_size: u64,
-    #[serde(rename = "Timestamp")]
-    timestamp: String,
-}
+    /// Ingest batch ID recorded in each pin's keyvalues
+    #[arg(long)]
+    batch_id: Option<String>,
+
+    /// Extra keyvalue attached to every pin (repeatable)
+    #[arg(long = "keyvalue", value_name = "KEY=VALUE", value_parser = parse_keyvalue)]
+    keyvalues: Vec<(String, String)>,
+
+    /// JSON file with keyvalues attached to every pin
+    #[arg(long)]
+    keyvalues_file: Option<PathBuf>,
+
+    /// CID version Pinata should use for uploaded content (0 or 1)
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(0..=1))]
+    cid_version: u8,
+
+    /// Wrap each uploaded file in a directory so it is addressable as <cid>/<name>
+    #[arg(long)]
+    wrap_with_directory: bool,
+
+    /// Pinata region to replicate each pin to, with its replica count, in place of the account's pin policy (repeatable)
+    #[arg(long = "pinata-region", value_name = "REGION=COUNT", value_parser = parse_region)]
+    pinata_regions: Vec<PinPolicyRegion>,
+
+    /// Number of times to retry a failed upload on network errors or 5xx responses
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Base delay in milliseconds for exponential backoff between retries
+    #[arg(long, default_value_t = 500)]
+    retry_base_ms: u64,
+
+    /// Maximum time in seconds to establish a connection to the service
+    #[arg(long, default_value_t = 30)]
+    connect_timeout_secs: u64,
+
+    /// Maximum time in seconds for a whole request including the upload; 0 for no limit
+    #[arg(long, default_value_t = 0)]
+    request_timeout_secs: u64,
+
+    /// How long in seconds an idle connection is kept open for reuse
+    #[arg(long, default_value_t = 90)]
+    pool_idle_timeout_secs: u64,
+
+    /// Send TCP keep-alive probes at this interval in seconds
+    #[arg(long)]
+    tcp_keepalive_secs: Option<u64>,
+
+    /// Open a new connection for every request instead of reusing idle ones
+    #[arg(long)]
+    no_connection_reuse: bool,
+
+    /// Maximum number of files to upload in parallel
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    concurrency: u64,
+
+    /// Wait for each upload to be reported as pinned before counting it as confirmed
+    #[arg(long)]
+    wait: bool,
+
+    /// Maximum time in seconds to wait for pin confirmation per file
+    #[arg(long, default_value_t = 300)]
+    wait_timeout_secs: u64,
+
+    /// Fetch each pin back from an IPFS gateway and compare it with the local file
+    #[arg(long)]
+    verify_gateway: bool,
+
+    /// Gateway used by --verify-gateway
+    #[arg(long, default_value = "https://ipfs.io")]
+    gateway_url: String,
+
+    /// Maximum time in seconds to fetch a file back from the gateway
+    #[arg(long, default_value_t = 300)]
+    gateway_timeout_secs: u64,
+
+    /// Shard files larger than this many MB with blob_cid_shard and pin the chunks instead
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    shard_threshold_mb: Option<u64>,
+
+    /// Chunk size in MB for --shard-threshold-mb, or "auto" to pick one from the file size
+    #[arg(long, default_value = "auto")]
+    shard_chunk_size: ChunkSize,
+
+    /// Where chunks are written before upload [default: <temp dir>/pinata-shards]
+    #[arg(long)]
+    shard_dir: Option<PathBuf>,
+
+    /// Webhook that receives a JSON summary when the batch finishes or the run fails
+    #[arg(long, value_name = "URL")]
+    notify_url: Option<String>,
+
+    /// Console output: human-readable text, or newline-delimited JSON events
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Path of the upload manifest JSON [default: <input-dir>/pinata-manifest.json]
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// Upload files even if Pinata already has an active pin for them
+    #[arg(long)]
+    force: bool,
+
+    /// Upload the whole input directory in one request as a single root CID
+    #[arg(long)]
+    as_directory: bool,
 
-#[derive(Debug, Serialize, Deserialize)]
-struct PinataError {
-    error: String,
+    /// Resume an interrupted batch, skipping files the state file records as done
+    #[arg(long)]
+    resume: bool,
+
+    /// Path of the upload state file [default: <input-dir>/.pinata-state.json]
+    #[arg(long)]
+    state_file: Option<PathBuf>,
+
+    /// Path of the failed-upload queue [default: <input-dir>/.pinata-failed.jsonl]
+    #[arg(long)]
+    failed_queue: Option<PathBuf>,
+
+    /// JSON Lines asset catalog shared with the other pipeline stages; each new pin is appended to it
+    #[arg(long)]
+    catalog: Option<PathBuf>,
+
+    /// Set by the retry-failed subcommand to upload only the queued files
+    #[arg(skip)]
+    retry_failed: bool,
 }
 
-struct PinataClient {
-    client: reqwest::Client,
-    api_key: String,
-    secret: String,
+/// Result of uploading a single file
+struct UploadOutcome {
+    response: PinnedFile,
+    sha256: String,
+    /// Whether Pinata confirmed the pin; None when not waiting for confirmation
+    confirmed: Option<bool>,
+    /// Whether the upload was skipped because the file was already pinned
+    skipped: bool,
+    /// Whether the file was completed by a previous run, per the state file
+    resumed: bool,
+    /// CID computed locally before upload, when the provider's layout is known
+    expected_cid: Option<String>,
+    /// Whether the gateway copy matched the local file; None when not verified
+    gateway_verified: Option<bool>,
+    /// Chunks pinned in place of the file when it was auto-sharded
+    chunks: Vec<ManifestChunk>,
 }
 
-impl PinataClient {
-    fn new(api_key: String, secret: String) -> Self {
+impl UploadOutcome {
+    /// Rebuilds the outcome of a file completed by a previous run.
+    fn from_entry(entry: &ManifestEntry) -> Self {
         Self {
-            client: reqwest::Client::new(),
-            api_key,
-            secret,
+            response: PinnedFile {
+                ipfs_hash: entry.ipfs_hash.clone().unwrap_or_default(),
+                pin_size: entry.pin_size.unwrap_or_default(),
+                timestamp: entry.timestamp.clone().unwrap_or_default(),
+                replicas: entry.replicas.clone(),
+                regions: entry.regions.clone(),
+            },
+            sha256: entry.sha256.clone().unwrap_or_default(),
+            confirmed: match entry.status {
+                UploadStatus::Pinned => Some(true),
+                UploadStatus::Unconfirmed => Some(false),
+                _ => None,
+            },
+            skipped: entry.status == UploadStatus::Skipped,
+            resumed: true,
+            expected_cid: entry.expected_cid.clone(),
+            gateway_verified: entry.gateway_verified,
+            chunks: entry.chunks.clone(),
         }
     }
 
-    async fn pin_file(&self, file_path: &PathBuf, custom_name: Option<String>) -> Result<PinataResponse> {
-        let file = File::open(file_path)
-            .await
-            .with_context(|| format!("Failed to open file: {:?}", file_path))?;
-
-        let file_name = custom_name.unwrap_or_else(|| {
-            file_path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string()
-        });
-
-        // Create a stream from the file
-        let stream = FramedRead::new(file, BytesCodec::new());
-        let file_body = reqwest::Body::wrap_stream(stream);
-
-        // Create multipart form
-        let form = multipart::Form::new()
-            .part("file", multipart::Part::stream(file_body).file_name(file_name));
-
-        println!("Uploading file: {:?}", file_path);
-
-        let response = self
-            .client
-            .post("https://api.pinata.cloud/pinning/pinFileToIPFS")
-            .header("pinata_api_key", &self.api_key)
-            .header("pinata_secret_api_key", &self.secret)
-            .multipart(form)
-            .send()
-            .await
-            .with_context(|| "Failed to send request to Pinata")?;
-
-        if response.status().is_success() {
-            let pinata_response: PinataResponse = response
-                .json()
-                .await
-                .with_context(|| "Failed to parse Pinata response")?;
-            
-            println!("Successfully uploaded: {:?}", file_path);
-            println!("   IPFS Hash: {}", pinata_response.ipfs_hash);
-            println!("   Size: {} bytes", pinata_response.pin_size);
-            
-            Ok(pinata_response)
-        } else {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            
-            anyhow::bail!("Pinata API error: {}", error_text);
-        }
-    }
-
-    async fn test_authentication(&self) -> Result<()> {
-        println!("Testing Pinata API authentication...");
-        
-        let response = self
-            .client
-            .get("https://api.pinata.cloud/data/testAuthentication")
-            .header("pinata_api_key", &self.api_key)
-            .header("pinata_secret_api_key", &self.secret)
-            .send()
-            .await
-            .with_context(|| "Failed to test authentication")?;
-
-        if response.status().is_success() {
-            println!("✅ Authentication successful!");
-            Ok(())
-        } else {
-            anyhow::bail!("Authentication failed: {}", response.status());
+    /// The expected and returned CIDs, if they differ.
+    fn cid_mismatch(&self) -> Option<(&str, &str)> {
+        match &self.expected_cid {
+            Some(expected) if *expected != self.response.ipfs_hash => Some((expected, &self.response.ipfs_hash)),
+            _ => None,
         }
     }
 }
 
-async fn find_files(input_dir: &PathBuf) -> Result<Vec<PathBuf>> {
-    if !input_dir.exists() {
-        anyhow::bail!("Input directory does not exist: {:?}", input_dir);
-    }
+/// Builds the pin name and keyvalues for a file: the shared keyvalues plus its
+/// source filename and, for blob_cid_shard chunks, the shard CID and chunk index.
+fn build_pin_metadata(
+    file_path: &PathBuf,
+    name_prefix: Option<&str>,
+    shared_keyvalues: &BTreeMap<String, Value>,
+    shard_chunks: &HashMap<PathBuf, ShardChunkRef>,
+) -> Result<PinataMetadata> {
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let name = match name_prefix {
+        Some(prefix) => format!("{}_{}", prefix, file_name),
+        None => file_name.clone(),
+    };
 
-    if !input_dir.is_dir() {
-        anyhow::bail!("Input path is not a directory: {:?}", input_dir);
+    let mut keyvalues = shared_keyvalues.clone();
+    keyvalues.insert("source_file".to_string(), Value::from(file_name));
+    if let Some(chunk) = shard_chunks.get(file_path) {
+        keyvalues.insert("shard_cid".to_string(), Value::from(chunk.shard_cid.clone()));
+        keyvalues.insert("chunk_index".to_string(), Value::from(chunk.chunk_index));
     }
 
-    let mut files = Vec::new();
-    
-    for entry in WalkDir::new(input_dir) {
-        let entry = entry.with_context(|| "Failed to read directory entry")?;
-        
-        if entry.file_type().is_file() {
-            files.push(entry.path().to_path_buf());
+    PinataMetadata::new(name, keyvalues)
+}
+
+/// Settings shared by every file upload in a batch
+#[derive(Clone, Copy)]
+struct UploadContext<'a> {
+    client: &'a dyn PinningService,
+    /// How long to wait for pin confirmation, when --wait is set
+    wait_timeout: Option<Duration>,
+    /// Pinned entries from the previous manifest by sha256; None with --force
+    known_pins: Option<&'a HashMap<String, ManifestEntry>>,
+    gateway: Option<&'a GatewayVerifier>,
+    /// Whether pins are wrapped in a directory, putting files at `<cid>/<name>`
+    wrapped: bool,
+    shard: Option<&'a ShardSettings>,
+}
+
+async fn upload_file(
+    context: UploadContext<'_>,
+    file_path: &PathBuf,
+    metadata: &PinataMetadata,
+    progress: &FileProgress,
+) -> Result<UploadOutcome> {
+    let UploadContext {
+        client,
+        wait_timeout,
+        known_pins,
+        gateway,
+        wrapped,
+        shard,
+    } = context;
+    let sha256 = file_sha256(file_path)?;
+    let metadata = &metadata.with_keyvalue(SHA256_KEY, sha256.clone())?;
+
+    let file_size = std::fs::metadata(file_path)
+        .with_context(|| format!("Failed to read file metadata: {:?}", file_path))?
+        .len();
+    let shard = shard.filter(|shard| file_size > shard.threshold);
+    // Sharded files are pinned as chunks, so there is no single CID to predict
+    let expected_cid = match client.dag_params().filter(|_| shard.is_none()) {
+        Some(params) => Some(build_file_dag(file_path, params)?.root.to_string()),
+        None => None,
+    };
+
+    if let Some(known_pins) = known_pins {
+        // Look pins up by content only: the CID recorded for identical content
+        // in the previous manifest, the SHA-256 keyvalue, then the CID computed
+        // locally. Names aren't used, since the same name can hold other content
+        let known = known_pins.get(&sha256);
+        let mut existing = match known.and_then(|entry| entry.ipfs_hash.as_deref()) {
+            Some(cid) => client.find_pinned(Some(cid), None).await?,
+            None => None,
+        };
+        if existing.is_none() {
+            existing = client.find_pinned(None, Some(&sha256)).await?;
+        }
+        if existing.is_none()
+            && let Some(cid) = &expected_cid
+        {
+            existing = client.find_pinned(Some(cid), None).await?;
+        }
+        if let Some(response) = existing {
+            status!("⏭️  Skipped (already pinned): {:?} ({})", file_path, response.ipfs_hash);
+            return Ok(UploadOutcome {
+                response,
+                sha256,
+                confirmed: None,
+                skipped: true,
+                resumed: false,
+                expected_cid: None,
+                gateway_verified: None,
+                // Carry over the chunk CIDs of a file sharded by an earlier run
+                chunks: known.map(|entry| entry.chunks.clone()).unwrap_or_default(),
+            });
         }
     }
 
-    if files.is_empty() {
-        println!("No files found in directory: {:?}", input_dir);
-    } else {
-        println!("📁 Found {} files to upload", files.len());
+    let (response, expected_cid, chunks) = match shard {
+        Some(shard) => {
+            status!("✂️  Sharding before upload ({} bytes): {:?}", file_size, file_path);
+            let sharded = pin_sharded(client, file_path, &sha256, metadata, shard, progress).await?;
+            status!("✂️  Pinned {} chunks of {:?}:", sharded.chunks.len(), file_path);
+            for chunk in &sharded.chunks {
+                status!("   {}: {}", chunk.filename, chunk.ipfs_hash);
+            }
+            (sharded.metadata_pin, None, sharded.chunks)
+        }
+        None => {
+            let response = client.pin_file(file_path, metadata, progress).await?;
+
+            if let Some(expected) = &expected_cid
+                && *expected != response.ipfs_hash
+            {
+                status!(
+                    "🚨 Integrity error: {} returned {} for {:?}, expected {}",
+                    client.name(),
+                    response.ipfs_hash,
+                    file_path,
+                    expected
+                );
+            }
+            (response, expected_cid, Vec::new())
+        }
+    };
+
+    // A sharded file is pinned as its metadata plus every chunk
+    let mut pinned_cids = vec![response.ipfs_hash.as_str()];
+    pinned_cids.extend(chunks.iter().map(|chunk| chunk.ipfs_hash.as_str()));
+
+    let confirmed = match wait_timeout {
+        Some(timeout) => {
+            let mut confirmed = true;
+            for cid in &pinned_cids {
+                status!("⏳ Waiting for pin confirmation: {}", cid);
+                let pinned = match client.wait_for_pin(cid, timeout).await {
+                    Ok(pinned) => pinned,
+                    Err(e) => {
+                        status!("⚠️  Could not check pin status for {}: {:#}", cid, e);
+                        false
+                    }
+                };
+                if pinned {
+                    status!("🔒 Pin confirmed: {}", cid);
+                } else {
+                    status!("⚠️  Pin not confirmed within {:?}: {}", timeout, cid);
+                }
+                confirmed &= pinned;
+            }
+            Some(confirmed)
+        }
+        None => None,
+    };
+
+    let gateway_verified = match gateway {
+        Some(gateway) => {
+            // The chunks of a sharded file are checked against their own hashes
+            let targets: Vec<(&str, &str, &str)> = match chunks.is_empty() {
+                true => vec![(&response.ipfs_hash, &metadata.name, &sha256)],
+                false => chunks
+                    .iter()
+                    .map(|chunk| (chunk.ipfs_hash.as_str(), chunk.filename.as_str(), chunk.sha256.as_str()))
+                    .collect(),
+            };
+            let mut verified = true;
+            for (cid, name, expected_sha256) in targets {
+                // Wrapped uploads put the file under <cid>/<name>
+                let ipfs_path = match wrapped {
+                    true if chunks.is_empty() => format!("{}/{}", cid, name),
+                    true => format!("{}/{}_{}", cid, metadata.name, name),
+                    false => cid.to_string(),
+                };
+                status!("🌐 Fetching back from gateway: {}", ipfs_path);
+                let matches = match gateway.verify(&ipfs_path, expected_sha256).await {
+                    Ok(matches) => matches,
+                    Err(e) => {
+                        status!("⚠️  Could not fetch {} from gateway: {:#}", ipfs_path, e);
+                        false
+                    }
+                };
+                if matches {
+                    status!("✔️  Gateway content matches: {}", ipfs_path);
+                } else {
+                    status!("🚨 Gateway content does not match local file: {:?} ({})", file_path, ipfs_path);
+                }
+                verified &= matches;
+            }
+            Some(verified)
+        }
+        None => None,
+    };
+
+    Ok(UploadOutcome {
+        response,
+        sha256,
+        confirmed,
+        skipped: false,
+        resumed: false,
+        expected_cid,
+        gateway_verified,
+        chunks,
+    })
+}
+
+fn manifest_entry(file_path: &Path, result: &Result<UploadOutcome>) -> ManifestEntry {
+    match result {
+        Ok(outcome) => ManifestEntry {
+            path: file_path.to_path_buf(),
+            sha256: Some(outcome.sha256.clone()),
+            ipfs_hash: Some(outcome.response.ipfs_hash.clone()),
+            pin_size: Some(outcome.response.pin_size),
+            timestamp: Some(outcome.response.timestamp.clone()),
+            status: match outcome.confirmed {
+                _ if outcome.skipped => UploadStatus::Skipped,
+                Some(true) => UploadStatus::Pinned,
+                Some(false) => UploadStatus::Unconfirmed,
+                None => UploadStatus::Uploaded,
+            },
+            expected_cid: outcome.expected_cid.clone(),
+            gateway_verified: outcome.gateway_verified,
+            ipfs_path: None,
+            chunks: outcome.chunks.clone(),
+            replicas: outcome.response.replicas.clone(),
+            regions: outcome.response.regions.clone(),
+            error: None,
+        },
+        Err(e) => ManifestEntry {
+            path: file_path.to_path_buf(),
+            sha256: file_sha256(file_path).ok(),
+            ipfs_hash: None,
+            pin_size: None,
+            timestamp: None,
+            status: UploadStatus::Failed,
+            expected_cid: None,
+            gateway_verified: None,
+            ipfs_path: None,
+            chunks: Vec::new(),
+            replicas: Vec::new(),
+            regions: Vec::new(),
+            error: Some(format!("{:#}", e)),
+        },
     }
+}
 
-    Ok(files)
+/// Combined size of the files in bytes, for progress reporting
+fn total_size(files: &[PathBuf]) -> u64 {
+    files
+        .iter()
+        .map(|file_path| std::fs::metadata(file_path).map(|metadata| metadata.len()).unwrap_or_default())
+        .sum()
 }
 
-fn load_env_vars() -> Result<(String, String)> {
+/// Loads Pinata credentials, preferring a scoped JWT from PINATA_JWT and
+/// falling back to the legacy PINATA_API_KEY / PINATA_API_SECRET pair.
+fn load_env_vars() -> Result<PinataAuth> {
     // Load .env file if it exists
     dotenv().ok();
 
+    if let Ok(jwt) = env::var("PINATA_JWT") {
+        if jwt.is_empty() {
+            anyhow::bail!("PINATA_JWT cannot be empty");
+        }
+        return Ok(PinataAuth::Jwt(jwt));
+    }
+
     let api_key = env::var("PINATA_API_KEY")
-        .with_context(|| "PINATA_API_KEY environment variable not found. Please set it in your .env file or environment.")?;
+        .with_context(|| "Neither PINATA_JWT nor PINATA_API_KEY environment variable found. Please set one in your .env file or environment.")?;
     
     let secret = env::var("PINATA_API_SECRET")
         .with_context(|| "PINATA_API_SECRET environment variable not found. Please set it in your .env file or environment.")?;
@@ -172,70 +603,727 @@ fn load_env_vars() -> Result<(String, String)> {
         anyhow::bail!("PINATA_API_SECRET cannot be empty");
     }
 
-    Ok((api_key, secret))
+    Ok(PinataAuth::ApiKey { api_key, secret })
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let cli = Cli::parse();
+    let args = &cli.upload;
 
-    println!("🚀 Starting BLOB upload to Pinata IPFS");
-    println!("Input directory: {:?}", args.input_dir);
+    let retry = RetryPolicy::new(args.retries, args.retry_base_ms);
 
-    // Load environment variables
-    let (api_key, secret) = load_env_vars()
-        .with_context(|| "Failed to load Pinata API credentials from environment")?;
+    match cli.command {
+        Some(Command::List(list_args)) => list::run(&pinata_client(args, retry)?, &list_args).await,
+        Some(Command::Unpin(unpin_args)) => unpin::run(&pinata_client(args, retry)?, &unpin_args).await,
+        Some(Command::UnpinPrefix(unpin_args)) => unpin::run(&pinata_client(args, retry)?, &(&unpin_args).into()).await,
+        Some(Command::PinCid(pin_cid_args)) => pin_cid::run(&pinata_client(args, retry)?, &pin_cid_args).await,
+        Some(Command::Export(export_args)) => export::run(&pinata_client(args, retry)?, &export_args).await,
+        Some(Command::Fixity(fixity_args)) => fixity::run(&pinata_client(args, retry)?, &fixity_args).await,
+        Some(Command::AuthTest) => pinata_client(args, retry)?.test_authentication().await,
+        Some(Command::RetryFailed(mut retry_args)) => {
+            retry_args.retry_failed = true;
+            let retry = RetryPolicy::new(retry_args.retries, retry_args.retry_base_ms);
+            upload_batch(&retry_args, retry).await
+        }
+        None => upload_batch(args, retry).await,
+    }
+}
 
-    // Initialize Pinata client
-    let client = PinataClient::new(api_key, secret);
+/// Runs an upload batch, reporting a run that fails outright to --notify-url.
+async fn upload_batch(args: &UploadArgs, retry: RetryPolicy) -> Result<()> {
+    output::set_format(args.output);
+    let notifier = match &args.notify_url {
+        Some(url) => Some(Notifier::new(url, &http_settings(args))?),
+        None => None,
+    };
+    let result = upload(args, retry, notifier.as_ref()).await;
+    if let (Err(e), Some(notifier), Some(input_dir)) = (&result, &notifier, &args.input_dir) {
+        notifier.batch_failed(input_dir, e).await;
+    }
+    result
+}
+
+async fn upload(args: &UploadArgs, retry: RetryPolicy, notifier: Option<&Notifier>) -> Result<()> {
+    let client: Box<dyn PinningService> = match &args.replication_config {
+        Some(config_path) => {
+            let policy = ReplicationPolicy::load(config_path)?;
+            check_pinata_regions(args, &policy.providers)?;
+            // Each provider gets its own rate-limit window
+            let services = policy
+                .providers
+                .iter()
+                .map(|&backend| pinning_service(backend, args, RetryPolicy::new(args.retries, args.retry_base_ms)))
+                .collect::<Result<Vec<_>>>()?;
+            Box::new(ReplicatedService::new(services, policy.min_copies))
+        }
+        None => {
+            check_pinata_regions(args, &[args.backend])?;
+            pinning_service(args.backend, args, retry)?
+        }
+    };
+    run_upload(client.as_ref(), args, notifier).await
+}
+
+/// Region replication is a Pinata pin policy, so it needs Pinata among the backends.
+fn check_pinata_regions(args: &UploadArgs, backends: &[Backend]) -> Result<()> {
+    if !args.pinata_regions.is_empty() && !backends.contains(&Backend::Pinata) {
+        anyhow::bail!("--pinata-region only applies to uploads to Pinata");
+    }
+    Ok(())
+}
+
+fn pinning_service(backend: Backend, args: &UploadArgs, retry: RetryPolicy) -> Result<Box<dyn PinningService>> {
+    Ok(match backend {
+        Backend::Pinata => Box::new(pinata_client(args, retry)?),
+        Backend::Storacha => {
+            dotenv().ok();
+            Box::new(
+                StorachaClient::from_env(retry, &http_settings(args))
+                    .with_context(|| "Failed to load Storacha credentials from environment")?,
+            )
+        }
+        Backend::Filebase => {
+            dotenv().ok();
+            Box::new(
+                FilebaseClient::from_env(retry)
+                    .with_context(|| "Failed to load Filebase credentials from environment")?,
+            )
+        }
+        Backend::Kubo => {
+            dotenv().ok();
+            Box::new(KuboClient::from_env(retry, &http_settings(args))?)
+        }
+    })
+}
+
+fn pinata_client(args: &UploadArgs, retry: RetryPolicy) -> Result<PinataClient> {
+    let auth = match (&args.credentials_file, &args.profile) {
+        (Some(path), _) => {
+            status!("🔑 Using Pinata credentials from {:?}", path);
+            load_credentials_file(path)?
+        }
+        (None, Some(profile)) => {
+            let path = args
+                .profiles_file
+                .clone()
+                .or_else(default_profiles_path)
+                .context("Cannot locate the profiles file: HOME is not set, pass --profiles-file")?;
+            status!("🔑 Using Pinata profile {:?} from {:?}", profile, path);
+            load_profile(&path, profile)?
+        }
+        // Load environment variables
+        (None, None) => load_env_vars().with_context(|| "Failed to load Pinata API credentials from environment")?,
+    };
+
+    let options = PinataOptions {
+        cid_version: args.cid_version,
+        wrap_with_directory: args.wrap_with_directory,
+        custom_pin_policy: (!args.pinata_regions.is_empty()).then(|| PinPolicy {
+            regions: args.pinata_regions.clone(),
+        }),
+    };
+    PinataClient::new(auth, retry, options, &http_settings(args))
+}
+
+fn http_settings(args: &UploadArgs) -> HttpSettings {
+    HttpSettings {
+        connect_timeout: Duration::from_secs(args.connect_timeout_secs),
+        request_timeout: (args.request_timeout_secs > 0).then(|| Duration::from_secs(args.request_timeout_secs)),
+        pool_idle_timeout: Duration::from_secs(args.pool_idle_timeout_secs),
+        tcp_keepalive: args.tcp_keepalive_secs.map(Duration::from_secs),
+        reuse_connections: !args.no_connection_reuse,
+    }
+}
+
+async fn run_upload(client: &dyn PinningService, args: &UploadArgs, notifier: Option<&Notifier>) -> Result<()> {
+    let input_dir = args.input_dir.as_ref().context("--input-dir is required")?;
+
+    status!("🚀 Starting BLOB upload to {} IPFS", client.name());
+    status!("Input directory: {:?}", input_dir);
 
     // Test authentication
     client.test_authentication().await?;
 
-    // Find all files in the input directory
-    let files = find_files(&args.input_dir).await?;
+    let manifest_path = args
+        .manifest
+        .clone()
+        .unwrap_or_else(|| input_dir.join(DEFAULT_MANIFEST_NAME));
 
-    if files.is_empty() {
-        println!("No files to upload. Exiting.");
-        return Ok(());
+    let state_path = args
+        .state_file
+        .clone()
+        .unwrap_or_else(|| input_dir.join(DEFAULT_STATE_NAME));
+
+    let queue_path = args
+        .failed_queue
+        .clone()
+        .unwrap_or_else(|| input_dir.join(DEFAULT_FAILED_QUEUE_NAME));
+
+    // Our own manifest, state, queue, ignore and catalog files are never uploaded
+    let filter = FileFilter::new(input_dir, &args.include, &args.exclude)?;
+    let ignore_path = input_dir.join(IGNORE_FILE_NAME);
+    let mut exclude = vec![manifest_path.clone(), state_path.clone(), queue_path.clone(), ignore_path];
+    exclude.extend(args.catalog.clone());
+
+    // Keyvalues shared by every pin in this batch
+    let mut shared_keyvalues = match &args.keyvalues_file {
+        Some(path) => load_keyvalues_file(path)?,
+        None => BTreeMap::new(),
+    };
+    for (key, value) in &args.keyvalues {
+        shared_keyvalues.insert(key.clone(), Value::from(value.clone()));
+    }
+    if let Some(batch_id) = &args.batch_id {
+        shared_keyvalues.insert("batch_id".to_string(), Value::from(batch_id.clone()));
     }
 
-    println!("\n📤 Starting uploads...\n");
+    // Retrying queued failures and uploading the whole directory need every
+    // file up front; otherwise uploads start while the scan is still running
+    let listings = if args.retry_failed || args.as_directory {
+        if args.retry_failed && args.as_directory {
+            anyhow::bail!("retry-failed can't be combined with --as-directory");
+        }
+        let mut files = discover::collect(discover::scan(input_dir, filter, exclude)?).await?;
 
-    let mut successful_uploads = 0;
-    let mut failed_uploads = 0;
+        if args.retry_failed {
+            let queue = failed_queue::load(&queue_path)?;
+            files.retain(|file_path| queue.contains_key(file_path));
+            status!("🔁 Retrying {} of {} queued failed uploads from {:?}", files.len(), queue.len(), queue_path);
 
-    // Upload each file
-    for (index, file_path) in files.iter().enumerate() {
-        let custom_name = args.name_prefix.as_ref().map(|prefix| {
-            format!("{}_{}", prefix, file_path.file_name().unwrap_or_default().to_string_lossy())
-        });
+            // Queued files that are gone or filtered out have nothing left to retry
+            let missing: HashSet<&Path> = queue
+                .keys()
+                .filter(|path| files.binary_search(path).is_err())
+                .map(PathBuf::as_path)
+                .collect();
+            for path in &missing {
+                status!("⚠️  Dropping queued file that is no longer in the input directory: {:?}", path);
+            }
+            failed_queue::remove(&queue_path, &missing)?;
+        }
+
+        if files.is_empty() {
+            status!("No files to upload. Exiting.");
+            return Ok(());
+        }
+        if args.as_directory {
+            return upload_directory(client, args, input_dir, &files, shared_keyvalues, &manifest_path, notifier)
+                .await;
+        }
+        discover::listing(files)
+    } else {
+        discover::scan(input_dir, filter, exclude)?
+    };
+
+    // Content pinned by a previous run, used to detect files that are already pinned
+    let known_pins = if args.force {
+        None
+    } else {
+        Some(
+            UploadManifest::read(&manifest_path)?
+                .map(|manifest| manifest.known_pins())
+                .unwrap_or_default(),
+        )
+    };
+
+    let state = if args.resume {
+        let state = UploadState::load(&state_path)?;
+        let completed = state.files.keys().filter(|file_path| state.completed(file_path).is_some()).count();
+        status!("↩️  Resuming: {} files already completed", completed);
+        state
+    } else {
+        UploadState::default()
+    };
+    let state = Mutex::new(state);
+
+    let gateway = match args.verify_gateway {
+        true => Some(GatewayVerifier::new(&args.gateway_url, Duration::from_secs(args.gateway_timeout_secs))?),
+        false => None,
+    };
 
-        println!("[{}/{}] Processing file: {:?}", index + 1, files.len(), file_path);
+    let shard = args.shard_threshold_mb.map(|threshold_mb| ShardSettings {
+        threshold: threshold_mb * 1024 * 1024,
+        chunk_size: args.shard_chunk_size,
+        work_dir: args
+            .shard_dir
+            .clone()
+            .unwrap_or_else(|| env::temp_dir().join("pinata-shards")),
+    });
+    // The batch grows as the scan finds files
+    let batch_progress = BatchProgress::new(0, 0);
+    emit(&Event::BatchStarted {
+        input_dir,
+        backend: client.name(),
+        files: None,
+        total_bytes: None,
+    });
 
-        match client.pin_file(file_path, custom_name).await {
-            Ok(_) => {
-                successful_uploads += 1;
+    status!("\n📤 Starting uploads (concurrency: {})...\n", args.concurrency);
+
+    // Chunk files are mapped to their blob_cid_shard metadata as each
+    // directory arrives, which the scan delivers whole. The metadata itself
+    // is held back so its chunks are pinned, and their CIDs can be recorded
+    // in it, before it is pinned
+    let shard_chunks = Mutex::new(HashMap::new());
+    let mut shard_manifests = Vec::new();
+    let mut scan_error = None;
+    let mut total_bytes = 0;
+
+    let name_prefix = args.name_prefix.as_deref();
+    let shared_keyvalues = &shared_keyvalues;
+    let shard_chunks = &shard_chunks;
+    let queue_path = &queue_path;
+    let context = UploadContext {
+        client,
+        wait_timeout: args.wait.then(|| Duration::from_secs(args.wait_timeout_secs)),
+        known_pins: known_pins.as_ref(),
+        gateway: gateway.as_ref(),
+        wrapped: args.wrap_with_directory && args.backend == Backend::Pinata,
+        shard: shard.as_ref(),
+    };
+    let batch_progress = &batch_progress;
+    let state = &state;
+    let state_path = &state_path;
+    let catalog = &args.catalog.as_deref().map(Catalog::new);
+    let processed = &AtomicUsize::new(0);
+    let upload_one = |file_path: PathBuf| async move {
+        let index = processed.fetch_add(1, Ordering::Relaxed) + 1;
+        let result = async {
+            let file_path = &file_path;
+            status!("[{}] Processing file: {:?}", index, file_path);
+            let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+            let file_size = total_size(std::slice::from_ref(file_path));
+            let progress = batch_progress.start_file(&file_name, file_size);
+
+            let completed = state.lock().unwrap().completed(file_path).cloned();
+            if let Some(entry) = completed {
+                let outcome = UploadOutcome::from_entry(&entry);
+                status!("↩️  Already completed: {:?} ({})", file_path, outcome.response.ipfs_hash);
+                status!();
+                emit(&Event::UploadSkipped {
+                    reason: "completed",
+                    entry: &entry,
+                });
+                progress.finish();
+                return Ok(outcome);
             }
-            Err(e) => {
-                println!("❌ Failed to upload {:?}: {}", file_path, e);
-                failed_uploads += 1;
+
+            emit(&Event::UploadStarted {
+                path: file_path,
+                size: file_size,
+            });
+            let linked = match is_shard_manifest(file_path) {
+                true => link_chunk_cids(file_path, &state.lock().unwrap().files),
+                false => Ok(()),
+            };
+            let metadata = linked.and_then(|_| {
+                build_pin_metadata(file_path, name_prefix, shared_keyvalues, &shard_chunks.lock().unwrap())
+            });
+            let result = match metadata {
+                Ok(metadata) => upload_file(context, file_path, &metadata, &progress).await,
+                Err(e) => Err(e),
+            };
+            progress.finish();
+            if let Err(e) = &result {
+                status!("❌ Failed to upload {:?}: {}", file_path, e);
+                if let Err(e) = failed_queue::append(queue_path, file_path, e) {
+                    status!("⚠️  Could not record failed upload: {:#}", e);
+                }
+            }
+
+            let entry = manifest_entry(file_path, &result);
+            match &result {
+                Ok(outcome) if outcome.skipped => emit(&Event::UploadSkipped {
+                    reason: "already_pinned",
+                    entry: &entry,
+                }),
+                Ok(_) => emit(&Event::UploadSucceeded { entry: &entry }),
+                Err(_) => emit(&Event::UploadFailed { entry: &entry }),
             }
+            if let (Some(catalog), Ok(outcome)) = (catalog, &result)
+                && !outcome.skipped
+                && let Err(e) = catalog.append(client.name(), &entry)
+            {
+                status!("⚠️  Could not record pin in catalog: {:#}", e);
+            }
+
+            // Record progress so an interrupted run can be resumed
+            let mut state = state.lock().unwrap();
+            state.files.insert(file_path.clone(), entry);
+            if let Err(e) = state.save(state_path) {
+                status!("⚠️  Could not save upload state: {:#}", e);
+            }
+            drop(state);
+
+            status!(); // Add spacing between files
+            result
         }
+        .await;
+        (file_path, result)
+    };
 
-        println!(); // Add spacing between files
+    // Files are uploaded with at most `concurrency` in flight as the scan finds them
+    let discovered = listings
+        .map(|listing| match listing {
+            Ok(listing) => {
+                total_bytes += listing.bytes;
+                batch_progress.add_files(listing.files.len() as u64, listing.bytes);
+                shard_chunks.lock().unwrap().extend(index_shard_chunks(&listing.files));
+                let (manifests, files): (Vec<PathBuf>, Vec<PathBuf>) =
+                    listing.files.into_iter().partition(|file_path| is_shard_manifest(file_path));
+                shard_manifests.extend(manifests);
+                stream::iter(files)
+            }
+            Err(e) => {
+                scan_error = Some(e);
+                stream::iter(Vec::new())
+            }
+        })
+        .flatten();
+    let mut uploads: Vec<(PathBuf, Result<UploadOutcome>)> =
+        discovered.map(upload_one).buffer_unordered(args.concurrency as usize).collect().await;
+    if let Some(e) = scan_error {
+        batch_progress.finish();
+        return Err(e.context("Stopped scanning the input directory; re-run with --resume to continue the batch"));
+    }
+
+    // Shard metadata starts only once every chunk has finished
+    let metadata_uploads: Vec<(PathBuf, Result<UploadOutcome>)> = stream::iter(shard_manifests)
+        .map(upload_one)
+        .buffer_unordered(args.concurrency as usize)
+        .collect()
+        .await;
+    uploads.extend(metadata_uploads);
+    batch_progress.finish();
+
+    // Report in path order, however the scan and uploads interleaved
+    uploads.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let (files, results): (Vec<PathBuf>, Vec<Result<UploadOutcome>>) = uploads.into_iter().unzip();
+    if files.is_empty() {
+        status!("No files to upload. Exiting.");
+        return Ok(());
+    }
+
+    let failed_files: Vec<&PathBuf> = files
+        .iter()
+        .zip(&results)
+        .filter(|(_, result)| result.is_err())
+        .map(|(file_path, _)| file_path)
+        .collect();
+    let skipped_files = results
+        .iter()
+        .filter(|result| matches!(result, Ok(UploadOutcome { skipped: true, resumed: false, .. })))
+        .count();
+    let resumed_files = results
+        .iter()
+        .filter(|result| matches!(result, Ok(UploadOutcome { resumed: true, .. })))
+        .count();
+    let failed_uploads = failed_files.len();
+    let successful_uploads = results.len() - failed_uploads - skipped_files - resumed_files;
+
+    status!("Upload Summary:");
+    status!("   ✅ Successful: {}", successful_uploads);
+    status!("   ⏭️  Skipped (already pinned): {}", skipped_files);
+    if args.resume {
+        status!("   ↩️  Completed in a previous run: {}", resumed_files);
+    }
+    status!("   ❌ Failed: {}", failed_uploads);
+    let sharded: Vec<&UploadOutcome> = results
+        .iter()
+        .filter_map(|result| result.as_ref().ok())
+        .filter(|outcome| !outcome.chunks.is_empty() && !outcome.skipped)
+        .collect();
+    if !sharded.is_empty() {
+        let chunk_count: usize = sharded.iter().map(|outcome| outcome.chunks.len()).sum();
+        status!("   ✂️  Sharded before upload: {} ({} chunks)", sharded.len(), chunk_count);
+    }
+    let mut replica_counts: Vec<(&str, usize)> = Vec::new();
+    for outcome in results.iter().filter_map(|result| result.as_ref().ok()) {
+        for replica in &outcome.response.replicas {
+            let pinned = usize::from(replica.ipfs_hash.is_some());
+            match replica_counts.iter_mut().find(|(provider, _)| *provider == replica.provider) {
+                Some((_, count)) => *count += pinned,
+                None => replica_counts.push((&replica.provider, pinned)),
+            }
+        }
     }
+    for (provider, count) in &replica_counts {
+        status!("   🌍 Pinned to {}: {}", provider, count);
+    }
+    if args.wait {
+        let confirmed = results
+            .iter()
+            .filter(|result| matches!(result, Ok(UploadOutcome { confirmed: Some(true), resumed: false, .. })))
+            .count();
+        status!("   🔒 Confirmed pinned: {}", confirmed);
+        status!("   ⏳ Unconfirmed: {}", successful_uploads - confirmed);
+        for (file_path, result) in files.iter().zip(&results) {
+            if let Ok(UploadOutcome { response, confirmed: Some(false), resumed: false, .. }) = result {
+                status!("      {:?} ({})", file_path, response.ipfs_hash);
+            }
+        }
+    }
+    let mismatches: Vec<(&PathBuf, (&str, &str))> = files
+        .iter()
+        .zip(&results)
+        .filter_map(|(file_path, result)| Some((file_path, result.as_ref().ok()?.cid_mismatch()?)))
+        .collect();
+    if !mismatches.is_empty() {
+        status!("   🚨 CID mismatches: {}", mismatches.len());
+        for (file_path, (expected, actual)) in &mismatches {
+            status!("      {:?} (expected {}, got {})", file_path, expected, actual);
+        }
+    }
+    if args.verify_gateway {
+        let unverified: Vec<&PathBuf> = files
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| matches!(result, Ok(UploadOutcome { gateway_verified: Some(false), .. })))
+            .map(|(file_path, _)| file_path)
+            .collect();
+        let verified = results
+            .iter()
+            .filter(|result| matches!(result, Ok(UploadOutcome { gateway_verified: Some(true), .. })))
+            .count();
+        status!("   🌐 Gateway verified: {}", verified);
+        status!("   🚨 Gateway mismatches or unreachable: {}", unverified.len());
+        for file_path in &unverified {
+            status!("      {:?}", file_path);
+        }
+    }
+    status!("   📁 Total files: {}", files.len());
+
+    let entries = files
+        .iter()
+        .zip(&results)
+        .map(|(file_path, result)| manifest_entry(file_path, result))
+        .collect();
+    // A retry only covers the failed files, so the rest of the batch is kept
+    let previous = match args.retry_failed {
+        true => UploadManifest::read(&manifest_path)?,
+        false => None,
+    };
+    let mut manifest = previous.unwrap_or_else(|| UploadManifest {
+        input_dir: input_dir.clone(),
+        root_cid: None,
+        files: Vec::new(),
+    });
+    manifest.merge(entries);
+    manifest.write(&manifest_path)?;
 
-    println!("Upload Summary:");
-    println!("   ✅ Successful: {}", successful_uploads);
-    println!("   ❌ Failed: {}", failed_uploads);
-    println!("   📁 Total files: {}", files.len());
+    // Files that made it this time no longer need retrying
+    let uploaded: HashSet<&Path> = files
+        .iter()
+        .zip(&results)
+        .filter(|(_, result)| result.is_ok())
+        .map(|(file_path, _)| file_path.as_path())
+        .collect();
+    failed_queue::remove(queue_path, &uploaded)?;
+    status!("   📝 Manifest: {:?}", manifest_path);
+    emit(&Event::BatchCompleted {
+        successful: successful_uploads,
+        skipped: skipped_files + resumed_files,
+        failed: failed_uploads,
+        total: files.len(),
+        manifest: &manifest_path,
+    });
+    if let Some(notifier) = notifier {
+        notifier
+            .batch_completed(&BatchSummary {
+                input_dir,
+                backend: client.name(),
+                successful: successful_uploads,
+                skipped: skipped_files + resumed_files,
+                failed: failed_uploads,
+                total: files.len(),
+                total_bytes,
+                failed_files: manifest
+                    .files
+                    .iter()
+                    .filter(|entry| entry.status == UploadStatus::Failed && failed_files.contains(&&entry.path))
+                    .map(|entry| FailedFile {
+                        path: &entry.path,
+                        error: entry.error.as_deref().unwrap_or_default(),
+                    })
+                    .collect(),
+                manifest: &manifest_path,
+                root_cid: None,
+            })
+            .await;
+    }
 
     if failed_uploads > 0 {
-        println!("\n⚠️  Some uploads failed. Check the logs above for details.");
+        status!("\nFailed files:");
+        for file_path in &failed_files {
+            status!("   {:?}", file_path);
+        }
+        status!("\n⚠️  Some uploads failed. Check the logs above for details.");
+        status!("🔁 Failed files are queued in {:?}; upload just those again with `retry-failed`.", queue_path);
+    } else if !mismatches.is_empty() {
+        status!("\n🚨 Some pins don't match their locally computed CIDs. Check the logs above for details.");
     } else {
-        println!("\n🎉 All files uploaded successfully!");
+        status!("\n🎉 All files uploaded successfully!");
     }
 
     Ok(())
 }
+
+/// Uploads every file in the input directory in a single request, yielding one
+/// root CID under which each file is addressable by its relative path.
+async fn upload_directory(
+    client: &dyn PinningService,
+    args: &UploadArgs,
+    input_dir: &PathBuf,
+    files: &[PathBuf],
+    mut keyvalues: BTreeMap<String, Value>,
+    manifest_path: &Path,
+    notifier: Option<&Notifier>,
+) -> Result<()> {
+    let dir_name = input_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let name = args.name_prefix.clone().unwrap_or_else(|| dir_name.clone());
+    keyvalues.insert("source_dir".to_string(), Value::from(dir_name));
+    let metadata = PinataMetadata::new(name, keyvalues)?;
+
+    let mut entries = Vec::with_capacity(files.len());
+    for file_path in files {
+        let relative_path = file_path
+            .strip_prefix(input_dir)
+            .unwrap_or(file_path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        entries.push((file_path.clone(), relative_path));
+    }
+
+    // Look the directory up by the root CID computed locally, since the same
+    // name can hold other content; without DAG parameters it is re-uploaded
+    if !args.force
+        && let Some(params) = client.dag_params()
+    {
+        let root_cid = build_directory_dag(&entries, params)?.to_string();
+        if let Some(existing) = client.find_pinned(Some(&root_cid), None).await? {
+            status!("⏭️  Skipped (already pinned): {} ({})", metadata.name, existing.ipfs_hash);
+            let manifest = directory_manifest(input_dir, &entries, &existing, UploadStatus::Skipped)?;
+            manifest.write(manifest_path)?;
+            for entry in &manifest.files {
+                emit(&Event::UploadSkipped {
+                    reason: "already_pinned",
+                    entry,
+                });
+            }
+            status!("   📝 Manifest: {:?}", manifest_path);
+            return Ok(());
+        }
+    }
+
+    let total_bytes = total_size(files);
+    let progress = FileProgress::standalone(&metadata.name, total_bytes);
+    emit(&Event::BatchStarted {
+        input_dir,
+        backend: client.name(),
+        files: Some(files.len()),
+        total_bytes: Some(total_bytes),
+    });
+
+    status!("\n📤 Uploading {} files as a directory...\n", entries.len());
+    let response = client.pin_directory(&entries, &metadata, &progress).await;
+    progress.finish();
+    let response = response?;
+
+    let confirmed = if args.wait {
+        let timeout = Duration::from_secs(args.wait_timeout_secs);
+        status!("⏳ Waiting for pin confirmation: {}", response.ipfs_hash);
+        let confirmed = client.wait_for_pin(&response.ipfs_hash, timeout).await?;
+        if confirmed {
+            status!("🔒 Pin confirmed: {}", response.ipfs_hash);
+        } else {
+            status!("⚠️  Pin not confirmed within {:?}: {}", timeout, response.ipfs_hash);
+        }
+        Some(confirmed)
+    } else {
+        None
+    };
+    let status = match confirmed {
+        Some(true) => UploadStatus::Pinned,
+        Some(false) => UploadStatus::Unconfirmed,
+        None => UploadStatus::Uploaded,
+    };
+
+    let manifest = directory_manifest(input_dir, &entries, &response, status)?;
+    manifest.write(manifest_path)?;
+    for entry in &manifest.files {
+        emit(&Event::UploadSucceeded { entry });
+    }
+    if let Some(catalog) = args.catalog.as_deref().map(Catalog::new) {
+        for entry in &manifest.files {
+            if let Err(e) = catalog.append(client.name(), entry) {
+                status!("⚠️  Could not record pin in catalog: {:#}", e);
+            }
+        }
+    }
+    emit(&Event::BatchCompleted {
+        successful: manifest.files.len(),
+        skipped: 0,
+        failed: 0,
+        total: manifest.files.len(),
+        manifest: manifest_path,
+    });
+    if let Some(notifier) = notifier {
+        notifier
+            .batch_completed(&BatchSummary {
+                input_dir,
+                backend: client.name(),
+                successful: manifest.files.len(),
+                skipped: 0,
+                failed: 0,
+                total: manifest.files.len(),
+                total_bytes,
+                failed_files: Vec::new(),
+                manifest: manifest_path,
+                root_cid: Some(&response.ipfs_hash),
+            })
+            .await;
+    }
+
+    status!("\nUpload Summary:");
+    status!("   📂 Root CID: {}", response.ipfs_hash);
+    status!("   📁 Total files: {}", entries.len());
+    status!("   📝 Manifest: {:?}", manifest_path);
+
+    Ok(())
+}
+
+/// Builds the manifest of a directory pin, recording each file at
+/// `<root cid>/<relative path>`.
+fn directory_manifest(
+    input_dir: &Path,
+    entries: &[(PathBuf, String)],
+    pinned: &PinnedFile,
+    status: UploadStatus,
+) -> Result<UploadManifest> {
+    let mut files = Vec::with_capacity(entries.len());
+    for (file_path, relative_path) in entries {
+        files.push(ManifestEntry {
+            path: file_path.clone(),
+            sha256: Some(file_sha256(file_path)?),
+            ipfs_hash: Some(pinned.ipfs_hash.clone()),
+            pin_size: Some(pinned.pin_size),
+            timestamp: Some(pinned.timestamp.clone()),
+            status,
+            expected_cid: None,
+            gateway_verified: None,
+            ipfs_path: Some(format!("{}/{}", pinned.ipfs_hash, relative_path)),
+            chunks: Vec::new(),
+            replicas: Vec::new(),
+            regions: pinned.regions.clone(),
+            error: None,
+        });
+    }
+    Ok(UploadManifest {
+        input_dir: input_dir.to_path_buf(),
+        root_cid: Some(pinned.ipfs_hash.clone()),
+        files,
+    })
+}
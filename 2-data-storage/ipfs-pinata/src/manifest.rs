@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Default manifest filename, written to the input directory
+pub const DEFAULT_MANIFEST_NAME: &str = "pinata-manifest.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadStatus {
+    /// Uploaded without waiting for pin confirmation
+    Uploaded,
+    /// Uploaded and confirmed pinned with --wait
+    Pinned,
+    /// Uploaded but not confirmed pinned within the --wait timeout
+    Unconfirmed,
+    /// Not uploaded because an active pin already existed
+    Skipped,
+    Failed,
+}
+
+/// One file of an upload batch and where it ended up on IPFS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub sha256: Option<String>,
+    pub ipfs_hash: Option<String>,
+    pub pin_size: Option<u64>,
+    /// Pin timestamp reported by Pinata
+    pub timestamp: Option<String>,
+    pub status: UploadStatus,
+    /// CID computed locally before upload, when the provider's layout is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_cid: Option<String>,
+    /// Whether the content fetched back from the gateway matched `sha256`,
+    /// when verified with --verify-gateway
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway_verified: Option<bool>,
+    /// `<root cid>/<relative path>` when uploaded as part of a directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_path: Option<String>,
+    /// Chunks pinned in place of the file when it exceeded --shard-threshold-mb;
+    /// `ipfs_hash` is then the CID of the shard metadata
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<ManifestChunk>,
+    /// Each provider's copy when uploaded with --replication-config
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replicas: Vec<ManifestReplica>,
+    /// Pinata's replication of the pin per region, when uploaded with --pinata-region
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<ManifestRegion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One chunk of an auto-sharded file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestChunk {
+    pub filename: String,
+    pub size: u64,
+    pub sha256: String,
+    pub ipfs_hash: String,
+}
+
+/// One provider's copy of a replicated file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestReplica {
+    pub provider: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipfs_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Replication of a pin in one Pinata region, as reported after upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestRegion {
+    pub region_id: String,
+    pub desired_replication_count: u32,
+    pub current_replication_count: u32,
+}
+
+/// Maps the local files of an upload batch to their IPFS hashes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadManifest {
+    pub input_dir: PathBuf,
+    /// CID of the directory when the batch was uploaded with --as-directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_cid: Option<String>,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl UploadManifest {
+    /// Reads a manifest from a previous run, if one exists.
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read manifest: {:?}", path))?;
+        let manifest = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse manifest: {:?}", path))?;
+        Ok(Some(manifest))
+    }
+
+    /// Maps file sha256 to its entry, for files that were pinned.
+    pub fn known_pins(&self) -> HashMap<String, ManifestEntry> {
+        self.files
+            .iter()
+            .filter(|entry| entry.status != UploadStatus::Failed && entry.ipfs_hash.is_some())
+            .filter_map(|entry| Some((entry.sha256.clone()?, entry.clone())))
+            .collect()
+    }
+
+    /// Replaces the entries of files that were uploaded again, keeping the rest
+    /// of the batch as it was.
+    pub fn merge(&mut self, entries: Vec<ManifestEntry>) {
+        for entry in entries {
+            match self.files.iter_mut().find(|existing| existing.path == entry.path) {
+                Some(existing) => *existing = entry,
+                None => self.files.push(entry),
+            }
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write manifest: {:?}", path))
+    }
+}
+
+/// Hex-encoded SHA-256 of a file's contents, streamed so large files aren't read into memory.
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {:?}", path))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).with_context(|| format!("Failed to hash file: {:?}", path))?;
+    Ok(hex::encode(hasher.finalize()))
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::http::HttpSettings;
+use crate::output::status;
+
+/// Outcome of a finished batch, as sent to --notify-url
+#[derive(Serialize)]
+pub struct BatchSummary<'a> {
+    pub input_dir: &'a Path,
+    pub backend: &'static str,
+    pub successful: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub total: usize,
+    pub total_bytes: u64,
+    pub failed_files: Vec<FailedFile<'a>>,
+    pub manifest: &'a Path,
+    /// CID of the directory when the batch was uploaded with --as-directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_cid: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+pub struct FailedFile<'a> {
+    pub path: &'a Path,
+    pub error: &'a str,
+}
+
+/// Webhook body. `text` is what Slack-compatible incoming webhooks display;
+/// other receivers can use the structured fields.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Notification<'a> {
+    BatchCompleted {
+        text: String,
+        #[serde(flatten)]
+        summary: &'a BatchSummary<'a>,
+    },
+    /// The run stopped before finishing the batch, e.g. on bad credentials
+    BatchFailed {
+        text: String,
+        input_dir: &'a Path,
+        error: String,
+    },
+}
+
+/// POSTs a JSON summary to a webhook when a batch finishes, so failed runs
+/// are reported without anyone watching the terminal. Delivery problems are
+/// only logged; they never fail the batch.
+pub struct Notifier {
+    client: reqwest::Client,
+    url: reqwest::Url,
+}
+
+impl Notifier {
+    pub fn new(url: &str, http: &HttpSettings) -> Result<Self> {
+        Ok(Self {
+            client: http.client("webhook")?,
+            url: url.parse().with_context(|| format!("Invalid --notify-url: {}", url))?,
+        })
+    }
+
+    pub async fn batch_completed(&self, summary: &BatchSummary<'_>) {
+        let text = match summary.failed {
+            0 => format!(
+                "✅ {} of {} files from {} pinned to {}",
+                summary.successful + summary.skipped,
+                summary.total,
+                summary.input_dir.display(),
+                summary.backend
+            ),
+            failed => format!(
+                "❌ {} of {} files from {} failed to pin to {}",
+                failed,
+                summary.total,
+                summary.input_dir.display(),
+                summary.backend
+            ),
+        };
+        self.post(&Notification::BatchCompleted { text, summary }).await;
+    }
+
+    pub async fn batch_failed(&self, input_dir: &Path, error: &anyhow::Error) {
+        let text = format!("❌ Upload of {} failed: {:#}", input_dir.display(), error);
+        self.post(&Notification::BatchFailed {
+            text,
+            input_dir,
+            error: format!("{:#}", error),
+        })
+        .await;
+    }
+
+    async fn post(&self, notification: &Notification<'_>) {
+        let result = self.client.post(self.url.clone()).json(notification).send().await;
+        match result.and_then(|response| response.error_for_status()) {
+            Ok(_) => status!("📣 Sent batch notification to {}", self.url.host_str().unwrap_or_default()),
+            // The URL often embeds a secret token, so keep it out of the log
+            Err(e) => status!("⚠️  Could not send batch notification: {}", e.without_url()),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::Path;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::manifest::ManifestEntry;
+
+/// How upload progress is reported on stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable status lines
+    Text,
+    /// Newline-delimited JSON events, one per line
+    Json,
+}
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Selects the output format for the rest of the run. Text is used if this is
+/// never called, as for the subcommands.
+pub fn set_format(format: OutputFormat) {
+    FORMAT.set(format).expect("output format is set once");
+}
+
+pub fn is_json() -> bool {
+    FORMAT.get() == Some(&OutputFormat::Json)
+}
+
+/// Prints a human-readable status line, which is suppressed in JSON mode so
+/// stdout carries only events.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_json() {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use status;
+
+/// Machine-readable upload events for --output json
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// Files and bytes are only known up front for --as-directory uploads;
+    /// otherwise uploads start while the input directory is still being scanned
+    BatchStarted {
+        input_dir: &'a Path,
+        backend: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        files: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total_bytes: Option<u64>,
+    },
+    ScanCompleted {
+        files: usize,
+        filtered: usize,
+        total_bytes: u64,
+    },
+    UploadStarted {
+        path: &'a Path,
+        size: u64,
+    },
+    UploadSucceeded {
+        #[serde(flatten)]
+        entry: &'a ManifestEntry,
+    },
+    /// Not uploaded: `reason` is `already_pinned` or `completed` (by a run being resumed)
+    UploadSkipped {
+        reason: &'static str,
+        #[serde(flatten)]
+        entry: &'a ManifestEntry,
+    },
+    UploadFailed {
+        #[serde(flatten)]
+        entry: &'a ManifestEntry,
+    },
+    Retrying {
+        attempt: u32,
+        delay_ms: u64,
+        error: String,
+    },
+    RateLimited {
+        delay_ms: u64,
+        error: String,
+    },
+    BatchCompleted {
+        successful: usize,
+        skipped: usize,
+        failed: usize,
+        total: usize,
+        manifest: &'a Path,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    /// When the event was emitted; `timestamp` is taken by manifest entries
+    time: String,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Writes the event as one JSON line in JSON mode; a no-op otherwise.
+pub fn emit(event: &Event) {
+    if !is_json() {
+        return;
+    }
+    let record = Record {
+        time: chrono::Utc::now().to_rfc3339(),
+        event,
+    };
+    match serde_json::to_string(&record) {
+        Ok(line) => println!("{}", line),
+        Err(e) => eprintln!("Failed to serialize event: {}", e),
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::pin_metadata::{PinataMetadata, parse_keyvalue};
+use crate::pinata::PinataClient;
+
+#[derive(clap::Args)]
+pub struct PinCidArgs {
+    /// CID of content already available on IPFS (repeatable)
+    #[arg(long = "cid", value_name = "CID", required = true)]
+    cids: Vec<String>,
+
+    /// Pin name; defaults to the CID, and is suffixed with the CID when pinning several
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Ingest batch ID recorded in each pin's keyvalues
+    #[arg(long)]
+    batch_id: Option<String>,
+
+    /// Extra keyvalue attached to every pin (repeatable)
+    #[arg(long = "keyvalue", value_name = "KEY=VALUE", value_parser = parse_keyvalue)]
+    keyvalues: Vec<(String, String)>,
+
+    /// Multiaddr of a peer known to hold the content, to speed up the search (repeatable)
+    #[arg(long = "host-node", value_name = "MULTIADDR")]
+    host_nodes: Vec<String>,
+
+    /// Wait for each CID to be reported as pinned
+    #[arg(long)]
+    wait: bool,
+
+    /// Maximum time in seconds to wait for pin confirmation per CID
+    #[arg(long, default_value_t = 300)]
+    wait_timeout_secs: u64,
+}
+
+pub async fn run(client: &PinataClient, args: &PinCidArgs) -> Result<()> {
+    let mut keyvalues: BTreeMap<String, Value> = args
+        .keyvalues
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::from(value.clone())))
+        .collect();
+    if let Some(batch_id) = &args.batch_id {
+        keyvalues.insert("batch_id".to_string(), Value::from(batch_id.clone()));
+    }
+
+    let mut failed = 0;
+    let mut unconfirmed = 0;
+    for cid in &args.cids {
+        let name = match &args.name {
+            Some(name) if args.cids.len() > 1 => format!("{}_{}", name, cid),
+            Some(name) => name.clone(),
+            None => cid.clone(),
+        };
+
+        let result = match PinataMetadata::new(name, keyvalues.clone()) {
+            Ok(metadata) => client.pin_by_hash(cid, &metadata, &args.host_nodes).await,
+            Err(e) => Err(e),
+        };
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                println!("❌ Failed to pin {}: {:#}", cid, e);
+                failed += 1;
+                continue;
+            }
+        };
+        println!("📌 Pin requested: {} (status: {})", response.ipfs_hash, response.status);
+
+        if args.wait {
+            let timeout = Duration::from_secs(args.wait_timeout_secs);
+            println!("⏳ Waiting for pin confirmation: {}", cid);
+            match client.wait_for_pin(cid, timeout).await {
+                Ok(true) => println!("🔒 Pin confirmed: {}", cid),
+                Ok(false) => {
+                    println!("⚠️  Pin not confirmed within {:?}: {}", timeout, cid);
+                    unconfirmed += 1;
+                }
+                Err(e) => {
+                    println!("⚠️  Could not check pin status for {}: {:#}", cid, e);
+                    unconfirmed += 1;
+                }
+            }
+        }
+    }
+
+    println!("\nPin Summary:");
+    println!("   ✅ Requested: {}", args.cids.len() - failed);
+    println!("   ❌ Failed: {}", failed);
+    if args.wait {
+        println!("   🔒 Confirmed pinned: {}", args.cids.len() - failed - unconfirmed);
+        println!("   ⏳ Unconfirmed: {}", unconfirmed);
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} CIDs could not be pinned", failed);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Pinata allows at most 10 keyvalues per pin
+pub const MAX_KEYVALUES: usize = 10;
+
+/// Keyvalue holding the hex SHA-256 of the pinned file, checked by `fixity`
+pub const SHA256_KEY: &str = "sha256";
+
+/// The `pinataMetadata` part of a pinning request.
+#[derive(Debug, Clone, Serialize)]
+pub struct PinataMetadata {
+    pub name: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keyvalues: BTreeMap<String, Value>,
+}
+
+impl PinataMetadata {
+    pub fn new(name: String, keyvalues: BTreeMap<String, Value>) -> Result<Self> {
+        if keyvalues.len() > MAX_KEYVALUES {
+            anyhow::bail!(
+                "Too many keyvalues for {}: {} (Pinata allows at most {})",
+                name,
+                keyvalues.len(),
+                MAX_KEYVALUES
+            );
+        }
+        Ok(Self { name, keyvalues })
+    }
+
+    /// A copy with one more keyvalue, checked against Pinata's limit.
+    pub fn with_keyvalue(&self, key: &str, value: impl Into<Value>) -> Result<Self> {
+        let mut keyvalues = self.keyvalues.clone();
+        keyvalues.insert(key.to_string(), value.into());
+        Self::new(self.name.clone(), keyvalues)
+    }
+}
+
+/// The `pinataOptions` part of a pinning request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinataOptions {
+    pub cid_version: u8,
+    pub wrap_with_directory: bool,
+    /// Regions to replicate to, in place of the account's default pin policy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_pin_policy: Option<PinPolicy>,
+}
+
+/// Pinata caps the replication count of each region
+pub const MAX_REGION_REPLICATION: u8 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PinPolicy {
+    pub regions: Vec<PinPolicyRegion>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinPolicyRegion {
+    /// Pinata region ID, e.g. FRA1 or NYC1
+    pub id: String,
+    pub desired_replication_count: u8,
+}
+
+/// Where a chunk file sits within a sharded asset produced by blob_cid_shard
+#[derive(Debug, Clone)]
+pub struct ShardChunkRef {
+    pub shard_cid: String,
+    pub chunk_index: usize,
+}
+
+/// The subset of blob_cid_shard's ShardMetadata needed to identify chunks
+#[derive(Debug, Deserialize)]
+struct ShardManifest {
+    cid: String,
+    chunks: Vec<ShardManifestChunk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShardManifestChunk {
+    filename: String,
+}
+
+/// Maps chunk paths to their shard CID and index using any `*_metadata.json`
+/// manifests among the given files. Manifests that can't be parsed (e.g.
+/// encrypted stubs) are skipped.
+pub fn index_shard_chunks(files: &[PathBuf]) -> HashMap<PathBuf, ShardChunkRef> {
+    let mut chunks = HashMap::new();
+
+    for manifest_path in files.iter().filter(|path| is_shard_manifest(path)) {
+        let Some(manifest) = std::fs::read_to_string(manifest_path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<ShardManifest>(&json).ok())
+        else {
+            continue;
+        };
+
+        let dir = manifest_path.parent().unwrap_or(Path::new(""));
+        for (chunk_index, chunk) in manifest.chunks.iter().enumerate() {
+            chunks.insert(
+                dir.join(&chunk.filename),
+                ShardChunkRef {
+                    shard_cid: manifest.cid.clone(),
+                    chunk_index,
+                },
+            );
+        }
+    }
+
+    chunks
+}
+
+/// Whether the file is named like blob_cid_shard's metadata output.
+pub fn is_shard_manifest(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().ends_with("_metadata.json"))
+        .unwrap_or(false)
+}
+
+/// Parses a `KEY=VALUE` command line argument.
+pub fn parse_keyvalue(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("invalid keyvalue '{}': expected KEY=VALUE", s)),
+    }
+}
+
+/// Parses a `REGION=COUNT` command line argument.
+pub fn parse_region(s: &str) -> Result<PinPolicyRegion, String> {
+    let invalid = || format!("invalid region '{}': expected REGION=COUNT, e.g. FRA1=2", s);
+    let (id, count) = s.split_once('=').ok_or_else(invalid)?;
+    let count: u8 = count.parse().map_err(|_| invalid())?;
+    if id.is_empty() {
+        return Err(invalid());
+    }
+    if !(1..=MAX_REGION_REPLICATION).contains(&count) {
+        return Err(format!(
+            "invalid region '{}': replication count must be between 1 and {}",
+            s, MAX_REGION_REPLICATION
+        ));
+    }
+    Ok(PinPolicyRegion {
+        id: id.to_ascii_uppercase(),
+        desired_replication_count: count,
+    })
+}
+
+/// Loads keyvalues applied to every pin from a sidecar JSON object.
+pub fn load_keyvalues_file(path: &Path) -> Result<BTreeMap<String, Value>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read keyvalues file: {:?}", path))?;
+    let keyvalues: BTreeMap<String, Value> = serde_json::from_str(&json)
+        .with_context(|| format!("Keyvalues file must be a flat JSON object: {:?}", path))?;
+
+    if let Some((key, _)) = keyvalues.iter().find(|(_, value)| value.is_object() || value.is_array()) {
+        anyhow::bail!("Keyvalue '{}' in {:?} must be a string, number or boolean", key, path);
+    }
+
+    Ok(keyvalues)
+}
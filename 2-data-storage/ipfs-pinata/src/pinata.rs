@@ -0,0 +1,572 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use reqwest::multipart;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::file_part::file_part;
+use crate::http::HttpSettings;
+use crate::manifest::ManifestRegion;
+use crate::output::status;
+use crate::pin_metadata::{PinataMetadata, PinataOptions, SHA256_KEY};
+use crate::progress::FileProgress;
+use crate::retry::{ApiError, RetryPolicy};
+use crate::service::{PinnedFile, PinningService};
+use crate::unixfs::{DagParams, KUBO_DAG_PARAMS};
+
+const PINATA_API_URL: &str = "https://api.pinata.cloud";
+
+/// Pinata caps pin list pages at 1000 rows
+pub const MAX_PAGE_LIMIT: u32 = 1000;
+
+/// Interval between pin status checks when waiting for confirmation
+const PIN_STATUS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PinataResponse {
+    #[serde(rename = "IpfsHash")]
+    pub ipfs_hash: String,
+    #[serde(rename = "PinSize")]
+    pub pin_size: u64,
+    #[serde(rename = "Timestamp")]
+    pub timestamp: String,
+}
+
+/// Response to a pinByHash request; Pinata queues the pin and fetches the content
+/// from the network asynchronously.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinByHashResponse {
+    pub id: String,
+    pub ipfs_hash: String,
+    pub status: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PinByHashRequest<'a> {
+    hash_to_pin: &'a str,
+    pinata_metadata: &'a PinataMetadata,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pinata_options: Option<PinByHashOptions<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PinByHashOptions<'a> {
+    host_nodes: &'a [String],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PinataError {
+    error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PinListResponse {
+    pub count: u64,
+    #[serde(default)]
+    pub rows: Vec<PinListRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinListRow {
+    pub ipfs_pin_hash: String,
+    pub size: u64,
+    pub date_pinned: Option<String>,
+    pub date_unpinned: Option<String>,
+    #[serde(default)]
+    pub metadata: PinListMetadata,
+    #[serde(default)]
+    pub regions: Vec<PinListRegion>,
+}
+
+/// Replication of a pin in one region
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinListRegion {
+    pub region_id: String,
+    pub current_replication_count: u32,
+    pub desired_replication_count: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PinListMetadata {
+    pub name: Option<String>,
+    pub keyvalues: Option<BTreeMap<String, Value>>,
+}
+
+/// Filters for Pinata's pin list endpoint
+#[derive(Debug, Clone, Default)]
+pub struct PinListQuery {
+    pub cid: Option<String>,
+    /// "pinned", "unpinned" or "all"
+    pub status: Option<String>,
+    /// Matches pins whose name contains this value
+    pub name: Option<String>,
+    /// ISO 8601 date or timestamp
+    pub pinned_after: Option<String>,
+    /// ISO 8601 date or timestamp
+    pub pinned_before: Option<String>,
+    /// Keyvalues that must match exactly
+    pub keyvalues: BTreeMap<String, String>,
+    pub page_limit: u32,
+    pub page_offset: u32,
+}
+
+impl PinListQuery {
+    fn to_params(&self) -> Result<Vec<(&'static str, String)>> {
+        let mut params = vec![
+            ("pageLimit", self.page_limit.to_string()),
+            ("pageOffset", self.page_offset.to_string()),
+        ];
+        if let Some(cid) = &self.cid {
+            params.push(("hashContains", cid.clone()));
+        }
+        if let Some(status) = &self.status {
+            params.push(("status", status.clone()));
+        }
+        if let Some(name) = &self.name {
+            params.push(("metadata[name]", name.clone()));
+        }
+        if let Some(pinned_after) = &self.pinned_after {
+            params.push(("pinStart", pinned_after.clone()));
+        }
+        if let Some(pinned_before) = &self.pinned_before {
+            params.push(("pinEnd", pinned_before.clone()));
+        }
+        if !self.keyvalues.is_empty() {
+            let filters: BTreeMap<&String, Value> = self
+                .keyvalues
+                .iter()
+                .map(|(key, value)| (key, serde_json::json!({ "value": value, "op": "eq" })))
+                .collect();
+            params.push(("metadata[keyvalues]", serde_json::to_string(&filters)?));
+        }
+        Ok(params)
+    }
+}
+
+/// Credentials sent with every Pinata request
+pub enum PinataAuth {
+    /// Scoped JWT, sent as an `Authorization: Bearer` header
+    Jwt(String),
+    /// Legacy key/secret pair, sent as `pinata_api_key` / `pinata_secret_api_key` headers
+    ApiKey { api_key: String, secret: String },
+}
+
+pub struct PinataClient {
+    client: reqwest::Client,
+    auth: PinataAuth,
+    retry: RetryPolicy,
+    options: PinataOptions,
+}
+
+impl PinataClient {
+    pub fn new(auth: PinataAuth, retry: RetryPolicy, options: PinataOptions, http: &HttpSettings) -> Result<Self> {
+        Ok(Self {
+            client: http.client("Pinata")?,
+            auth,
+            retry,
+            options,
+        })
+    }
+
+    fn with_auth(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.auth {
+            PinataAuth::Jwt(jwt) => request.bearer_auth(jwt),
+            PinataAuth::ApiKey { api_key, secret } => request
+                .header("pinata_api_key", api_key)
+                .header("pinata_secret_api_key", secret),
+        }
+    }
+
+    pub async fn pin_file(
+        &self,
+        file_path: &Path,
+        metadata: &PinataMetadata,
+        progress: &FileProgress,
+    ) -> Result<PinataResponse> {
+        self.retry.run(|| self.try_pin_file(file_path, metadata, progress)).await
+    }
+
+    async fn try_pin_file(
+        &self,
+        file_path: &Path,
+        metadata: &PinataMetadata,
+        progress: &FileProgress,
+    ) -> Result<PinataResponse> {
+        // Counts bytes as reqwest sends them
+        progress.restart();
+        let file = file_part(file_path, metadata.name.clone(), progress).await?;
+
+        // Create multipart form
+        let form = multipart::Form::new()
+            .part("file", file)
+            .text("pinataMetadata", serde_json::to_string(metadata)?)
+            .text("pinataOptions", serde_json::to_string(&self.options)?);
+
+        status!("Uploading file: {:?}", file_path);
+
+        let response = self
+            .with_auth(self.client.post(format!("{}/pinning/pinFileToIPFS", PINATA_API_URL)))
+            .multipart(form)
+            .send()
+            .await
+            .with_context(|| "Failed to send request to Pinata")?;
+
+        if response.status().is_success() {
+            let pinata_response: PinataResponse = response
+                .json()
+                .await
+                .with_context(|| "Failed to parse Pinata response")?;
+            
+            status!("Successfully uploaded: {:?}", file_path);
+            status!("   IPFS Hash: {}", pinata_response.ipfs_hash);
+            status!("   Size: {} bytes", pinata_response.pin_size);
+            
+            Ok(pinata_response)
+        } else {
+            Err(api_error(response).await.into())
+        }
+    }
+
+    /// Uploads several files in one request, wrapped in a directory so each is
+    /// addressable as `<root cid>/<relative path>`. `files` pairs each local path
+    /// with its path inside the directory.
+    pub async fn pin_directory(
+        &self,
+        files: &[(PathBuf, String)],
+        metadata: &PinataMetadata,
+        progress: &FileProgress,
+    ) -> Result<PinataResponse> {
+        self.retry.run(|| self.try_pin_directory(files, metadata, progress)).await
+    }
+
+    async fn try_pin_directory(
+        &self,
+        files: &[(PathBuf, String)],
+        metadata: &PinataMetadata,
+        progress: &FileProgress,
+    ) -> Result<PinataResponse> {
+        let options = PinataOptions {
+            wrap_with_directory: true,
+            ..self.options.clone()
+        };
+
+        progress.restart();
+        let mut form = multipart::Form::new();
+        for (file_path, relative_path) in files {
+            form = form.part("file", file_part(file_path, relative_path.clone(), progress).await?);
+        }
+        let form = form
+            .text("pinataMetadata", serde_json::to_string(metadata)?)
+            .text("pinataOptions", serde_json::to_string(&options)?);
+
+        status!("Uploading {} files as a directory", files.len());
+
+        let response = self
+            .with_auth(self.client.post(format!("{}/pinning/pinFileToIPFS", PINATA_API_URL)))
+            .multipart(form)
+            .send()
+            .await
+            .with_context(|| "Failed to send request to Pinata")?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response).await.into());
+        }
+
+        let pinata_response: PinataResponse = response
+            .json()
+            .await
+            .with_context(|| "Failed to parse Pinata response")?;
+
+        status!("Successfully uploaded directory");
+        status!("   Root CID: {}", pinata_response.ipfs_hash);
+        status!("   Size: {} bytes", pinata_response.pin_size);
+
+        Ok(pinata_response)
+    }
+
+    /// Asks Pinata to pin content that is already available on the network.
+    /// `host_nodes` are multiaddrs of peers known to hold the content.
+    pub async fn pin_by_hash(
+        &self,
+        cid: &str,
+        metadata: &PinataMetadata,
+        host_nodes: &[String],
+    ) -> Result<PinByHashResponse> {
+        let request = PinByHashRequest {
+            hash_to_pin: cid,
+            pinata_metadata: metadata,
+            pinata_options: (!host_nodes.is_empty()).then_some(PinByHashOptions { host_nodes }),
+        };
+
+        self.retry.run(|| async {
+            let response = self
+                .with_auth(self.client.post(format!("{}/pinning/pinByHash", PINATA_API_URL)))
+                .json(&request)
+                .send()
+                .await
+                .with_context(|| format!("Failed to send pinByHash request for {}", cid))?;
+
+            if !response.status().is_success() {
+                return Err(api_error(response).await.into());
+            }
+
+            response
+                .json::<PinByHashResponse>()
+                .await
+                .with_context(|| "Failed to parse pinByHash response")
+        })
+        .await
+    }
+
+    pub async fn list_pins(&self, query: &PinListQuery) -> Result<PinListResponse> {
+        let response = self
+            .with_auth(self.client.get(format!("{}/data/pinList", PINATA_API_URL)))
+            .query(&query.to_params()?)
+            .send()
+            .await
+            .with_context(|| "Failed to query pin list")?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response).await.into());
+        }
+
+        response
+            .json()
+            .await
+            .with_context(|| "Failed to parse pin list response")
+    }
+
+    /// Fetches every page of the pin list matching the query, starting from its page offset.
+    pub async fn list_all_pins(&self, query: &PinListQuery) -> Result<Vec<PinListRow>> {
+        let mut query = query.clone();
+        if query.page_limit == 0 {
+            query.page_limit = MAX_PAGE_LIMIT;
+        }
+
+        let mut rows = Vec::new();
+        loop {
+            let page = self.list_pins(&query).await?;
+            let page_len = page.rows.len() as u32;
+            rows.extend(page.rows);
+
+            if page_len < query.page_limit {
+                return Ok(rows);
+            }
+            query.page_offset += page_len;
+        }
+    }
+
+    pub async fn unpin(&self, cid: &str) -> Result<()> {
+        let response = self
+            .with_auth(self.client.delete(format!("{}/pinning/unpin/{}", PINATA_API_URL, cid)))
+            .send()
+            .await
+            .with_context(|| format!("Failed to send unpin request for {}", cid))?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response).await.into());
+        }
+
+        Ok(())
+    }
+
+    /// Finds an active pin with exactly this CID and/or `sha256` keyvalue.
+    pub async fn find_pinned(&self, cid: Option<&str>, sha256: Option<&str>) -> Result<Option<PinListRow>> {
+        let query = PinListQuery {
+            cid: cid.map(str::to_string),
+            status: Some("pinned".to_string()),
+            keyvalues: sha256
+                .map(|sha256| BTreeMap::from([(SHA256_KEY.to_string(), sha256.to_string())]))
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+
+        // The CID filter is a substring match, so look for an exact match
+        Ok(self.list_all_pins(&query).await?.into_iter().find(|row| {
+            cid.is_none_or(|cid| row.ipfs_pin_hash == cid)
+                && sha256.is_none_or(|sha256| {
+                    let keyvalues = row.metadata.keyvalues.as_ref();
+                    keyvalues.and_then(|keyvalues| keyvalues.get(SHA256_KEY)?.as_str()) == Some(sha256)
+                })
+        }))
+    }
+
+    /// Looks up how a new pin is replicated across regions. The upload already
+    /// succeeded, so a failed lookup is only logged.
+    async fn pin_regions(&self, cid: &str) -> Vec<ManifestRegion> {
+        let row = match self.find_pinned(Some(cid), None).await {
+            Ok(row) => row,
+            Err(e) => {
+                status!("⚠️  Could not look up the region replication of {}: {:#}", cid, e);
+                return Vec::new();
+            }
+        };
+        let regions: Vec<ManifestRegion> = row
+            .map(|row| row.regions.into_iter().map(ManifestRegion::from).collect())
+            .unwrap_or_default();
+        for region in &regions {
+            status!(
+                "   Region {}: {}/{} replicas",
+                region.region_id,
+                region.current_replication_count,
+                region.desired_replication_count
+            );
+        }
+        regions
+    }
+
+    /// Returns true if Pinata lists the CID with a pinned status.
+    async fn is_pinned(&self, cid: &str) -> Result<bool> {
+        let query = PinListQuery {
+            cid: Some(cid.to_string()),
+            status: Some("pinned".to_string()),
+            page_limit: 1,
+            ..Default::default()
+        };
+        Ok(self.list_pins(&query).await?.count > 0)
+    }
+
+    /// Polls the pin list until the CID reports a pinned status or the timeout elapses.
+    pub async fn wait_for_pin(&self, cid: &str, timeout: Duration) -> Result<bool> {
+        let started = Instant::now();
+        loop {
+            if self.is_pinned(cid).await? {
+                return Ok(true);
+            }
+            if started.elapsed() + PIN_STATUS_POLL_INTERVAL > timeout {
+                return Ok(false);
+            }
+            tokio::time::sleep(PIN_STATUS_POLL_INTERVAL).await;
+        }
+    }
+
+    pub async fn test_authentication(&self) -> Result<()> {
+        status!("Testing Pinata API authentication...");
+        
+        let response = self
+            .with_auth(self.client.get(format!("{}/data/testAuthentication", PINATA_API_URL)))
+            .send()
+            .await
+            .with_context(|| "Failed to test authentication")?;
+
+        if response.status().is_success() {
+            status!("✅ Authentication successful!");
+            Ok(())
+        } else {
+            anyhow::bail!("Authentication failed: {}", response.status());
+        }
+    }
+}
+
+impl From<PinataResponse> for PinnedFile {
+    fn from(response: PinataResponse) -> Self {
+        Self {
+            ipfs_hash: response.ipfs_hash,
+            pin_size: response.pin_size,
+            timestamp: response.timestamp,
+            replicas: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+}
+
+impl From<PinListRow> for PinnedFile {
+    fn from(row: PinListRow) -> Self {
+        Self {
+            ipfs_hash: row.ipfs_pin_hash,
+            pin_size: row.size,
+            timestamp: row.date_pinned.unwrap_or_default(),
+            replicas: Vec::new(),
+            regions: row.regions.into_iter().map(ManifestRegion::from).collect(),
+        }
+    }
+}
+
+impl From<PinListRegion> for ManifestRegion {
+    fn from(region: PinListRegion) -> Self {
+        Self {
+            region_id: region.region_id,
+            desired_replication_count: region.desired_replication_count,
+            current_replication_count: region.current_replication_count,
+        }
+    }
+}
+
+impl PinningService for PinataClient {
+    fn name(&self) -> &'static str {
+        "Pinata"
+    }
+
+    /// Pinata follows Kubo's layout for CIDv1. CIDv0 uses dag-pb leaves and
+    /// wrapped uploads have a directory root, neither of which we compute.
+    fn dag_params(&self) -> Option<DagParams> {
+        (self.options.cid_version == 1 && !self.options.wrap_with_directory).then_some(KUBO_DAG_PARAMS)
+    }
+
+    fn test_authentication(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(PinataClient::test_authentication(self))
+    }
+
+    fn pin_file<'a>(
+        &'a self,
+        file_path: &'a Path,
+        metadata: &'a PinataMetadata,
+        progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>> {
+        Box::pin(async move {
+            let mut pinned: PinnedFile = PinataClient::pin_file(self, file_path, metadata, progress).await?.into();
+            if self.options.custom_pin_policy.is_some() {
+                pinned.regions = self.pin_regions(&pinned.ipfs_hash).await;
+            }
+            Ok(pinned)
+        })
+    }
+
+    fn pin_directory<'a>(
+        &'a self,
+        files: &'a [(PathBuf, String)],
+        metadata: &'a PinataMetadata,
+        progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>> {
+        Box::pin(async move {
+            let mut pinned: PinnedFile = PinataClient::pin_directory(self, files, metadata, progress).await?.into();
+            if self.options.custom_pin_policy.is_some() {
+                pinned.regions = self.pin_regions(&pinned.ipfs_hash).await;
+            }
+            Ok(pinned)
+        })
+    }
+
+    fn find_pinned<'a>(&'a self, cid: Option<&'a str>, sha256: Option<&'a str>) -> BoxFuture<'a, Result<Option<PinnedFile>>> {
+        Box::pin(async move { Ok(PinataClient::find_pinned(self, cid, sha256).await?.map(PinnedFile::from)) })
+    }
+
+    fn wait_for_pin<'a>(&'a self, cid: &'a str, timeout: Duration) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(PinataClient::wait_for_pin(self, cid, timeout))
+    }
+}
+
+/// Converts a non-success response into an ApiError, using the `error`
+/// field of the JSON body when present.
+async fn api_error(response: reqwest::Response) -> ApiError {
+    let status = response.status();
+    let headers = response.headers().clone();
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    let message = serde_json::from_str::<PinataError>(&error_text)
+        .map(|e| e.error)
+        .unwrap_or(error_text);
+
+    ApiError::new("Pinata", status, &headers, message)
+}
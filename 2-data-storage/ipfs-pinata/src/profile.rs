@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::pinata::PinataAuth;
+
+/// Profiles file path under the user's config directory
+const PROFILES_FILE: &str = "starling/pinata.toml";
+
+/// Named Pinata credentials, so staging and production accounts can be
+/// selected with --profile instead of swapping .env files:
+///
+/// ```toml
+/// [profiles.staging]
+/// jwt = "eyJ..."
+///
+/// [profiles.production]
+/// api_key = "..."
+/// api_secret = "..."
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfilesFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// One set of credentials: a profile, or the whole of a --credentials-file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Profile {
+    jwt: Option<String>,
+    api_key: Option<String>,
+    api_secret: Option<String>,
+}
+
+/// `$XDG_CONFIG_HOME/starling/pinata.toml`, falling back to `~/.config`.
+pub fn default_profiles_path() -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join(PROFILES_FILE))
+}
+
+impl Profile {
+    /// `source` names where the credentials came from, for error messages.
+    fn auth(self, source: &str) -> Result<PinataAuth> {
+        let non_empty = |value: Option<String>, field: &str| match value {
+            Some(value) if value.is_empty() => anyhow::bail!("{}: {} cannot be empty", source, field),
+            value => Ok(value),
+        };
+        match (
+            non_empty(self.jwt, "jwt")?,
+            non_empty(self.api_key, "api_key")?,
+            non_empty(self.api_secret, "api_secret")?,
+        ) {
+            (Some(jwt), None, None) => Ok(PinataAuth::Jwt(jwt)),
+            (None, Some(api_key), Some(secret)) => Ok(PinataAuth::ApiKey { api_key, secret }),
+            _ => anyhow::bail!("{} must set either jwt, or both api_key and api_secret", source),
+        }
+    }
+}
+
+/// Reads the credentials of profile `name` from the profiles file at `path`.
+pub fn load_profile(path: &Path, name: &str) -> Result<PinataAuth> {
+    let contents = read_secret_file(path)?;
+    let mut file: ProfilesFile =
+        toml::from_str(&contents).with_context(|| format!("Failed to parse credential profiles: {:?}", path))?;
+
+    let Some(profile) = file.profiles.remove(name) else {
+        let known: Vec<&str> = file.profiles.keys().map(String::as_str).collect();
+        anyhow::bail!("No profile named {:?} in {:?} (available: {})", name, path, known.join(", "));
+    };
+    profile.auth(&format!("Profile {:?} in {:?}", name, path))
+}
+
+/// Reads credentials from a keyfile holding a single profile's fields at the
+/// top level, for deployments where secrets may not be put in the environment.
+pub fn load_credentials_file(path: &Path) -> Result<PinataAuth> {
+    let contents = read_secret_file(path)?;
+    let profile: Profile =
+        toml::from_str(&contents).with_context(|| format!("Failed to parse credentials file: {:?}", path))?;
+    profile.auth(&format!("Credentials file {:?}", path))
+}
+
+/// Reads a file holding secrets, refusing it if other users can read or write it.
+fn read_secret_file(path: &Path) -> Result<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read credentials: {:?}", path))?
+            .permissions()
+            .mode()
+            & 0o777;
+        if mode & 0o077 != 0 {
+            anyhow::bail!(
+                "Credentials in {:?} are accessible by other users (mode {:04o}); restrict them with `chmod 600 {}`",
+                path,
+                mode,
+                path.display()
+            );
+        }
+    }
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read credentials: {:?}", path))
+}
@@ -0,0 +1,127 @@
+use std::io;
+
+use futures_util::{Stream, TryStreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::output;
+
+const FILE_TEMPLATE: &str = "{msg:32!} [{bar:30.cyan/blue}] {bytes}/{total_bytes} {binary_bytes_per_sec}";
+const FILES_TEMPLATE: &str = "Files  [{bar:30.green/white}] {pos}/{len}";
+const BYTES_TEMPLATE: &str = "Bytes  [{bar:30.green/white}] {bytes}/{total_bytes} {binary_bytes_per_sec} ETA {eta}";
+
+/// Aggregate progress for a batch: files processed and bytes sent. Per-file
+/// bars are drawn above the two batch bars while their upload is in flight.
+/// Bars draw to stderr and are hidden when it isn't a terminal or with
+/// --output json.
+#[derive(Clone)]
+pub struct BatchProgress {
+    multi: MultiProgress,
+    files: ProgressBar,
+    bytes: ProgressBar,
+}
+
+impl BatchProgress {
+    pub fn new(total_files: u64, total_bytes: u64) -> Self {
+        let multi = match output::is_json() {
+            true => MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+            false => MultiProgress::new(),
+        };
+        let files = multi.add(ProgressBar::new(total_files).with_style(style(FILES_TEMPLATE)));
+        let bytes = multi.add(ProgressBar::new(total_bytes).with_style(style(BYTES_TEMPLATE)));
+        Self { multi, files, bytes }
+    }
+
+    /// Grows the batch as the scan finds more files.
+    pub fn add_files(&self, files: u64, bytes: u64) {
+        self.files.inc_length(files);
+        self.bytes.inc_length(bytes);
+    }
+
+    /// Adds a bar for one file of `len` bytes.
+    pub fn start_file(&self, name: &str, len: u64) -> FileProgress {
+        let bar = self.multi.insert_before(&self.files, file_bar(name, len));
+        FileProgress {
+            bar,
+            batch: Some(self.clone()),
+        }
+    }
+
+    pub fn finish(&self) {
+        self.files.finish();
+        self.bytes.finish();
+    }
+}
+
+/// Progress of a single upload, also counted toward its batch if it has one.
+#[derive(Clone)]
+pub struct FileProgress {
+    bar: ProgressBar,
+    batch: Option<BatchProgress>,
+}
+
+impl FileProgress {
+    /// A bar for an upload that isn't part of a batch.
+    pub fn standalone(name: &str, len: u64) -> Self {
+        let bar = match output::is_json() {
+            true => ProgressBar::hidden(),
+            false => file_bar(name, len),
+        };
+        Self { bar, batch: None }
+    }
+
+    /// Progress that is tracked but never drawn.
+    pub fn hidden() -> Self {
+        Self {
+            bar: ProgressBar::hidden(),
+            batch: None,
+        }
+    }
+
+    pub fn inc(&self, bytes: u64) {
+        self.bar.inc(bytes);
+        if let Some(batch) = &self.batch {
+            batch.bytes.inc(bytes);
+        }
+    }
+
+    /// Rewinds to zero before a retry so the bytes aren't counted twice.
+    pub fn restart(&self) {
+        if let Some(batch) = &self.batch {
+            batch.bytes.set_position(batch.bytes.position().saturating_sub(self.bar.position()));
+        }
+        self.bar.reset();
+    }
+
+    /// Wraps a file stream so bytes are counted as the HTTP client reads them.
+    pub fn track<S, B>(&self, stream: S) -> impl Stream<Item = io::Result<B>> + use<S, B>
+    where
+        S: Stream<Item = io::Result<B>>,
+        B: AsRef<[u8]>,
+    {
+        let progress = self.clone();
+        stream.inspect_ok(move |chunk| progress.inc(chunk.as_ref().len() as u64))
+    }
+
+    /// Removes the bar once the file is done, successfully or not. The batch
+    /// counts the whole file as processed so its bars end at 100%.
+    pub fn finish(self) {
+        if let Some(batch) = &self.batch {
+            let remaining = self.bar.length().unwrap_or_default().saturating_sub(self.bar.position());
+            batch.bytes.inc(remaining);
+            batch.files.inc(1);
+        }
+        self.bar.finish_and_clear();
+    }
+}
+
+fn file_bar(name: &str, len: u64) -> ProgressBar {
+    ProgressBar::new(len)
+        .with_style(style(FILE_TEMPLATE).progress_chars("=> "))
+        .with_message(name.to_string())
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .expect("progress template is valid")
+        .progress_chars("=> ")
+}
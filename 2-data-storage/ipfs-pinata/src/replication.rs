@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures_util::future::{BoxFuture, join_all};
+use serde::Deserialize;
+
+use crate::manifest::ManifestReplica;
+use crate::output::status;
+use crate::pin_metadata::PinataMetadata;
+use crate::progress::FileProgress;
+use crate::service::{Backend, PinnedFile, PinningService};
+
+/// Which services every file is pinned to, read from --replication-config
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplicationPolicy {
+    pub providers: Vec<Backend>,
+    /// Pins that must succeed for a file to count as uploaded
+    #[serde(default = "default_min_copies")]
+    pub min_copies: usize,
+}
+
+fn default_min_copies() -> usize {
+    1
+}
+
+impl ReplicationPolicy {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read replication config: {:?}", path))?;
+        let policy: Self = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse replication config: {:?}", path))?;
+
+        if policy.providers.is_empty() {
+            anyhow::bail!("Replication config lists no providers: {:?}", path);
+        }
+        for (index, provider) in policy.providers.iter().enumerate() {
+            if policy.providers[..index].contains(provider) {
+                anyhow::bail!("Provider {:?} is listed more than once in {:?}", provider, path);
+            }
+        }
+        if policy.min_copies == 0 || policy.min_copies > policy.providers.len() {
+            anyhow::bail!(
+                "min_copies must be between 1 and the number of providers ({}), got {}",
+                policy.providers.len(),
+                policy.min_copies
+            );
+        }
+        Ok(policy)
+    }
+}
+
+/// Pins every file to several services at once. A file succeeds when at least
+/// `min_copies` services pin it, so one provider's outage doesn't leave the
+/// batch without copies. Each service's result is returned in
+/// `PinnedFile::replicas`, and the first successful service's pin is used as
+/// the file's own.
+pub struct ReplicatedService {
+    services: Vec<Box<dyn PinningService>>,
+    min_copies: usize,
+    /// CID each service returned, keyed by the file's own CID, for waiting on
+    /// services whose chunking gives a different CID
+    replica_cids: Mutex<HashMap<String, Vec<Option<String>>>>,
+}
+
+impl ReplicatedService {
+    pub fn new(services: Vec<Box<dyn PinningService>>, min_copies: usize) -> Self {
+        Self {
+            services,
+            min_copies,
+            replica_cids: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Runs `pin` against every service concurrently and combines the results.
+    async fn replicate<'a, F>(&'a self, target: &str, pin: F) -> Result<PinnedFile>
+    where
+        F: Fn(usize, &'a dyn PinningService) -> BoxFuture<'a, Result<PinnedFile>>,
+    {
+        let results = join_all(
+            self.services
+                .iter()
+                .enumerate()
+                .map(|(index, service)| pin(index, service.as_ref())),
+        )
+        .await;
+
+        let replicas: Vec<ManifestReplica> = self
+            .services
+            .iter()
+            .zip(&results)
+            .map(|(service, result)| ManifestReplica {
+                provider: service.name().to_string(),
+                ipfs_hash: result.as_ref().ok().map(|pinned| pinned.ipfs_hash.clone()),
+                error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            })
+            .collect();
+
+        let pinned_copies = results.iter().filter(|result| result.is_ok()).count();
+        for replica in &replicas {
+            if let Some(error) = &replica.error {
+                status!("⚠️  {} could not pin {}: {}", replica.provider, target, error);
+            }
+        }
+        if pinned_copies < self.min_copies {
+            anyhow::bail!(
+                "Only {} of {} providers pinned {} (need {})",
+                pinned_copies,
+                self.services.len(),
+                target,
+                self.min_copies
+            );
+        }
+
+        // Only Pinata reports regions, whichever provider's pin is the primary
+        let regions = results.iter().flatten().flat_map(|pinned| pinned.regions.clone()).collect();
+        let primary = results.into_iter().flatten().next().expect("at least one copy is pinned");
+        self.replica_cids.lock().unwrap().insert(
+            primary.ipfs_hash.clone(),
+            replicas.iter().map(|replica| replica.ipfs_hash.clone()).collect(),
+        );
+        status!("🌍 Pinned {} to {} of {} providers", target, pinned_copies, self.services.len());
+
+        Ok(PinnedFile { replicas, regions, ..primary })
+    }
+}
+
+impl PinningService for ReplicatedService {
+    fn name(&self) -> &'static str {
+        "Replicated"
+    }
+
+    /// Passes if at least `min_copies` services are reachable; the others are
+    /// reported and will show up as failed replicas.
+    fn test_authentication(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let results = join_all(self.services.iter().map(|service| service.test_authentication())).await;
+            let mut healthy = 0;
+            for (service, result) in self.services.iter().zip(results) {
+                match result {
+                    Ok(()) => healthy += 1,
+                    Err(e) => status!("⚠️  {} is unavailable: {:#}", service.name(), e),
+                }
+            }
+            if healthy < self.min_copies {
+                anyhow::bail!(
+                    "Only {} of {} providers are available (need {})",
+                    healthy,
+                    self.services.len(),
+                    self.min_copies
+                );
+            }
+            Ok(())
+        })
+    }
+
+    /// Only the first service reports progress, since each upload restarts its
+    /// file's bar on retry.
+    fn pin_file<'a>(
+        &'a self,
+        file_path: &'a Path,
+        metadata: &'a PinataMetadata,
+        progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>> {
+        Box::pin(async move {
+            let hidden = FileProgress::hidden();
+            let hidden = &hidden;
+            let target = format!("{:?}", file_path);
+            self.replicate(&target, |index, service| match index {
+                0 => service.pin_file(file_path, metadata, progress),
+                _ => service.pin_file(file_path, metadata, hidden),
+            })
+            .await
+        })
+    }
+
+    fn pin_directory<'a>(
+        &'a self,
+        files: &'a [(PathBuf, String)],
+        metadata: &'a PinataMetadata,
+        progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>> {
+        Box::pin(async move {
+            let hidden = FileProgress::hidden();
+            let hidden = &hidden;
+            self.replicate(&metadata.name, |index, service| match index {
+                0 => service.pin_directory(files, metadata, progress),
+                _ => service.pin_directory(files, metadata, hidden),
+            })
+            .await
+        })
+    }
+
+    /// Reports a file as pinned only when every service already has it, so a
+    /// re-run fills in copies that are missing.
+    fn find_pinned<'a>(&'a self, cid: Option<&'a str>, sha256: Option<&'a str>) -> BoxFuture<'a, Result<Option<PinnedFile>>> {
+        Box::pin(async move {
+            let mut replicas = Vec::with_capacity(self.services.len());
+            let mut regions = Vec::new();
+            let mut primary = None;
+            for service in &self.services {
+                let Some(pinned) = service.find_pinned(cid, sha256).await? else {
+                    return Ok(None);
+                };
+                replicas.push(ManifestReplica {
+                    provider: service.name().to_string(),
+                    ipfs_hash: Some(pinned.ipfs_hash.clone()),
+                    error: None,
+                });
+                regions.extend(pinned.regions.iter().cloned());
+                primary.get_or_insert(pinned);
+            }
+            Ok(primary.map(|primary| PinnedFile { replicas, regions, ..primary }))
+        })
+    }
+
+    /// Waits on every service that pinned the file, each with the CID it returned.
+    fn wait_for_pin<'a>(&'a self, cid: &'a str, timeout: Duration) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(async move {
+            let replica_cids = self.replica_cids.lock().unwrap().get(cid).cloned();
+            let replica_cids = replica_cids.unwrap_or_else(|| vec![Some(cid.to_string()); self.services.len()]);
+
+            let waits = self
+                .services
+                .iter()
+                .zip(&replica_cids)
+                .filter_map(|(service, replica_cid)| Some(service.wait_for_pin(replica_cid.as_deref()?, timeout)));
+            let mut confirmed = true;
+            for result in join_all(waits).await {
+                confirmed &= result?;
+            }
+            Ok(confirmed)
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use rand::Rng;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use tokio::time::Instant;
+
+use crate::output::{Event, emit, status};
+
+/// Longest we honor a single Retry-After, so a bogus header can't stall a batch
+const MAX_RATE_LIMIT_DELAY: Duration = Duration::from_secs(15 * 60);
+
+/// Rate-limited responses are retried until they have waited this long in
+/// total, separately from the --retries budget for other failures
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60 * 60);
+
+/// Error returned by a pinning service API with a non-success status code.
+#[derive(Debug)]
+pub struct ApiError {
+    /// Name of the service that returned the error, e.g. "Pinata"
+    pub service: &'static str,
+    pub status: StatusCode,
+    pub message: String,
+    /// Delay requested by the Retry-After header of a 429 or 503 response
+    pub retry_after: Option<Duration>,
+}
+
+impl ApiError {
+    /// Builds an error from a non-success response's status and headers.
+    pub fn new(service: &'static str, status: StatusCode, headers: &HeaderMap, message: String) -> Self {
+        Self {
+            service,
+            status,
+            message,
+            retry_after: retry_after(headers),
+        }
+    }
+
+    fn is_rate_limited(&self) -> bool {
+        self.status == StatusCode::TOO_MANY_REQUESTS
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} API error ({}): {}", self.service, self.status, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// A request that failed without a response, e.g. a connection failure or
+/// timeout in a service's own SDK, which is worth retrying
+#[derive(Debug)]
+pub struct TransientError {
+    pub service: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for TransientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} request failed: {}", self.service, self.message)
+    }
+}
+
+impl std::error::Error for TransientError {}
+
+/// Retries transient failures. Clones share one rate-limit window, so when
+/// any request is rate limited, every concurrent upload pauses until it clears.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// When the service last told us to slow down, the time it's safe to resume
+    rate_limited_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, base_delay_ms: u64) -> Self {
+        Self {
+            max_retries,
+            base_delay: Duration::from_millis(base_delay_ms),
+            rate_limited_until: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Delay before retry number `attempt` (0-based): base * 2^attempt plus up to
+    /// one base delay of random jitter so parallel clients don't retry in lockstep.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        let jitter_ms = rand::thread_rng().gen_range(0..=self.base_delay.as_millis() as u64);
+        exponential.saturating_add(Duration::from_millis(jitter_ms))
+    }
+
+    /// Delay after the `attempt`th consecutive 429 without a Retry-After header.
+    /// Starts at one second so we back off well beyond the normal retry delay.
+    fn rate_limit_backoff(&self, attempt: u32) -> Duration {
+        let exponential = Duration::from_secs(1).saturating_mul(2u32.saturating_pow(attempt));
+        exponential.saturating_add(self.backoff(0)).min(MAX_RATE_LIMIT_DELAY)
+    }
+
+    /// Pushes the shared rate-limit window out to at least `delay` from now.
+    fn hold_off(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut rate_limited_until = self.rate_limited_until.lock().unwrap();
+        if rate_limited_until.is_none_or(|current| current < until) {
+            *rate_limited_until = Some(until);
+        }
+    }
+
+    /// Sleeps until the shared rate-limit window, if any, has passed.
+    async fn wait_for_rate_limit(&self) {
+        let until = *self.rate_limited_until.lock().unwrap();
+        if let Some(until) = until {
+            tokio::time::sleep_until(until).await;
+        }
+    }
+
+    /// Runs a request, retrying transient failures with exponential backoff.
+    /// Rate-limited requests wait out the service's Retry-After (or an
+    /// adaptive backoff) and don't count against `max_retries`.
+    pub async fn run<T, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        let mut rate_limit_attempt = 0;
+        let mut rate_limit_waited = Duration::ZERO;
+        loop {
+            self.wait_for_rate_limit().await;
+            match request().await {
+                Ok(response) => return Ok(response),
+                Err(e) if rate_limit_waited < MAX_RATE_LIMIT_WAIT && rate_limited(&e).is_some() => {
+                    let requested = rate_limited(&e).flatten();
+                    let delay = requested
+                        .map(|delay| delay.min(MAX_RATE_LIMIT_DELAY))
+                        .unwrap_or_else(|| self.rate_limit_backoff(rate_limit_attempt));
+                    rate_limit_attempt += 1;
+                    rate_limit_waited += delay;
+                    status!("⏳ Rate limited: {:#}. Pausing requests for {:?}...", e, delay);
+                    emit(&Event::RateLimited {
+                        delay_ms: delay.as_millis() as u64,
+                        error: format!("{:#}", e),
+                    });
+                    self.hold_off(delay);
+                }
+                Err(e) if attempt < self.max_retries && is_retryable(&e) => {
+                    let delay = self.backoff(attempt);
+                    attempt += 1;
+                    status!("⚠️  Attempt {} failed: {:#}. Retrying in {:?}...", attempt, e, delay);
+                    emit(&Event::Retrying {
+                        attempt,
+                        delay_ms: delay.as_millis() as u64,
+                        error: format!("{:#}", e),
+                    });
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// If the request was rate limited (429), the delay the service asked for.
+fn rate_limited(error: &anyhow::Error) -> Option<Option<Duration>> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<ApiError>())
+        .filter(|api_error| api_error.is_rate_limited())
+        .map(|api_error| api_error.retry_after)
+}
+
+/// Parses a Retry-After header, given either as delay-seconds or an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    // A date in the past means we may retry immediately
+    Some((date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+/// Whether a failed request is worth retrying. Network failures, timeouts,
+/// 408, 429 and 5xx responses, and `TransientError`s, are transient; everything else (bad credentials,
+/// malformed requests, unreadable local files) fails the same way every time.
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(api_error) = cause.downcast_ref::<ApiError>() {
+            let status = api_error.status;
+            return status.is_server_error()
+                || status == StatusCode::REQUEST_TIMEOUT
+                || status == StatusCode::TOO_MANY_REQUESTS;
+        }
+        if cause.is::<TransientError>() {
+            return true;
+        }
+        if let Some(request_error) = cause.downcast_ref::<reqwest::Error>() {
+            return request_error.is_timeout()
+                || request_error.is_connect()
+                || request_error.is_request()
+                || request_error.is_body();
+        }
+    }
+    false
+}
@@ -0,0 +1,91 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+
+use crate::manifest::{ManifestRegion, ManifestReplica};
+use crate::pin_metadata::PinataMetadata;
+use crate::progress::FileProgress;
+use crate::unixfs::DagParams;
+
+/// Remote services the upload mode can pin to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Pinata,
+    Storacha,
+    Filebase,
+    /// A Kubo node reached through its RPC API
+    Kubo,
+}
+
+/// A file stored by a pinning service
+#[derive(Debug, Clone)]
+pub struct PinnedFile {
+    pub ipfs_hash: String,
+    pub pin_size: u64,
+    /// ISO 8601 time the pin was created
+    pub timestamp: String,
+    /// Each provider's copy when pinned with a replication policy
+    pub replicas: Vec<ManifestReplica>,
+    /// Pinata's replication per region, when the pin has a region policy
+    pub regions: Vec<ManifestRegion>,
+}
+
+/// A remote service that can store files on IPFS. Methods return boxed
+/// futures so services can be selected at runtime as `dyn PinningService`.
+pub trait PinningService: Send + Sync {
+    /// Human-readable service name used in log output
+    fn name(&self) -> &'static str;
+
+    /// How the service chunks files into a UnixFS DAG, when it is known, so the
+    /// returned CID can be checked against one computed locally.
+    fn dag_params(&self) -> Option<DagParams> {
+        None
+    }
+
+    /// Verifies the credentials before a batch starts.
+    fn test_authentication(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Uploads one file, reporting bytes sent to `progress`.
+    fn pin_file<'a>(
+        &'a self,
+        file_path: &'a Path,
+        metadata: &'a PinataMetadata,
+        progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>>;
+
+    /// Uploads several files as one directory; `files` pairs each local path
+    /// with its path inside the directory.
+    fn pin_directory<'a>(
+        &'a self,
+        _files: &'a [(PathBuf, String)],
+        _metadata: &'a PinataMetadata,
+        _progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>> {
+        Box::pin(async move { anyhow::bail!("{} does not support directory uploads", self.name()) })
+    }
+
+    /// Finds an active pin with exactly this CID and/or content SHA-256, as
+    /// recorded in the `sha256` keyvalue. Services that can't search by
+    /// SHA-256 return None for SHA-256-only lookups.
+    fn find_pinned<'a>(&'a self, cid: Option<&'a str>, sha256: Option<&'a str>) -> BoxFuture<'a, Result<Option<PinnedFile>>>;
+
+    /// Waits until the CID is reported as pinned, returning false on timeout.
+    fn wait_for_pin<'a>(&'a self, cid: &'a str, timeout: Duration) -> BoxFuture<'a, Result<bool>>;
+}
+
+/// Reads a credential that must be set and non-empty.
+pub fn required_env(name: &str) -> Result<String> {
+    let value = env::var(name).with_context(|| {
+        format!("{} environment variable not found. Please set it in your .env file or environment.", name)
+    })?;
+    if value.is_empty() {
+        anyhow::bail!("{} cannot be empty", name);
+    }
+    Ok(value)
+}
@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use blob_cid_shard::ShardMetadata;
+
+use crate::manifest::{ManifestEntry, UploadStatus};
+use crate::output::status;
+
+/// Records the CID of every chunk in a blob_cid_shard `*_metadata.json`, using
+/// the chunks' entries from this batch, so the pinned metadata links to its
+/// chunks. The file is only rewritten when a CID changed. Files that aren't
+/// plain shard metadata (e.g. encrypted stubs) are left alone.
+pub fn link_chunk_cids(metadata_path: &Path, entries: &BTreeMap<PathBuf, ManifestEntry>) -> Result<()> {
+    let json = std::fs::read_to_string(metadata_path)
+        .with_context(|| format!("Failed to read shard metadata: {:?}", metadata_path))?;
+    let Ok(mut metadata) = serde_json::from_str::<ShardMetadata>(&json) else {
+        return Ok(());
+    };
+
+    let dir = metadata_path.parent().unwrap_or(Path::new(""));
+    let mut unpinned = Vec::new();
+    let mut changed = false;
+    for chunk in &mut metadata.chunks {
+        let pinned = entries
+            .get(&dir.join(&chunk.filename))
+            .filter(|entry| entry.status != UploadStatus::Failed)
+            .and_then(|entry| entry.ipfs_hash.clone());
+        match pinned {
+            Some(cid) => {
+                changed |= chunk.ipfs_hash.as_ref() != Some(&cid);
+                chunk.ipfs_hash = Some(cid);
+            }
+            // Keep a CID recorded by an earlier run for chunks not in this batch
+            None if chunk.ipfs_hash.is_some() => {}
+            None => unpinned.push(chunk.filename.clone()),
+        }
+    }
+
+    if !unpinned.is_empty() {
+        anyhow::bail!(
+            "{} of {} chunks are not pinned: {}",
+            unpinned.len(),
+            metadata.chunks.len(),
+            unpinned.join(", ")
+        );
+    }
+
+    if changed {
+        std::fs::write(metadata_path, serde_json::to_string_pretty(&metadata)?)
+            .with_context(|| format!("Failed to update shard metadata: {:?}", metadata_path))?;
+        status!("🔗 Recorded {} chunk CIDs in {:?}", metadata.chunks.len(), metadata_path);
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::{ManifestEntry, UploadStatus};
+
+/// Default state filename, written to the input directory
+pub const DEFAULT_STATE_NAME: &str = ".pinata-state.json";
+
+/// Per-file progress of an upload batch, saved after every file so an
+/// interrupted run can be resumed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UploadState {
+    pub files: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+impl UploadState {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read upload state: {:?}", path))?;
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse upload state: {:?}", path))
+    }
+
+    /// Writes to a temporary file and renames it into place so a crash mid-write
+    /// can't leave a truncated state file behind.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write upload state: {:?}", tmp_path))?;
+        std::fs::rename(&tmp_path, path).with_context(|| format!("Failed to write upload state: {:?}", path))
+    }
+
+    /// The recorded entry for a file that doesn't need uploading again.
+    pub fn completed(&self, file_path: &Path) -> Option<&ManifestEntry> {
+        self.files
+            .get(file_path)
+            .filter(|entry| entry.status != UploadStatus::Failed)
+    }
+}
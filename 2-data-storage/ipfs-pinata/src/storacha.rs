@@ -0,0 +1,250 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use serde_json::{Value, json};
+
+use crate::car::{CAR_CODEC, CarWriter};
+use crate::cid::Cid;
+use crate::http::HttpSettings;
+use crate::output::status;
+use crate::pin_metadata::PinataMetadata;
+use crate::progress::FileProgress;
+use crate::retry::{ApiError, RetryPolicy};
+use crate::service::{PinnedFile, PinningService, required_env};
+use crate::unixfs::{DagParams, build_file_dag};
+
+/// HTTP bridge that accepts w3up invocations authorized by bridge tokens
+const STORACHA_BRIDGE_URL: &str = "https://up.storacha.network/bridge";
+
+/// Chunking used by the w3up upload client, so our root CIDs match its
+pub const STORACHA_DAG_PARAMS: DagParams = DagParams {
+    chunk_size: 1024 * 1024,
+    max_links: 1024,
+};
+
+/// Target size of each CAR shard, matching the w3up client default
+const SHARD_SIZE: usize = 127 * 1024 * 1024;
+
+/// Uploads files to a Storacha (web3.storage) space as CAR shards through the
+/// w3up HTTP bridge. Content is stored on IPFS and replicated to Filecoin.
+pub struct StorachaClient {
+    client: reqwest::Client,
+    space_did: String,
+    auth_secret: String,
+    authorization: String,
+    retry: RetryPolicy,
+}
+
+impl StorachaClient {
+    /// Reads the space DID and bridge tokens (from `storacha bridge generate-tokens`)
+    /// from STORACHA_SPACE_DID, STORACHA_AUTH_SECRET and STORACHA_AUTHORIZATION.
+    pub fn from_env(retry: RetryPolicy, http: &HttpSettings) -> Result<Self> {
+        Ok(Self {
+            client: http.client("Storacha")?,
+            space_did: required_env("STORACHA_SPACE_DID")?,
+            auth_secret: required_env("STORACHA_AUTH_SECRET")?,
+            authorization: required_env("STORACHA_AUTHORIZATION")?,
+            retry,
+        })
+    }
+
+    /// Invokes a capability on our space and returns the receipt's `ok` value.
+    async fn invoke(&self, ability: &str, caveats: Value) -> Result<Value> {
+        let body = json!({ "tasks": [[ability, self.space_did, caveats]] });
+
+        let response = self
+            .client
+            .post(STORACHA_BRIDGE_URL)
+            .header("X-Auth-Secret", &self.auth_secret)
+            .header("Authorization", &self.authorization)
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send {} invocation to Storacha", ability))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let message = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(ApiError::new("Storacha", status, &headers, message).into());
+        }
+
+        let receipts: Value = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse {} receipt", ability))?;
+        let out = &receipts[0]["p"]["out"];
+        if let Some(error) = out.get("error") {
+            return Err(StorachaError {
+                name: error["name"].as_str().unwrap_or("Error").to_string(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            }
+            .into());
+        }
+        out.get("ok")
+            .cloned()
+            .with_context(|| format!("Unexpected {} receipt: {}", ability, receipts))
+    }
+
+    /// Registers a CAR shard with the space and uploads its bytes if the
+    /// service doesn't already have them.
+    async fn store_shard(&self, car: &[u8]) -> Result<Cid> {
+        let car_cid = Cid::sha256(CAR_CODEC, car);
+        let allocation = self
+            .invoke("store/add", json!({ "link": { "/": car_cid.to_string() }, "size": car.len() }))
+            .await?;
+
+        if allocation["status"] == "done" {
+            return Ok(car_cid);
+        }
+
+        let url = allocation["url"]
+            .as_str()
+            .with_context(|| format!("store/add returned no upload URL: {}", allocation))?;
+        let mut request = self.client.put(url).body(car.to_vec());
+        if let Some(headers) = allocation["headers"].as_object() {
+            for (name, value) in headers {
+                request = request.header(name, value.as_str().unwrap_or_default());
+            }
+        }
+
+        let response = request.send().await.with_context(|| "Failed to upload CAR shard")?;
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let message = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(ApiError::new("Storacha", status, &headers, message).into());
+        }
+
+        Ok(car_cid)
+    }
+
+    async fn upload(&self, file_path: &Path, progress: &FileProgress) -> Result<PinnedFile> {
+        status!("Uploading file: {:?}", file_path);
+
+        let dag = build_file_dag(file_path, STORACHA_DAG_PARAMS)?;
+        let mut file = File::open(file_path).with_context(|| format!("Failed to open file: {:?}", file_path))?;
+        let file_size = file.metadata()?.len();
+
+        let mut shards = Vec::new();
+        let mut car = CarWriter::new();
+        let mut buffer = Vec::new();
+        // File bytes in the shard being built, reported once the shard is stored
+        let mut shard_file_bytes = 0;
+        for leaf in &dag.leaves {
+            if !car.is_empty() && car.len() + leaf.len > SHARD_SIZE {
+                let bytes = std::mem::replace(&mut car, CarWriter::new()).finish(&[]);
+                shards.push(self.retry.run(|| self.store_shard(&bytes)).await?);
+                progress.inc(std::mem::take(&mut shard_file_bytes));
+            }
+            shard_file_bytes += leaf.len as u64;
+            buffer.resize(leaf.len, 0);
+            file.seek(SeekFrom::Start(leaf.offset))?;
+            file.read_exact(&mut buffer)
+                .with_context(|| format!("Failed to read file: {:?}", file_path))?;
+            car.write_block(&leaf.cid, &buffer);
+        }
+        for (cid, bytes) in &dag.nodes {
+            car.write_block(cid, bytes);
+        }
+        // The root goes in the final shard's header, as the w3up client does
+        let bytes = car.finish(&[dag.root]);
+        shards.push(self.retry.run(|| self.store_shard(&bytes)).await?);
+        progress.inc(shard_file_bytes);
+
+        let shard_links: Vec<Value> = shards.iter().map(|cid| json!({ "/": cid.to_string() })).collect();
+        self.retry
+            .run(|| self.invoke("upload/add", json!({ "root": { "/": dag.root.to_string() }, "shards": shard_links })))
+            .await?;
+
+        status!("Successfully uploaded: {:?}", file_path);
+        status!("   IPFS Hash: {}", dag.root);
+        status!("   Size: {} bytes ({} CAR shards)", file_size, shards.len());
+
+        Ok(PinnedFile {
+            ipfs_hash: dag.root.to_string(),
+            pin_size: file_size,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            replicas: Vec::new(),
+            regions: Vec::new(),
+        })
+    }
+
+    async fn find_upload(&self, cid: &str) -> Result<Option<PinnedFile>> {
+        match self.invoke("upload/get", json!({ "root": { "/": cid } })).await {
+            Ok(upload) => Ok(Some(PinnedFile {
+                ipfs_hash: cid.to_string(),
+                pin_size: 0,
+                timestamp: upload["insertedAt"].as_str().unwrap_or_default().to_string(),
+                replicas: Vec::new(),
+                regions: Vec::new(),
+            })),
+            Err(e) if e.downcast_ref::<StorachaError>().is_some_and(|e| e.name == "UploadNotFound") => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Error returned in a w3up receipt
+#[derive(Debug)]
+struct StorachaError {
+    name: String,
+    message: String,
+}
+
+impl std::fmt::Display for StorachaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Storacha error ({}): {}", self.name, self.message)
+    }
+}
+
+impl std::error::Error for StorachaError {}
+
+impl PinningService for StorachaClient {
+    fn name(&self) -> &'static str {
+        "Storacha"
+    }
+
+    fn dag_params(&self) -> Option<DagParams> {
+        Some(STORACHA_DAG_PARAMS)
+    }
+
+    fn test_authentication(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            status!("Testing Storacha bridge authentication...");
+            self.invoke("upload/list", json!({ "size": 1 }))
+                .await
+                .with_context(|| "Authentication failed")?;
+            status!("✅ Authentication successful!");
+            Ok(())
+        })
+    }
+
+    /// Storacha has no per-upload names or keyvalues, so the metadata is not sent.
+    fn pin_file<'a>(
+        &'a self,
+        file_path: &'a Path,
+        _metadata: &'a PinataMetadata,
+        progress: &'a FileProgress,
+    ) -> BoxFuture<'a, Result<PinnedFile>> {
+        Box::pin(self.upload(file_path, progress))
+    }
+
+    fn find_pinned<'a>(&'a self, cid: Option<&'a str>, _sha256: Option<&'a str>) -> BoxFuture<'a, Result<Option<PinnedFile>>> {
+        Box::pin(async move {
+            match cid {
+                Some(cid) => self.find_upload(cid).await,
+                None => Ok(None),
+            }
+        })
+    }
+
+    /// Uploads are stored as soon as upload/add succeeds, so there is nothing to wait for.
+    fn wait_for_pin<'a>(&'a self, _cid: &'a str, _timeout: Duration) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(async { Ok(true) })
+    }
+}
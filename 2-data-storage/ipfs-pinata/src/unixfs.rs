@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::cid::Cid;
+
+pub const RAW_CODEC: u64 = 0x55;
+const DAG_PB_CODEC: u64 = 0x70;
+
+/// UnixFS `Data.DataType.Directory`
+const UNIXFS_DIRECTORY: u64 = 1;
+/// UnixFS `Data.DataType.File`
+const UNIXFS_FILE: u64 = 2;
+
+/// How a file is split into a UnixFS DAG. Providers use different defaults,
+/// and the root CID only matches when these match.
+#[derive(Debug, Clone, Copy)]
+pub struct DagParams {
+    pub chunk_size: usize,
+    /// Maximum number of children per intermediate node (balanced layout)
+    pub max_links: usize,
+}
+
+/// Kubo's defaults for `ipfs add --cid-version 1`, which Pinata follows
+pub const KUBO_DAG_PARAMS: DagParams = DagParams {
+    chunk_size: 256 * 1024,
+    max_links: 174,
+};
+
+/// A file's UnixFS DAG with raw leaves. Leaf bytes are not kept in memory;
+/// they are re-read from the file by offset when needed.
+pub struct FileDag {
+    pub root: Cid,
+    pub leaves: Vec<Leaf>,
+    /// Encoded dag-pb intermediate nodes, children before parents
+    pub nodes: Vec<(Cid, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Leaf {
+    pub cid: Cid,
+    pub offset: u64,
+    pub len: usize,
+}
+
+/// A child link while building a layer of the tree
+struct Link {
+    cid: Cid,
+    /// Serialized size of the child and all its descendants
+    tsize: u64,
+    /// Number of file bytes under the child
+    filesize: u64,
+}
+
+/// Chunks a file into raw leaves and builds a balanced UnixFS DAG over them,
+/// matching the layout Kubo and the JS importer produce for CIDv1 with raw leaves.
+pub fn build_file_dag(path: &Path, params: DagParams) -> Result<FileDag> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open file: {:?}", path))?;
+
+    let mut leaves = Vec::new();
+    let mut buffer = vec![0u8; params.chunk_size];
+    let mut offset = 0u64;
+    loop {
+        let len = read_chunk(&mut file, &mut buffer).with_context(|| format!("Failed to read file: {:?}", path))?;
+        // An empty file is still a single (empty) leaf
+        if len == 0 && !leaves.is_empty() {
+            break;
+        }
+        leaves.push(Leaf {
+            cid: Cid::sha256(RAW_CODEC, &buffer[..len]),
+            offset,
+            len,
+        });
+        offset += len as u64;
+        if len < params.chunk_size {
+            break;
+        }
+    }
+
+    let mut layer: Vec<Link> = leaves
+        .iter()
+        .map(|leaf| Link {
+            cid: leaf.cid,
+            tsize: leaf.len as u64,
+            filesize: leaf.len as u64,
+        })
+        .collect();
+
+    let mut nodes = Vec::new();
+    while layer.len() > 1 {
+        let mut parents = Vec::with_capacity(layer.len().div_ceil(params.max_links));
+        for children in layer.chunks(params.max_links) {
+            let bytes = encode_file_node(children);
+            let cid = Cid::sha256(DAG_PB_CODEC, &bytes);
+            parents.push(Link {
+                cid,
+                tsize: bytes.len() as u64 + children.iter().map(|child| child.tsize).sum::<u64>(),
+                filesize: children.iter().map(|child| child.filesize).sum(),
+            });
+            nodes.push((cid, bytes));
+        }
+        layer = parents;
+    }
+
+    Ok(FileDag {
+        root: layer[0].cid,
+        leaves,
+        nodes,
+    })
+}
+
+/// An entry of a directory while building its DAG: a file's root CID and
+/// serialized size, or a subdirectory's entries by name
+enum DirectoryEntry {
+    File(Cid, u64),
+    Directory(BTreeMap<String, DirectoryEntry>),
+}
+
+/// Builds the UnixFS directory DAG holding each file at its relative path,
+/// as Kubo builds it for a recursive CIDv1 add with raw leaves, and returns
+/// its root CID. `files` pairs each local path with its path inside the
+/// directory. Kubo shards very large directories (HAMT), which this doesn't,
+/// so their CIDs won't match.
+pub fn build_directory_dag(files: &[(PathBuf, String)], params: DagParams) -> Result<Cid> {
+    let mut root = BTreeMap::new();
+    for (file_path, relative_path) in files {
+        let dag = build_file_dag(file_path, params)?;
+        // Every node of a balanced tree is linked once, so the root's
+        // cumulative size is the sum of all of them
+        let tsize = dag.leaves.iter().map(|leaf| leaf.len as u64).sum::<u64>()
+            + dag.nodes.iter().map(|(_, bytes)| bytes.len() as u64).sum::<u64>();
+
+        let mut components: Vec<&str> = relative_path.split('/').collect();
+        let file_name = components.pop().unwrap_or_default();
+        let mut directory = &mut root;
+        for component in components {
+            let entry = directory
+                .entry(component.to_string())
+                .or_insert_with(|| DirectoryEntry::Directory(BTreeMap::new()));
+            let DirectoryEntry::Directory(entries) = entry else {
+                anyhow::bail!("{} is both a file and a directory", component);
+            };
+            directory = entries;
+        }
+        directory.insert(file_name.to_string(), DirectoryEntry::File(dag.root, tsize));
+    }
+    Ok(directory_node(&root).0)
+}
+
+/// CID and cumulative size of a directory node over its entries.
+fn directory_node(entries: &BTreeMap<String, DirectoryEntry>) -> (Cid, u64) {
+    // Links are sorted by name, which the BTreeMap's byte order gives
+    let links: Vec<(&str, Cid, u64)> = entries
+        .iter()
+        .map(|(name, entry)| {
+            let (cid, tsize) = match entry {
+                DirectoryEntry::File(cid, tsize) => (*cid, *tsize),
+                DirectoryEntry::Directory(entries) => directory_node(entries),
+            };
+            (name.as_str(), cid, tsize)
+        })
+        .collect();
+
+    let mut node = Vec::new();
+    for (name, cid, tsize) in &links {
+        let mut link = Vec::new();
+        write_bytes_field(&mut link, 1, &cid.to_bytes());
+        write_bytes_field(&mut link, 2, name.as_bytes());
+        write_varint_field(&mut link, 3, *tsize);
+        write_bytes_field(&mut node, 2, &link);
+    }
+    let mut data = Vec::new();
+    write_varint_field(&mut data, 1, UNIXFS_DIRECTORY);
+    write_bytes_field(&mut node, 1, &data);
+
+    let tsize = node.len() as u64 + links.iter().map(|(_, _, tsize)| tsize).sum::<u64>();
+    (Cid::sha256(DAG_PB_CODEC, &node), tsize)
+}
+
+/// Fills the buffer from the reader, returning fewer bytes only at end of file.
+fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Encodes a dag-pb PBNode for a UnixFS file node. dag-pb's canonical form
+/// writes Links (field 2) before Data (field 1).
+fn encode_file_node(children: &[Link]) -> Vec<u8> {
+    let mut node = Vec::new();
+    for child in children {
+        let mut link = Vec::new();
+        write_bytes_field(&mut link, 1, &child.cid.to_bytes());
+        write_bytes_field(&mut link, 2, b"");
+        write_varint_field(&mut link, 3, child.tsize);
+        write_bytes_field(&mut node, 2, &link);
+    }
+
+    let mut data = Vec::new();
+    write_varint_field(&mut data, 1, UNIXFS_FILE);
+    write_varint_field(&mut data, 3, children.iter().map(|child| child.filesize).sum());
+    for child in children {
+        write_varint_field(&mut data, 4, child.filesize);
+    }
+    write_bytes_field(&mut node, 1, &data);
+
+    node
+}
+
+pub fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(out, field << 3);
+    write_varint(out, value);
+}
+
+fn write_bytes_field(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(out, (field << 3) | 2);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
@@ -0,0 +1,128 @@
+use std::io::{self, Write};
+
+use anyhow::{Context, Result};
+
+use crate::list::{PinStatus, filter_name_prefix, print_table};
+use crate::pinata::{PinListQuery, PinListRow, PinataClient};
+
+#[derive(clap::Args)]
+#[command(group(clap::ArgGroup::new("target").required(true)))]
+pub struct UnpinArgs {
+    /// CID of the pin to remove
+    #[arg(long, group = "target")]
+    cid: Option<String>,
+
+    /// Remove every pin whose name starts with this prefix
+    #[arg(long, group = "target", value_parser = non_empty_prefix)]
+    name_prefix: Option<String>,
+
+    /// Show which pins would be removed without unpinning anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Skip the confirmation prompt
+    #[arg(short, long)]
+    yes: bool,
+}
+
+/// Arguments for `unpin-prefix`, the shorthand for rolling back a batch by its
+/// name prefix
+#[derive(clap::Args)]
+pub struct UnpinPrefixArgs {
+    /// Name prefix of the pins to remove, e.g. the batch's --name-prefix
+    #[arg(value_parser = non_empty_prefix)]
+    prefix: String,
+
+    /// Show which pins would be removed without unpinning anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Skip the confirmation prompt
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl From<&UnpinPrefixArgs> for UnpinArgs {
+    fn from(args: &UnpinPrefixArgs) -> Self {
+        Self {
+            cid: None,
+            name_prefix: Some(args.prefix.clone()),
+            dry_run: args.dry_run,
+            yes: args.yes,
+        }
+    }
+}
+
+/// An empty prefix would match, and unpin, every pin in the account.
+fn non_empty_prefix(prefix: &str) -> Result<String, String> {
+    match prefix.is_empty() {
+        true => Err("name prefix cannot be empty".to_string()),
+        false => Ok(prefix.to_string()),
+    }
+}
+
+pub async fn run(client: &PinataClient, args: &UnpinArgs) -> Result<()> {
+    let query = PinListQuery {
+        cid: args.cid.clone(),
+        status: Some(PinStatus::Pinned.as_str().to_string()),
+        name: args.name_prefix.clone(),
+        ..Default::default()
+    };
+
+    // hashContains is a substring match, so keep only the exact CID
+    let rows: Vec<PinListRow> = filter_name_prefix(client.list_all_pins(&query).await?, args.name_prefix.as_deref())
+        .into_iter()
+        .filter(|row| args.cid.as_ref().is_none_or(|cid| &row.ipfs_pin_hash == cid))
+        .collect();
+
+    if rows.is_empty() {
+        println!("No matching pins found.");
+        return Ok(());
+    }
+
+    print_table(&rows);
+
+    if args.dry_run {
+        println!("\nDry run: {} pins would be unpinned", rows.len());
+        return Ok(());
+    }
+
+    if !args.yes && !confirm(&format!("\nUnpin {} pins?", rows.len()))? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for row in &rows {
+        match client.unpin(&row.ipfs_pin_hash).await {
+            Ok(()) => println!("🗑️  Unpinned: {}", row.ipfs_pin_hash),
+            Err(e) => {
+                println!("❌ Failed to unpin {}: {:#}", row.ipfs_pin_hash, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\nUnpin Summary:");
+    println!("   ✅ Unpinned: {}", rows.len() - failed);
+    println!("   ❌ Failed: {}", failed);
+
+    if failed > 0 {
+        anyhow::bail!("{} pins could not be unpinned", failed);
+    }
+
+    Ok(())
+}
+
+/// Asks a yes/no question on stdin, defaulting to no.
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .with_context(|| "Failed to read confirmation")?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::hash_image;
+
+/// File extensions (lowercase) picked up when hashing a directory, videos
+/// included
+pub const IMAGE_EXTENSIONS: [&str; 15] = [
+    "jpg", "jpeg", "png", "tif", "tiff", "dng", "cr2", "nef", "arw", "heic", "heif", "webp",
+    "mp4", "mov", "m4v",
+];
+
+#[derive(Serialize, Debug)]
+struct BatchEntry {
+    image: String,
+    root_hash: Option<String>,
+    merkle_path: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct BatchSummary {
+    input_dir: String,
+    total_images: usize,
+    hashed: usize,
+    failed: usize,
+    entries: Vec<BatchEntry>,
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Every image under `dir`, in a stable order.
+fn find_images(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut images = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() && is_image(entry.path()) {
+            images.push(entry.into_path());
+        }
+    }
+    Ok(images)
+}
+
+/// Builds and saves a Merkle tree for every image under `input_dir`. Trees
+/// are saved under `out_dir` in the same layout as the images, so photos
+/// with the same name in different folders don't overwrite each other.
+/// Images that fail are reported and skipped; returns how many failed. With
+/// `salted`, each image's tree gets its own salt. With `tsa_url`, each root
+/// is timestamped by that authority.
+pub fn hash_directory(input_dir: &Path, out_dir: &Path, summary_path: Option<&Path>, salted: bool, tsa_url: Option<&str>) -> Result<usize, Box<dyn std::error::Error>> {
+    let images = find_images(input_dir)?;
+    println!("Hashing {} images in {}", images.len(), input_dir.display());
+
+    let mut entries = Vec::new();
+    for (index, image) in images.iter().enumerate() {
+        let relative_dir = image.parent()
+            .and_then(|parent| parent.strip_prefix(input_dir).ok())
+            .unwrap_or(Path::new(""));
+        let entry = match hash_image(image, &out_dir.join(relative_dir), salted, tsa_url) {
+            Ok((tree, merkle_path)) => {
+                let root_hash = hex::encode(&tree.root.hash);
+                println!("[{}/{}] {} {}", index + 1, images.len(), root_hash, image.display());
+                BatchEntry {
+                    image: image.display().to_string(),
+                    root_hash: Some(root_hash),
+                    merkle_path: Some(merkle_path.display().to_string()),
+                    error: None,
+                }
+            }
+            Err(e) => {
+                println!("[{}/{}] FAILED {}: {}", index + 1, images.len(), image.display(), e);
+                BatchEntry {
+                    image: image.display().to_string(),
+                    root_hash: None,
+                    merkle_path: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        entries.push(entry);
+    }
+
+    let failed = entries.iter().filter(|entry| entry.error.is_some()).count();
+    let summary = BatchSummary {
+        input_dir: input_dir.display().to_string(),
+        total_images: entries.len(),
+        hashed: entries.len() - failed,
+        failed,
+        entries,
+    };
+    println!("\nHashed: {}", summary.hashed);
+    println!("Failed: {}", summary.failed);
+    println!("Merkle trees saved under: {}", out_dir.display());
+
+    if let Some(summary_path) = summary_path {
+        std::fs::write(summary_path, serde_json::to_string_pretty(&summary)?)?;
+        println!("Summary saved to: {}", summary_path.display());
+    }
+    Ok(failed)
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+use serde::Serialize;
+
+use crate::{embed, exif_leaves, leaf};
+use crate::merkle::{SavedTree, build_merkle_tree, hash_leaf};
+
+/// A leaf in only one of the saved tree and the image
+#[derive(Serialize, Debug)]
+pub struct LeafEntry {
+    /// Unknown for a missing leaf when the tree predates saving leaf names
+    pub name: Option<String>,
+    /// Hex hash of the leaf as in the tree
+    pub hash: String,
+    /// The image's value for an added leaf; a missing leaf's is unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// A leaf whose value differs between the saved tree and the image
+#[derive(Serialize, Debug)]
+pub struct ChangedLeaf {
+    pub name: String,
+    pub stored_hash: String,
+    pub hash: String,
+    /// The image's value; the saved tree keeps hashes only
+    pub value: String,
+}
+
+/// How an image's leaves differ from its saved tree, leaf by leaf, so a
+/// failed verification says what was tampered with
+#[derive(Serialize, Debug)]
+pub struct TreeDiff {
+    pub valid: bool,
+    /// Hex root hash of the saved tree
+    pub stored_root: String,
+    /// Hex root hash rebuilt from the image
+    pub root: String,
+    /// Leaves of the saved tree the image no longer has
+    pub missing: Vec<LeafEntry>,
+    /// Leaves of the image the saved tree doesn't have
+    pub added: Vec<LeafEntry>,
+    pub changed: Vec<ChangedLeaf>,
+    /// Hex root embedded in the image, if it carries one, see `embed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedded_root: Option<String>,
+    /// Whether the embedded root is the saved tree's; absent without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedded_root_matches: Option<bool>,
+}
+
+/// Compares the image's leaves with its saved tree. Leaves are matched by
+/// hash first; of the rest, a missing and an added leaf with the same name
+/// are reported as one changed leaf. Trees saved without leaf names can only
+/// report missing and added leaves. A root embedded in the image is
+/// checked against the saved tree's too. A salted tree salts each leaf for its
+/// position, so a leaf is hashed with the salt of the stored position it
+/// is matched against, and still matches after leaves before it change.
+pub fn diff_image(image_path: &Path, stored_tree: &SavedTree) -> Result<TreeDiff, Box<dyn std::error::Error>> {
+    let salt = stored_tree.salt_bytes()?;
+    let leaves = exif_leaves(image_path, salt.as_deref())?;
+    let tree_leaves = leaf::tree_leaves(&leaves, salt.as_deref());
+    let root = build_merkle_tree(tree_leaves.clone()).ok_or("Failed to build Merkle tree")?;
+
+    let mut stored: Vec<Option<(Option<String>, Vec<u8>)>> = stored_tree.leaf_hashes()
+        .into_iter()
+        .enumerate()
+        .map(|(index, hash)| {
+            let name = stored_tree.leaf_names.as_ref().and_then(|names| names.get(index).cloned());
+            Some((name, hash))
+        })
+        .collect();
+
+    let mut unmatched = Vec::new();
+    for (leaf, tree_leaf) in leaves.iter().zip(&tree_leaves) {
+        let hash = hash_leaf(tree_leaf);
+        let hash_at = |index| match &salt {
+            Some(salt) => hash_leaf(&leaf::salted_leaf(&leaf::leaf_salt(salt, index), leaf)),
+            None => hash.clone(),
+        };
+        let matching = stored.iter_mut()
+            .enumerate()
+            .find(|(index, entry)| entry.as_ref().is_some_and(|(_, stored_hash)| *stored_hash == hash_at(*index)));
+        match matching {
+            Some((_, entry)) => *entry = None,
+            None => unmatched.push((leaf::decode(leaf)?, hash)),
+        }
+    }
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for (decoded, hash) in unmatched {
+        let same_name = stored.iter_mut()
+            .find(|entry| entry.as_ref().is_some_and(|(name, _)| name.as_deref() == Some(decoded.name.as_str())));
+        match same_name.and_then(Option::take) {
+            Some((_, stored_hash)) => changed.push(ChangedLeaf {
+                name: decoded.name,
+                stored_hash: hex::encode(stored_hash),
+                hash: hex::encode(hash),
+                value: decoded.value,
+            }),
+            None => added.push(LeafEntry {
+                name: Some(decoded.name),
+                hash: hex::encode(hash),
+                value: Some(decoded.value),
+            }),
+        }
+    }
+    let missing = stored.into_iter()
+        .flatten()
+        .map(|(name, hash)| LeafEntry { name, hash: hex::encode(hash), value: None })
+        .collect();
+    let embedded_root = embed::read_embedded_root(image_path)?.map(|embedded| embedded.root);
+    let stored_root = hex::encode(&stored_tree.root.hash);
+
+    Ok(TreeDiff {
+        valid: root.hash == stored_tree.root.hash,
+        root: hex::encode(&root.hash),
+        missing,
+        added,
+        changed,
+        embedded_root_matches: embedded_root.as_ref().map(|embedded| *embedded == stored_root),
+        embedded_root,
+        stored_root,
+    })
+}
@@ -0,0 +1,95 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::{exif_leaves, leaf};
+use crate::merkle::{DisclosedLeaf, MerkleNode, build_merkle_tree, hash_leaf};
+
+/// A leaf as published: its canonical encoding in hex, with its hex salt
+/// in a salted tree, or its hex hash only
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishedLeaf {
+    Value(String),
+    /// A disclosed leaf of a salted tree and its salt, see `leaf::leaf_salt`
+    Salted { value: String, salt: String },
+    Hash(String),
+}
+
+/// Every leaf of an image's tree, in order, with only the chosen tags in
+/// plaintext. Enough to rebuild the root, so publishing it proves those tags
+/// without revealing the others.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Disclosure {
+    /// Hex root hash of the image's tree
+    pub root: String,
+    pub leaves: Vec<PublishedLeaf>,
+}
+
+impl Disclosure {
+    /// The disclosed leaves, read back as `tag:value`.
+    pub fn values(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        self.leaves.iter()
+            .filter_map(|leaf| match leaf {
+                PublishedLeaf::Value(value) | PublishedLeaf::Salted { value, .. } => Some(value),
+                PublishedLeaf::Hash(_) => None,
+            })
+            .map(|value| Ok(leaf::decode(&hex::decode(value)?)?.to_string()))
+            .collect()
+    }
+
+    /// Whether the leaves rebuild to `root`, a hex root hash from a trusted
+    /// source. The disclosure's own `root` field is not trusted.
+    pub fn verify(&self, root: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let leaves = self.leaves.iter()
+            .map(|published| Ok(match published {
+                PublishedLeaf::Value(value) => DisclosedLeaf::Data(hex::decode(value)?),
+                PublishedLeaf::Salted { value, salt } => {
+                    DisclosedLeaf::Data(leaf::salted_leaf(&hex::decode(salt)?, &hex::decode(value)?))
+                }
+                PublishedLeaf::Hash(hash) => DisclosedLeaf::Hash(hex::decode(hash)?),
+            }))
+            .collect::<Result<Vec<_>, hex::FromHexError>>()?;
+        Ok(MerkleNode::from_hash(hex::decode(root)?).verify_disclosed(&leaves))
+    }
+}
+
+/// Publishes the image's leaves for `tags` in plaintext and the rest as
+/// hashes. For a salted tree, pass its salt; the disclosure carries only
+/// the salts of the disclosed leaves.
+pub fn disclose_tags(image_path: &Path, tags: &[String], salt: Option<&[u8]>) -> Result<Disclosure, Box<dyn std::error::Error>> {
+    let leaves = exif_leaves(image_path, salt)?;
+    let tree_leaves = leaf::tree_leaves(&leaves, salt);
+    let root = build_merkle_tree(tree_leaves.clone())
+        .ok_or("Failed to build Merkle tree")?;
+
+    let names = leaves.iter()
+        .map(|data| Ok(leaf::decode(data)?.name))
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+    for tag in tags {
+        if !names.contains(tag) {
+            return Err(format!("{} has no {} tag", image_path.display(), tag).into());
+        }
+    }
+
+    let published = leaves.iter()
+        .zip(&tree_leaves)
+        .zip(&names)
+        .enumerate()
+        .map(|(index, ((data, tree_leaf), name))| {
+            if !tags.contains(name) {
+                return PublishedLeaf::Hash(hex::encode(hash_leaf(tree_leaf)));
+            }
+            match salt {
+                Some(salt) => PublishedLeaf::Salted {
+                    value: hex::encode(data),
+                    salt: hex::encode(leaf::leaf_salt(salt, index)),
+                },
+                None => PublishedLeaf::Value(hex::encode(data)),
+            }
+        })
+        .collect();
+    Ok(Disclosure {
+        root: hex::encode(&root.hash),
+        leaves: published,
+    })
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::jpeg;
+
+/// What is embedded in an image: its tree's root, and a signature over it
+/// if one was given. Kept in an APP11 segment, which neither the leaves nor
+/// the image data hash cover, so the root still matches the new file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EmbeddedRoot {
+    /// Hex root hash of the image's tree
+    pub root: String,
+    /// Hex signature over the root, made with the signer's own key; it is
+    /// carried, not checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// Writes a copy of the JPEG to `out` with the root embedded.
+pub fn embed_root(image_path: &Path, out: &Path, embedded: &EmbeddedRoot) -> Result<(), Box<dyn std::error::Error>> {
+    if !jpeg::is_jpeg(image_path)? {
+        return Err(format!("Can only embed a root in a JPEG: {}", image_path.display()).into());
+    }
+    jpeg::write_with_root(image_path, out, &serde_json::to_vec(embedded)?)
+}
+
+/// The root embedded in the image, if it is a JPEG with one.
+pub fn read_embedded_root(image_path: &Path) -> Result<Option<EmbeddedRoot>, Box<dyn std::error::Error>> {
+    if !jpeg::is_jpeg(image_path)? {
+        return Ok(None);
+    }
+    match jpeg::embedded_root(image_path)? {
+        Some(payload) => Ok(Some(serde_json::from_slice(&payload)?)),
+        None => Ok(None),
+    }
+}
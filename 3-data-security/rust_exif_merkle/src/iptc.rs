@@ -0,0 +1,98 @@
+use crate::leaf::{Source, named_leaf};
+
+/// Marker byte that starts every IIM dataset
+const TAG_MARKER: u8 = 0x1C;
+
+/// The application record, which holds the editorial fields
+const APPLICATION_RECORD: u8 = 2;
+
+/// Names of the application record's datasets, as in the IIM specification
+const DATASET_NAMES: [(u8, &str); 30] = [
+    (0, "ApplicationRecordVersion"),
+    (5, "ObjectName"),
+    (7, "EditStatus"),
+    (10, "Urgency"),
+    (15, "Category"),
+    (20, "SupplementalCategories"),
+    (22, "FixtureIdentifier"),
+    (25, "Keywords"),
+    (26, "ContentLocationCode"),
+    (27, "ContentLocationName"),
+    (40, "SpecialInstructions"),
+    (55, "DateCreated"),
+    (60, "TimeCreated"),
+    (62, "DigitalCreationDate"),
+    (63, "DigitalCreationTime"),
+    (65, "OriginatingProgram"),
+    (70, "ProgramVersion"),
+    (80, "By-line"),
+    (85, "By-lineTitle"),
+    (90, "City"),
+    (92, "Sub-location"),
+    (95, "Province-State"),
+    (100, "Country-PrimaryLocationCode"),
+    (101, "Country-PrimaryLocationName"),
+    (103, "OriginalTransmissionReference"),
+    (105, "Headline"),
+    (110, "Credit"),
+    (115, "Source"),
+    (116, "CopyrightNotice"),
+    (120, "Caption-Abstract"),
+];
+
+fn dataset_name(dataset: u8) -> String {
+    DATASET_NAMES.iter()
+        .find(|(number, _)| *number == dataset)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("{}:{}", APPLICATION_RECORD, dataset))
+}
+
+/// Text as UTF-8, or as Latin-1 for older records that aren't.
+fn decode_text(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(text) => text.to_string(),
+        Err(_) => data.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// An IPTC value as text: ApplicationRecordVersion is a number, the other
+/// datasets text.
+pub fn display_value(name: &str, data: &[u8]) -> String {
+    match (name, data) {
+        ("ApplicationRecordVersion", [high, low]) => u16::from_be_bytes([*high, *low]).to_string(),
+        _ => decode_text(data),
+    }
+}
+
+/// One leaf per dataset in the record's application record, holding its raw
+/// bytes and named by the dataset, e.g. `IPTC:By-line` or
+/// `IPTC:Caption-Abstract`. Repeatable datasets such as Keywords give a
+/// leaf per value, and unnamed datasets are named by number, e.g. `IPTC:2:221`.
+pub fn iptc_leaves(record: &[u8]) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut leaves = Vec::new();
+    let mut rest = record;
+    // Records are often padded with zeros after the last dataset
+    while rest.first() == Some(&TAG_MARKER) {
+        // Marker, record and dataset numbers, then the data length
+        let header = rest.get(..5).ok_or("Truncated IPTC dataset")?;
+        let (record_number, dataset) = (header[1], header[2]);
+        let len = u16::from_be_bytes([header[3], header[4]]);
+        let (len, data_at) = if len & 0x8000 == 0 {
+            (len as usize, 5)
+        } else {
+            // Extended dataset: the low bits give the size of the length
+            let len_size = (len & 0x7FFF) as usize;
+            let len_bytes = rest.get(5..5 + len_size).filter(|bytes| bytes.len() <= 8).ok_or("Invalid IPTC dataset length")?;
+            let len = len_bytes.iter().fold(0usize, |len, &b| len << 8 | b as usize);
+            (len, 5 + len_size)
+        };
+        let data = rest.get(data_at..data_at + len).ok_or("Truncated IPTC dataset")?;
+        rest = &rest[data_at + len..];
+
+        if record_number != APPLICATION_RECORD {
+            continue;
+        }
+        leaves.push(named_leaf(Source::Iptc, &dataset_name(dataset), data));
+    }
+    Ok(leaves)
+}
@@ -0,0 +1,252 @@
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// ftyp brands of HEIF images: HEIC stills and sequences from iPhones, and
+/// the generic image brands
+const HEIF_BRANDS: [&[u8; 4]; 8] = [b"mif1", b"msf1", b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis"];
+
+/// Reads big-endian fields off the front of a box body
+pub struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Fields { data }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+        if len > self.data.len() {
+            return Err("Truncated ISO-BMFF box".into());
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Box<dyn std::error::Error>> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Box<dyn std::error::Error>> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    /// An unsigned field `size` bytes wide, as iloc sizes its offsets.
+    fn uint(&mut self, size: u8) -> Result<u64, Box<dyn std::error::Error>> {
+        match size {
+            0 => Ok(0),
+            4 => Ok(self.u32()? as u64),
+            8 => self.u64(),
+            _ => Err(format!("Unsupported iloc field size: {}", size).into()),
+        }
+    }
+
+    /// Version and flags of a full box, returning the version.
+    pub fn full_box_header(&mut self) -> Result<u8, Box<dyn std::error::Error>> {
+        Ok(self.take(4)?[0])
+    }
+
+    pub fn rest(self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// A box's type and body
+pub type IsoBox<'a> = ([u8; 4], &'a [u8]);
+
+/// The boxes within a box body.
+pub fn child_boxes(body: &[u8]) -> Result<Vec<IsoBox<'_>>, Box<dyn std::error::Error>> {
+    let mut fields = Fields::new(body);
+    let mut boxes = Vec::new();
+    while !fields.data.is_empty() {
+        let size = fields.u32()? as u64;
+        let box_type: [u8; 4] = fields.take(4)?.try_into()?;
+        let body_len = match size {
+            0 => fields.data.len() as u64,
+            1 => fields.u64()?.checked_sub(16).ok_or("Invalid ISO-BMFF box size")?,
+            size => size.checked_sub(8).ok_or("Invalid ISO-BMFF box size")?,
+        };
+        boxes.push((box_type, fields.take(body_len as usize)?));
+    }
+    Ok(boxes)
+}
+
+pub fn find_box<'a>(boxes: &[IsoBox<'a>], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    boxes.iter()
+        .find(|(child_type, _)| child_type == box_type)
+        .map(|(_, body)| *body)
+}
+
+/// The major and compatible brands in the file's leading ftyp box, or None
+/// if it doesn't start with one.
+pub fn ftyp_brands(path: &Path) -> Result<Option<Vec<[u8; 4]>>, Box<dyn std::error::Error>> {
+    let mut header = Vec::new();
+    File::open(path)?.take(256).read_to_end(&mut header)?;
+    if header.get(4..8) != Some(b"ftyp".as_slice()) {
+        return Ok(None);
+    }
+    let size = u32::from_be_bytes(header[..4].try_into()?) as usize;
+    let Some(ftyp) = header.get(8..size.min(header.len())) else {
+        return Ok(None);
+    };
+    // Major brand, minor version, then compatible brands
+    Ok(Some(ftyp.chunks_exact(4)
+        .enumerate()
+        .filter(|(index, _)| *index != 1)
+        .map(|(_, brand)| brand.try_into())
+        .collect::<Result<_, _>>()?))
+}
+
+/// Whether the file is HEIF, going by the brands in its leading ftyp box.
+pub fn is_heif(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(ftyp_brands(path)?.is_some_and(|brands| {
+        brands.iter().any(|brand| HEIF_BRANDS.contains(&brand))
+    }))
+}
+
+/// The body of the file's first top-level box of type `box_type`. Other
+/// top-level boxes, including the media data in mdat, are skipped without
+/// being read.
+pub fn read_top_level_box(reader: &mut BufReader<File>, box_type: &[u8; 4]) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    loop {
+        let mut header = [0u8; 8];
+        match reader.read_exact(&mut header) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        let size = u32::from_be_bytes(header[..4].try_into()?) as u64;
+        let body_len = match size {
+            // The last box, running to the end of the file
+            0 if &header[4..] == box_type => None,
+            0 => return Ok(None),
+            1 => {
+                let mut large_size = [0u8; 8];
+                reader.read_exact(&mut large_size)?;
+                Some(u64::from_be_bytes(large_size).checked_sub(16).ok_or("Invalid ISO-BMFF box size")?)
+            }
+            size => Some(size.checked_sub(8).ok_or("Invalid ISO-BMFF box size")?),
+        };
+
+        if &header[4..] == box_type {
+            let mut body = Vec::new();
+            match body_len {
+                Some(len) => reader.take(len).read_to_end(&mut body)?,
+                None => reader.read_to_end(&mut body)?,
+            };
+            return Ok(Some(body));
+        }
+        reader.seek_relative(body_len.unwrap_or_default() as i64)?;
+    }
+}
+
+/// The ID of the item whose type is Exif, from the iinf box.
+fn exif_item_id(iinf: &[u8]) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+    let mut fields = Fields::new(iinf);
+    let version = fields.full_box_header()?;
+    // Entry count; the infe boxes follow
+    fields.take(if version == 0 { 2 } else { 4 })?;
+
+    for (box_type, infe) in child_boxes(fields.rest())? {
+        let mut infe = Fields::new(infe);
+        let version = infe.full_box_header()?;
+        // Item types were added in version 2
+        if &box_type != b"infe" || version < 2 {
+            continue;
+        }
+        let item_id = if version == 2 { infe.u16()? as u32 } else { infe.u32()? };
+        infe.u16()?; // protection index
+        if infe.take(4)? == b"Exif" {
+            return Ok(Some(item_id));
+        }
+    }
+    Ok(None)
+}
+
+/// Where an item's bytes are, from the iloc box
+struct ItemLocation {
+    /// 0 for offsets into the file, 1 for offsets into the idat box
+    construction_method: u8,
+    base_offset: u64,
+    /// (offset, length)
+    extents: Vec<(u64, u64)>,
+}
+
+fn item_location(iloc: &[u8], item_id: u32) -> Result<Option<ItemLocation>, Box<dyn std::error::Error>> {
+    let mut fields = Fields::new(iloc);
+    let version = fields.full_box_header()?;
+    let sizes = fields.u16()?;
+    let offset_size = (sizes >> 12) as u8;
+    let length_size = (sizes >> 8 & 0xF) as u8;
+    let base_offset_size = (sizes >> 4 & 0xF) as u8;
+    let index_size = if version == 0 { 0 } else { (sizes & 0xF) as u8 };
+    let item_count = if version < 2 { fields.u16()? as u32 } else { fields.u32()? };
+
+    for _ in 0..item_count {
+        let id = if version < 2 { fields.u16()? as u32 } else { fields.u32()? };
+        let construction_method = if version == 0 { 0 } else { (fields.u16()? & 0xF) as u8 };
+        fields.u16()?; // data reference index
+        let base_offset = fields.uint(base_offset_size)?;
+        let extent_count = fields.u16()?;
+        let mut extents = Vec::new();
+        for _ in 0..extent_count {
+            fields.uint(index_size)?;
+            let offset = fields.uint(offset_size)?;
+            let length = fields.uint(length_size)?;
+            extents.push((offset, length));
+        }
+        if id == item_id {
+            return Ok(Some(ItemLocation { construction_method, base_offset, extents }));
+        }
+    }
+    Ok(None)
+}
+
+/// The raw EXIF (TIFF) data in the image's Exif item, if it has one.
+pub fn exif_item(path: &Path) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let meta = read_top_level_box(&mut reader, b"meta")?
+        .ok_or_else(|| format!("No meta box in {}", path.display()))?;
+    let mut meta_fields = Fields::new(&meta);
+    meta_fields.full_box_header()?;
+    let boxes = child_boxes(meta_fields.rest())?;
+
+    let iinf = find_box(&boxes, b"iinf").ok_or("No iinf box in HEIF meta box")?;
+    let Some(item_id) = exif_item_id(iinf)? else {
+        return Ok(None);
+    };
+    let iloc = find_box(&boxes, b"iloc").ok_or("No iloc box in HEIF meta box")?;
+    let location = item_location(iloc, item_id)?.ok_or("Exif item has no location")?;
+
+    let mut item = Vec::new();
+    for (offset, length) in location.extents {
+        let start = location.base_offset + offset;
+        match location.construction_method {
+            0 => {
+                reader.seek(SeekFrom::Start(start))?;
+                let mut extent = vec![0u8; length as usize];
+                reader.read_exact(&mut extent)?;
+                item.extend(extent);
+            }
+            1 => {
+                let idat = find_box(&boxes, b"idat").ok_or("No idat box in HEIF meta box")?;
+                let extent = idat.get(start as usize..(start + length) as usize)
+                    .ok_or("Exif item runs past the idat box")?;
+                item.extend_from_slice(extent);
+            }
+            method => return Err(format!("Unsupported HEIF item construction method: {}", method).into()),
+        }
+    }
+
+    // The item starts with the offset of the TIFF header past this field,
+    // usually skipping an "Exif\0\0" marker
+    let mut fields = Fields::new(&item);
+    let header_offset = fields.u32()? as usize;
+    let tiff = fields.rest().get(header_offset..).ok_or("Invalid Exif header offset in HEIF item")?;
+    Ok(Some(tiff.to_vec()))
+}
@@ -0,0 +1,335 @@
+use std::fs::File;
+use std::io::{Read, BufReader, Seek};
+use std::path::Path;
+use sha2::{Digest, Sha256};
+
+use crate::mpf;
+use crate::tiff::TIFF_HEADERS;
+
+/// Marker of the APP1 segments that hold EXIF and XMP
+pub const APP1: u8 = 0xE1;
+
+/// Marker of the APP2 segments that hold ICC profiles and the MPF index
+pub const APP2: u8 = 0xE2;
+
+/// Marker of the APP11 segment a Merkle root is embedded in
+const APP11: u8 = 0xEB;
+
+/// Marker of the APP13 segments that hold Photoshop image resources
+const APP13: u8 = 0xED;
+
+/// Start of scan: the entropy-coded image data follows
+const SOS: u8 = 0xDA;
+const EOI: u8 = 0xD9;
+
+/// Comment segments, which like the APPn segments hold no image data
+const COM: u8 = 0xFE;
+
+/// Identifier in front of EXIF in an APP1 segment
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// Identifier in front of an XMP packet in an APP1 segment
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// Identifier in front of a chunk of extended XMP in an APP1 segment
+const XMP_EXTENSION_HEADER: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+
+/// Length of the GUID naming an extended XMP packet, an MD5 in hex
+const XMP_GUID_LEN: usize = 32;
+
+/// Identifier in front of an embedded Merkle root in an APP11 segment
+const ROOT_HEADER: &[u8] = b"starling-merkle-root\0";
+
+/// Identifier in front of Photoshop image resources in an APP13 segment
+const PHOTOSHOP_HEADER: &[u8] = b"Photoshop 3.0\0";
+
+/// ID of the image resource holding the IPTC-NAA record
+const IPTC_RESOURCE_ID: u16 = 0x0404;
+
+pub fn is_jpeg(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut soi = [0u8; 2];
+    let read = File::open(path)?.read(&mut soi)?;
+    Ok(read == soi.len() && soi == [0xFF, 0xD8])
+}
+
+/// The payload of every segment with `marker`, in file order. Other
+/// segments are skipped, and reading stops where the image data starts.
+pub fn read_segments(path: &Path, marker: u8) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    Ok(read_segments_at(path, marker)?
+        .into_iter()
+        .map(|(_, payload)| payload)
+        .collect())
+}
+
+/// A segment's payload and the file offset it starts at
+pub type Segment = (u64, Vec<u8>);
+
+/// Like `read_segments`, with the file offset each payload starts at.
+pub fn read_segments_at(path: &Path, marker: u8) -> Result<Vec<Segment>, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    if buf != [0xFF, 0xD8] {
+        return Err(format!("Not a JPEG: {}", path.display()).into());
+    }
+
+    let mut segments = Vec::new();
+    loop {
+        reader.read_exact(&mut buf)?;
+        if buf[0] != 0xFF {
+            return Err(format!("Invalid JPEG marker in {}", path.display()).into());
+        }
+        match buf[1] {
+            SOS | EOI => return Ok(segments),
+            // Fill bytes, and markers without a payload
+            0xFF => reader.seek_relative(-1)?,
+            0x01 | 0xD0..=0xD7 => {}
+            segment_marker => {
+                // The length counts its own two bytes
+                reader.read_exact(&mut buf)?;
+                let len = (u16::from_be_bytes(buf) as usize).checked_sub(2)
+                    .ok_or_else(|| format!("Invalid JPEG segment length in {}", path.display()))?;
+                if segment_marker == marker {
+                    let offset = reader.stream_position()?;
+                    let mut payload = vec![0u8; len];
+                    reader.read_exact(&mut payload)?;
+                    segments.push((offset, payload));
+                } else {
+                    reader.seek_relative(len as i64)?;
+                }
+            }
+        }
+    }
+}
+
+/// The XMP packet in the image's APP1 segments, if there is one.
+pub fn xmp_packet(path: &Path) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    for segment in read_segments(path, APP1)? {
+        if let Some(packet) = segment.strip_prefix(XMP_HEADER) {
+            return Ok(Some(String::from_utf8(packet.to_vec())?));
+        }
+    }
+    Ok(None)
+}
+
+/// The TIFF data of the image's EXIF, joined from every APP1 segment it
+/// spans. EXIF too large for one segment, as with the big maker notes of
+/// some Sony and Fujifilm cameras, continues in further `Exif` segments
+/// without a TIFF header of their own.
+pub fn exif_blob(path: &Path) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut blob: Option<Vec<u8>> = None;
+    for segment in read_segments(path, APP1)? {
+        let Some(data) = segment.strip_prefix(EXIF_HEADER) else {
+            continue;
+        };
+        let starts_tiff = TIFF_HEADERS.iter().any(|header| data.starts_with(header));
+        match &mut blob {
+            None if starts_tiff => blob = Some(data.to_vec()),
+            Some(blob) if !starts_tiff => blob.extend(data),
+            Some(_) => return Err(format!("More than one EXIF block in {}", path.display()).into()),
+            None => return Err(format!("EXIF segment without a TIFF header in {}", path.display()).into()),
+        }
+    }
+    Ok(blob)
+}
+
+/// The extended XMP the main packet refers to by GUID, joined from its
+/// APP1 chunks. Packets too large for one segment move the overflow here,
+/// in chunks giving the GUID, the full length and their offset into it.
+pub fn extended_xmp_packet(path: &Path, main_packet: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let invalid = || format!("Invalid extended XMP in {}", path.display());
+    let mut full_len = None;
+    let mut chunks = Vec::new();
+    for segment in read_segments(path, APP1)? {
+        let Some(chunk) = segment.strip_prefix(XMP_EXTENSION_HEADER) else {
+            continue;
+        };
+        // Only the packet the main one names; others are left over from edits
+        let guid = std::str::from_utf8(chunk.get(..XMP_GUID_LEN).ok_or_else(invalid)?)?;
+        if !main_packet.contains(guid) {
+            continue;
+        }
+        let header = chunk.get(XMP_GUID_LEN..XMP_GUID_LEN + 8).ok_or_else(invalid)?;
+        let chunk_full_len = u32::from_be_bytes(header[..4].try_into()?) as usize;
+        if *full_len.get_or_insert(chunk_full_len) != chunk_full_len {
+            return Err(invalid().into());
+        }
+        let offset = u32::from_be_bytes(header[4..].try_into()?) as usize;
+        chunks.push((offset, chunk[XMP_GUID_LEN + 8..].to_vec()));
+    }
+    let Some(full_len) = full_len else {
+        return Ok(None);
+    };
+
+    if chunks.iter().map(|(_, data)| data.len()).sum::<usize>() != full_len {
+        return Err(format!("Extended XMP in {} is missing chunks", path.display()).into());
+    }
+    let mut packet = vec![0u8; full_len];
+    for (offset, data) in chunks {
+        packet.get_mut(offset..offset + data.len())
+            .ok_or_else(invalid)?
+            .copy_from_slice(&data);
+    }
+    Ok(Some(String::from_utf8(packet)?))
+}
+
+/// The IPTC-NAA record in the image's Photoshop resources, if there is one.
+/// Resources too large for one APP13 segment continue in the next.
+pub fn iptc_record(path: &Path) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let resources: Vec<u8> = read_segments(path, APP13)?
+        .iter()
+        .filter_map(|segment| segment.strip_prefix(PHOTOSHOP_HEADER))
+        .flatten()
+        .copied()
+        .collect();
+
+    let mut rest = resources.as_slice();
+    while !rest.is_empty() {
+        // "8BIM", resource ID, a Pascal-string name padded to an even
+        // length, then the data size and the data, also padded
+        let truncated = || format!("Truncated Photoshop resource in {}", path.display());
+        if !rest.starts_with(b"8BIM") {
+            return Err(format!("Invalid Photoshop resource in {}", path.display()).into());
+        }
+        let id: [u8; 2] = rest.get(4..6).ok_or_else(truncated)?.try_into()?;
+        let name_len = *rest.get(6).ok_or_else(truncated)? as usize;
+        let size_at = 6 + (name_len + 2) / 2 * 2;
+        let size: [u8; 4] = rest.get(size_at..size_at + 4).ok_or_else(truncated)?.try_into()?;
+        let size = u32::from_be_bytes(size) as usize;
+        let data = rest.get(size_at + 4..size_at + 4 + size).ok_or_else(truncated)?;
+        if u16::from_be_bytes(id) == IPTC_RESOURCE_ID {
+            return Ok(Some(data.to_vec()));
+        }
+        rest = rest.get(size_at + 4 + size + size % 2..).unwrap_or_default();
+    }
+    Ok(None)
+}
+
+/// SHA-256 of the primary image's data: every byte from SOI to its EOI
+/// except the APPn and COM segments, which hold metadata (EXIF, XMP, IPTC,
+/// ICC profiles, thumbnails) rather than pixels. The scans and the tables
+/// they are decoded with are included, so editing or re-encoding the pixels
+/// changes it, while rewriting the metadata doesn't.
+pub fn image_data_hash(path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let data = std::fs::read(path)?;
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err(format!("Not a JPEG: {}", path.display()).into());
+    }
+    let truncated = || format!("Truncated JPEG: {}", path.display());
+
+    let mut hasher = Sha256::new();
+    hasher.update(&data[..2]);
+    let mut pos = 2;
+    loop {
+        let marker = *data.get(pos + 1).ok_or_else(truncated)?;
+        if data[pos] != 0xFF {
+            return Err(format!("Invalid JPEG marker in {}", path.display()).into());
+        }
+        match marker {
+            EOI => {
+                hasher.update([0xFF, EOI]);
+                return Ok(hasher.finalize().to_vec());
+            }
+            // Fill bytes
+            0xFF => pos += 1,
+            // Markers without a payload
+            0x01 | 0xD0..=0xD7 => {
+                hasher.update([0xFF, marker]);
+                pos += 2;
+            }
+            _ => {
+                // The length counts its own two bytes, but not the marker's
+                let len: [u8; 2] = data.get(pos + 2..pos + 4).ok_or_else(truncated)?.try_into()?;
+                let len = u16::from_be_bytes(len) as usize;
+                if len < 2 {
+                    return Err(format!("Invalid JPEG segment length in {}", path.display()).into());
+                }
+                let segment = data.get(pos..pos + 2 + len).ok_or_else(truncated)?;
+                if !matches!(marker, 0xE0..=0xEF | COM) {
+                    hasher.update(segment);
+                }
+                pos += segment.len();
+
+                if marker == SOS {
+                    // Entropy-coded data runs to the next marker, other than
+                    // a stuffed 0xFF00 or a restart marker
+                    let start = pos;
+                    while !(data.get(pos) == Some(&0xFF)
+                        && data.get(pos + 1).is_some_and(|&next| next != 0 && !(0xD0..=0xD7).contains(&next)))
+                    {
+                        if pos >= data.len() {
+                            return Err(truncated().into());
+                        }
+                        pos += 1;
+                    }
+                    hasher.update(&data[start..pos]);
+                }
+            }
+        }
+    }
+}
+
+/// The payload of the APP11 segment holding an embedded Merkle root, if
+/// there is one.
+pub fn embedded_root(path: &Path) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    Ok(read_segments(path, APP11)?
+        .into_iter()
+        .find_map(|segment| segment.strip_prefix(ROOT_HEADER).map(<[u8]>::to_vec)))
+}
+
+/// Writes a copy of the JPEG to `out` with `payload` in an APP11 segment,
+/// in place of any root embedded before. It goes straight after the
+/// leading APP0 and APP1 segments, ahead of any APP2 MPF index, whose image
+/// offsets count from inside that segment and so must not have anything
+/// inserted between them and the images. The MPF index's size of the
+/// primary image is updated to match; the rest of the file is copied as it
+/// is.
+pub fn write_with_root(path: &Path, out: &Path, payload: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let data = std::fs::read(path)?;
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err(format!("Not a JPEG: {}", path.display()).into());
+    }
+    let segment_len = u16::try_from(2 + ROOT_HEADER.len() + payload.len())
+        .map_err(|_| "Embedded root too large for a JPEG segment")?;
+    let truncated = || format!("Truncated JPEG: {}", path.display());
+
+    let mut root_segment = vec![0xFF, APP11];
+    root_segment.extend(segment_len.to_be_bytes());
+    root_segment.extend(ROOT_HEADER);
+    root_segment.extend(payload);
+
+    let mut output = data[..2].to_vec();
+    // Where each APP2 segment starts in `output`
+    let mut app2_segments = Vec::new();
+    let mut inserted = false;
+    let mut pos = 2;
+    // Copy the APPn and COM segments up to the first segment of image data
+    while data.get(pos) == Some(&0xFF) && data.get(pos + 1).is_some_and(|&marker| matches!(marker, 0xE0..=0xEF | COM)) {
+        let len: [u8; 2] = data.get(pos + 2..pos + 4).ok_or_else(truncated)?.try_into()?;
+        let segment = data.get(pos..pos + 2 + u16::from_be_bytes(len) as usize).ok_or_else(truncated)?;
+        if !inserted && !matches!(segment[1], 0xE0 | 0xE1) {
+            output.extend(&root_segment);
+            inserted = true;
+        }
+        if segment[1] == APP2 {
+            app2_segments.push((output.len(), segment.len()));
+        }
+        if !(segment[1] == APP11 && segment.get(4..).is_some_and(|payload| payload.starts_with(ROOT_HEADER))) {
+            output.extend(segment);
+        }
+        pos += segment.len();
+    }
+    if !inserted {
+        output.extend(&root_segment);
+    }
+    output.extend(&data[pos..]);
+
+    // Only the primary image's segments changed length
+    let delta = output.len() as i64 - data.len() as i64;
+    for (start, len) in app2_segments {
+        mpf::shift_primary_size(&mut output[start + 4..start + len], delta)?;
+    }
+
+    std::fs::write(out, output)?;
+    Ok(())
+}
@@ -0,0 +1,323 @@
+use std::fmt;
+use exif::{Context, In, Rational, SRational, Tag, Value};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::iptc;
+
+/// Version of the leaf encoding, the first byte of every leaf. Bump it for
+/// any change to the bytes below or to how `merkle` hashes them into a
+/// tree, since it changes every root.
+pub const LEAF_VERSION: u8 = 2;
+
+/// Length of the random salt of a salted tree
+const SALT_LEN: usize = 32;
+
+/// Tag number no EXIF tag has, for showing maker note values without any
+/// tag's own formatting
+const UNNAMED_TAG: Tag = Tag(Context::Tiff, 0xFFFF);
+
+/// EXIF tag contexts, numbered by their position here in leaves
+const CONTEXTS: [Context; 4] = [Context::Tiff, Context::Exif, Context::Gps, Context::Interop];
+
+/// Where a leaf's value comes from, the second byte of every leaf
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Exif = 1,
+    Xmp = 2,
+    Iptc = 3,
+    Movie = 4,
+    /// A hash of the image data itself, binding the metadata to its pixels
+    ImageData = 5,
+    /// A JPEG's Multi-Picture Format index and the images it lists
+    Mpf = 6,
+    /// An entry of a maker note, a leaf of its subtree
+    MakerNote = 7,
+    /// The root of the maker note's subtree, a leaf of the main tree
+    MakerNoteRoot = 8,
+}
+
+impl Source {
+    fn from_byte(byte: u8) -> Option<Self> {
+        [
+            Source::Exif, Source::Xmp, Source::Iptc, Source::Movie, Source::ImageData, Source::Mpf,
+            Source::MakerNote, Source::MakerNoteRoot,
+        ]
+            .into_iter()
+            .find(|source| *source as u8 == byte)
+    }
+
+    /// Prefix on the names of its leaves, keeping them apart from EXIF tags
+    fn name_prefix(self) -> &'static str {
+        match self {
+            Source::Xmp => "XMP:",
+            Source::Iptc => "IPTC:",
+            Source::Mpf => "MPF:",
+            Source::MakerNote => "MakerNote:",
+            Source::Exif | Source::Movie | Source::ImageData | Source::MakerNoteRoot => "",
+        }
+    }
+}
+
+/// A leaf read back for people: its name, e.g. `DateTime` or
+/// `XMP:dc:creator[1]`, and its value as text
+#[derive(Debug, Clone)]
+pub struct DecodedLeaf {
+    pub name: String,
+    pub value: String,
+}
+
+impl DecodedLeaf {
+    /// The value without the quotes EXIF text reads back in, e.g. `Canon`
+    /// rather than `"Canon"`.
+    pub fn unquoted_value(&self) -> &str {
+        self.value.strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(&self.value)
+    }
+}
+
+impl fmt::Display for DecodedLeaf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.name, self.value)
+    }
+}
+
+fn be_bytes<T, const N: usize>(values: &[T], to_bytes: impl Fn(&T) -> [u8; N]) -> Vec<u8> {
+    values.iter().flat_map(to_bytes).collect()
+}
+
+/// The value's TIFF field type, count and bytes, big-endian whatever the
+/// byte order of the file it came from.
+fn encode_value(value: &Value) -> (u16, u32, Vec<u8>) {
+    let (field_type, count, bytes) = match value {
+        Value::Byte(values) => (1, values.len(), values.clone()),
+        Value::Ascii(strings) => {
+            // Each string NUL-terminated, as TIFF stores them
+            let bytes: Vec<u8> = strings.iter().flat_map(|s| s.iter().copied().chain([0])).collect();
+            (2, bytes.len(), bytes)
+        }
+        Value::Short(values) => (3, values.len(), be_bytes(values, |v| v.to_be_bytes())),
+        Value::Long(values) => (4, values.len(), be_bytes(values, |v| v.to_be_bytes())),
+        Value::Rational(values) => (5, values.len(), be_bytes(values, |v| {
+            (u64::from(v.num) << 32 | u64::from(v.denom)).to_be_bytes()
+        })),
+        Value::SByte(values) => (6, values.len(), be_bytes(values, |v| v.to_be_bytes())),
+        Value::Undefined(values, _) => (7, values.len(), values.clone()),
+        Value::SShort(values) => (8, values.len(), be_bytes(values, |v| v.to_be_bytes())),
+        Value::SLong(values) => (9, values.len(), be_bytes(values, |v| v.to_be_bytes())),
+        Value::SRational(values) => (10, values.len(), be_bytes(values, |v| {
+            ((v.num as u32 as u64) << 32 | v.denom as u32 as u64).to_be_bytes()
+        })),
+        Value::Float(values) => (11, values.len(), be_bytes(values, |v| v.to_be_bytes())),
+        Value::Double(values) => (12, values.len(), be_bytes(values, |v| v.to_be_bytes())),
+        // Types the reader doesn't know have no value, only a count
+        Value::Unknown(field_type, count, _) => return (*field_type, *count, Vec::new()),
+    };
+    (field_type, count as u32, bytes)
+}
+
+fn chunks<const N: usize>(bytes: &[u8]) -> impl Iterator<Item = [u8; N]> + '_ {
+    bytes.chunks_exact(N).map(|chunk| chunk.try_into().expect("chunks are N bytes"))
+}
+
+fn decode_value(field_type: u16, count: u32, bytes: &[u8]) -> Result<Value, Box<dyn std::error::Error>> {
+    let unit_size = match field_type {
+        1 | 2 | 6 | 7 => 1,
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => return Ok(Value::Unknown(field_type, count, 0)),
+    };
+    if bytes.len() != count as usize * unit_size {
+        return Err("EXIF leaf value doesn't match its count".into());
+    }
+    Ok(match field_type {
+        1 => Value::Byte(bytes.to_vec()),
+        2 => Value::Ascii(match bytes.strip_suffix(&[0]) {
+            Some(strings) => strings.split(|&b| b == 0).map(<[u8]>::to_vec).collect(),
+            None if bytes.is_empty() => Vec::new(),
+            None => return Err("EXIF leaf string isn't NUL-terminated".into()),
+        }),
+        3 => Value::Short(chunks(bytes).map(u16::from_be_bytes).collect()),
+        4 => Value::Long(chunks(bytes).map(u32::from_be_bytes).collect()),
+        5 => Value::Rational(chunks(bytes).map(|v| {
+            let v = u64::from_be_bytes(v);
+            Rational { num: (v >> 32) as u32, denom: v as u32 }
+        }).collect()),
+        6 => Value::SByte(chunks(bytes).map(i8::from_be_bytes).collect()),
+        7 => Value::Undefined(bytes.to_vec(), 0),
+        8 => Value::SShort(chunks(bytes).map(i16::from_be_bytes).collect()),
+        9 => Value::SLong(chunks(bytes).map(i32::from_be_bytes).collect()),
+        10 => Value::SRational(chunks(bytes).map(|v| {
+            let v = u64::from_be_bytes(v);
+            SRational { num: (v >> 32) as i32, denom: v as i32 }
+        }).collect()),
+        11 => Value::Float(chunks(bytes).map(f32::from_be_bytes).collect()),
+        _ => Value::Double(chunks(bytes).map(f64::from_be_bytes).collect()),
+    })
+}
+
+/// An EXIF field's leaf: version, source, then the IFD number (u16), tag
+/// context (u8), tag number (u16), TIFF field type (u16), count (u32) and
+/// the value's bytes, all big-endian. Built from the raw value rather than
+/// its display text, so it doesn't change with the exif crate's formatting.
+pub fn exif_leaf(field: &exif::Field) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let context = CONTEXTS.iter()
+        .position(|context| *context == field.tag.0)
+        .ok_or_else(|| format!("Unsupported EXIF tag context: {:?}", field.tag.0))?;
+    let (field_type, count, value) = encode_value(&field.value);
+
+    let mut leaf = vec![LEAF_VERSION, Source::Exif as u8];
+    leaf.extend(field.ifd_num.0.to_be_bytes());
+    leaf.push(context as u8);
+    leaf.extend(field.tag.1.to_be_bytes());
+    leaf.extend(field_type.to_be_bytes());
+    leaf.extend(count.to_be_bytes());
+    leaf.extend(value);
+    Ok(leaf)
+}
+
+/// A leaf for a named value from XMP, IPTC, movie metadata, MPF or the
+/// image data hash: version, source, then the name's length (u16) and UTF-8 bytes,
+/// and the value's length (u32) and bytes, all big-endian.
+pub fn named_leaf(source: Source, name: &str, value: &[u8]) -> Vec<u8> {
+    let mut leaf = vec![LEAF_VERSION, source as u8];
+    leaf.extend((name.len() as u16).to_be_bytes());
+    leaf.extend(name.as_bytes());
+    leaf.extend((value.len() as u32).to_be_bytes());
+    leaf.extend(value);
+    leaf
+}
+
+/// A leaf for an entry of a maker note, in the maker note's subtree:
+/// version, source, the entry's name length (u16) and UTF-8 bytes, then its
+/// TIFF field type (u16), count (u32) and value bytes made big-endian, as in
+/// an EXIF leaf.
+pub fn makernote_leaf(name: &str, field_type: u16, count: u32, value: &[u8]) -> Vec<u8> {
+    let mut leaf = vec![LEAF_VERSION, Source::MakerNote as u8];
+    leaf.extend((name.len() as u16).to_be_bytes());
+    leaf.extend(name.as_bytes());
+    leaf.extend(field_type.to_be_bytes());
+    leaf.extend(count.to_be_bytes());
+    leaf.extend(value);
+    leaf
+}
+
+/// The main tree's leaf standing for a maker note's subtree, holding its
+/// root: a named leaf called `MakerNote`.
+pub fn makernote_root_leaf(root: &[u8]) -> Vec<u8> {
+    named_leaf(Source::MakerNoteRoot, "MakerNote", root)
+}
+
+/// Splits `len` bytes off the front of `rest`.
+fn take<'a>(rest: &mut &'a [u8], len: usize) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+    if len > rest.len() {
+        return Err("Truncated leaf".into());
+    }
+    let (taken, remaining) = rest.split_at(len);
+    *rest = remaining;
+    Ok(taken)
+}
+
+fn take_u16(rest: &mut &[u8]) -> Result<u16, Box<dyn std::error::Error>> {
+    Ok(u16::from_be_bytes(take(rest, 2)?.try_into()?))
+}
+
+fn take_u32(rest: &mut &[u8]) -> Result<u32, Box<dyn std::error::Error>> {
+    Ok(u32::from_be_bytes(take(rest, 4)?.try_into()?))
+}
+
+/// Reads a leaf back into its name and value.
+pub fn decode(leaf: &[u8]) -> Result<DecodedLeaf, Box<dyn std::error::Error>> {
+    let mut rest = leaf;
+    let header = take(&mut rest, 2)?;
+    if header[0] != LEAF_VERSION {
+        return Err(format!("Unsupported leaf version: {}", header[0]).into());
+    }
+    let source = Source::from_byte(header[1])
+        .ok_or_else(|| format!("Unknown leaf source: {}", header[1]))?;
+
+    if source == Source::Exif {
+        let ifd_num = In(take_u16(&mut rest)?);
+        let context = *CONTEXTS.get(take(&mut rest, 1)?[0] as usize).ok_or("Unknown EXIF tag context")?;
+        let tag = Tag(context, take_u16(&mut rest)?);
+        let field_type = take_u16(&mut rest)?;
+        let count = take_u32(&mut rest)?;
+        let field = exif::Field { tag, ifd_num, value: decode_value(field_type, count, rest)? };
+        return Ok(DecodedLeaf {
+            name: field.tag.to_string(),
+            value: field.display_value().to_string(),
+        });
+    }
+
+    let name_len = take_u16(&mut rest)? as usize;
+    let name = std::str::from_utf8(take(&mut rest, name_len)?)?;
+    if source == Source::MakerNote {
+        let field_type = take_u16(&mut rest)?;
+        let count = take_u32(&mut rest)?;
+        return Ok(DecodedLeaf {
+            name: format!("{}{}", source.name_prefix(), name),
+            value: decode_value(field_type, count, rest)?.display_as(UNNAMED_TAG).to_string(),
+        });
+    }
+    let value_len = take_u32(&mut rest)? as usize;
+    let value = take(&mut rest, value_len)?;
+    if !rest.is_empty() {
+        return Err("Trailing bytes in leaf".into());
+    }
+    Ok(DecodedLeaf {
+        name: format!("{}{}", source.name_prefix(), name),
+        value: match source {
+            Source::Iptc => iptc::display_value(name, value),
+            Source::ImageData | Source::MakerNoteRoot => hex::encode(value),
+            _ => String::from_utf8_lossy(value).into_owned(),
+        },
+    })
+}
+
+/// A fresh random salt for a salted tree.
+pub fn new_salt() -> Vec<u8> {
+    rand::random::<[u8; SALT_LEN]>().to_vec()
+}
+
+/// The salt of the leaf at `index` of a salted tree: HMAC-SHA256 of the
+/// index (u64, big-endian) keyed with the tree's salt. Proofs and
+/// disclosures carry only the salts of the leaves they reveal, so the
+/// hashes of the others can't be brute-forced from them.
+pub fn leaf_salt(tree_salt: &[u8], index: usize) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(tree_salt).expect("HMAC takes keys of any length");
+    mac.update(&(index as u64).to_be_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The salt of a salted tree's maker note subtree, which its entries' own
+/// salts are derived from as the main tree's leaves' are from the tree's:
+/// HMAC-SHA256 of `MakerNote` keyed with the tree's salt.
+pub fn makernote_salt(tree_salt: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(tree_salt).expect("HMAC takes keys of any length");
+    mac.update(b"MakerNote");
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// HMAC-SHA256 of the leaf keyed with its salt from `leaf_salt`, which
+/// stands in for the leaf in a salted tree. Without the salt, low-entropy
+/// values such as the camera model or capture date can't be guessed from
+/// the root.
+pub fn salted_leaf(salt: &[u8], leaf: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(salt).expect("HMAC takes keys of any length");
+    mac.update(leaf);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The leaves as hashed into the tree: each salted with its own salt, for
+/// a tree salted with `salt`, or as they are otherwise.
+pub fn tree_leaves(leaves: &[Vec<u8>], salt: Option<&[u8]>) -> Vec<Vec<u8>> {
+    match salt {
+        Some(salt) => leaves.iter()
+            .enumerate()
+            .map(|(index, leaf)| salted_leaf(&leaf_salt(salt, index), leaf))
+            .collect(),
+        None => leaves.to_vec(),
+    }
+}
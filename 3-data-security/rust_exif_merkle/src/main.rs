@@ -1,10 +1,172 @@
 use std::fs::File;
-use std::io::{Read, BufReader};
-use std::path::Path;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 
+mod batch;
+mod diff;
+mod disclosure;
+mod embed;
+mod iptc;
+mod isobmff;
+mod jpeg;
+mod leaf;
+mod makernote;
 mod merkle;
-use merkle::{MerkleNode, build_merkle_tree};
+mod mpf;
+mod png;
+mod policy;
+mod proof;
+mod quicktime;
+mod tiff;
+mod timestamp;
+mod webp;
+mod xmp;
+use merkle::{SavedTree, build_merkle_tree};
+
+/// TIFF tag 700, which holds an XMP packet in TIFF-based files
+const XMP_TAG: exif::Tag = exif::Tag(exif::Context::Tiff, 700);
+
+/// Name of the leaf holding the SHA-256 of a JPEG's image data
+const IMAGE_DATA_LEAF: &str = "ImageDataSHA256";
+
+#[derive(Parser)]
+#[command(about = "Build and verify Merkle trees over the EXIF metadata of images")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build the Merkle tree over an image's EXIF, XMP and IPTC metadata and save it as <image>_merkle.cbor
+    Hash {
+        /// Image, or a directory to hash every JPEG, PNG, TIFF, DNG, camera raw, HEIC, WebP, MP4 and MOV under; exits with 1 if any fail
+        image: PathBuf,
+
+        /// Directory to save the tree in
+        #[arg(long, short, default_value = ".")]
+        out_dir: PathBuf,
+
+        /// With a directory, also save the root hash or error for each image as JSON here
+        #[arg(long)]
+        summary: Option<PathBuf>,
+
+        /// Derive each leaf as an HMAC under a random salt saved in the tree, so the root reveals nothing; keep the tree private
+        #[arg(long)]
+        salted: bool,
+
+        /// URL of an RFC 3161 timestamp authority to timestamp the root with; the token is saved in the tree
+        #[arg(long)]
+        timestamp: Option<String>,
+    },
+    /// Check an image's EXIF fields still match a saved Merkle tree, listing the fields that don't; exits with 1 if not
+    Verify {
+        image: PathBuf,
+        tree: PathBuf,
+
+        /// Also save the missing, added and changed leaves as JSON here
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+
+        /// PEM of the timestamp authority's CA, to check a timestamped tree's token signature with openssl
+        #[arg(long)]
+        tsa_ca: Option<PathBuf>,
+    },
+    /// Write a copy of a JPEG with its Merkle root, and optionally a signature over it, embedded in an APP11 segment
+    Embed {
+        image: PathBuf,
+
+        /// Path to write the copy to
+        #[arg(long, short)]
+        out: PathBuf,
+
+        /// Saved tree of the image to embed the root of, needed for a salted tree
+        #[arg(long)]
+        tree: Option<PathBuf>,
+
+        /// Hex signature over the root, made with your own key, to embed beside it
+        #[arg(long)]
+        signature: Option<String>,
+    },
+    /// Print inclusion proofs for one EXIF tag, to check it against the root without the image
+    Prove {
+        image: PathBuf,
+
+        /// Tag to prove, e.g. DateTime or Model
+        #[arg(long)]
+        tag: String,
+
+        /// Saved tree of the image, needed for a salted tree; the proven leaf's salt, derived from its salt, goes into the proof
+        #[arg(long)]
+        tree: Option<PathBuf>,
+
+        /// Save the proofs as JSON here instead of printing them
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// Check inclusion proofs saved by prove against a published root, without the image; exits with 1 if any fail
+    VerifyProof {
+        proof: PathBuf,
+
+        /// Hex root hash published for the image
+        #[arg(long)]
+        root: String,
+
+        /// Value the proven tag should have, e.g. "Canon"; without it, the proof's value is printed for the reader to check
+        #[arg(long)]
+        value: Option<String>,
+    },
+    /// Publish chosen EXIF tags in plaintext and every other leaf as a hash only
+    Disclose {
+        image: PathBuf,
+
+        /// Tag to disclose, e.g. DateTime; repeat for more
+        #[arg(long = "tag", required = true)]
+        tags: Vec<String>,
+
+        /// Saved tree of the image, needed for a salted tree; the disclosed leaves' salts, derived from its salt, go into the disclosure
+        #[arg(long)]
+        tree: Option<PathBuf>,
+
+        /// Save the disclosure as JSON here instead of printing it
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// Check a disclosure rebuilds to a published root; exits with 1 if not
+    VerifyDisclosure {
+        disclosure: PathBuf,
+
+        /// Hex root hash published for the image
+        #[arg(long)]
+        root: String,
+    },
+    /// Check an image's metadata against a JSON policy of required tags, allowed cameras and date windows; exits with 1 if it fails
+    CheckPolicy {
+        image: PathBuf,
+
+        /// Policy file, e.g. policy.example.json
+        #[arg(long)]
+        policy: PathBuf,
+
+        /// Also save the report as JSON here
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// Print an image's EXIF fields as JSON
+    Inspect {
+        image: PathBuf,
+
+        /// Save the fields as <image>_exif.json in this directory instead of printing them
+        #[arg(long, short)]
+        out_dir: Option<PathBuf>,
+
+        /// Print the printable characters of the raw EXIF segment instead
+        #[arg(long, conflicts_with = "out_dir")]
+        raw: bool,
+    },
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct ExifField {
@@ -21,55 +183,81 @@ struct ExifData {
     total_fields: usize,
 }
 
-/// Returns the raw EXIF blob from a JPEG file (excluding JPEG markers).
-pub fn extract_exif_blob(path: &str) -> Option<Vec<u8>> {
-    let mut reader = BufReader::new(File::open(path).ok()?);
-    let mut buf = [0u8; 2];
-
-    // Read SOI (Start of Image)
-    reader.read_exact(&mut buf).ok()?;
-    if buf != [0xFF, 0xD8] {
-        return None; // Not a JPEG
+/// Returns the raw EXIF blob from a JPEG file (excluding JPEG markers and
+/// joined across segments), from
+/// a PNG's eXIf chunk, a WebP's EXIF chunk or a HEIF's Exif item, or the
+/// whole of a TIFF-based file such as a DNG or camera raw, whose IFDs are
+/// the EXIF structure.
+pub fn extract_exif_blob(path: &Path) -> Option<Vec<u8>> {
+    if png::is_png(path).ok()? {
+        return png::exif_chunk(path).ok()?;
+    }
+    if webp::is_webp(path).ok()? {
+        return webp::exif_chunk(path).ok()?;
+    }
+    if isobmff::is_heif(path).ok()? {
+        return isobmff::exif_item(path).ok()?;
+    }
+    if tiff::is_tiff(path).ok()? {
+        return std::fs::read(path).ok();
     }
 
-    loop {
-        reader.read_exact(&mut buf).ok()?;
-        if buf[0] != 0xFF {
-            return None; // Invalid marker
-        }
-
-        // Read marker
-        let marker = buf[1];
-        reader.read_exact(&mut buf).ok()?; // Read segment length
-        let len = u16::from_be_bytes(buf) as usize;
+    jpeg::exif_blob(path).ok()?
+}
 
-        if marker == 0xE1 {
-            // APP1 (EXIF) segment
-            let mut exif_buf = vec![0u8; len - 2]; // already read 2 bytes for length
-            reader.read_exact(&mut exif_buf).ok()?;
-            if &exif_buf[..6] == b"Exif\0\0" {
-                return Some(exif_buf);
-            }
-        } else {
-            // Skip other segments
-            reader.seek_relative((len - 2) as i64).ok()?;
-        }
+/// The leaves of the image's maker note subtree, sorted, or None if it has
+/// no maker note in a format `makernote` knows. A maker note that doesn't
+/// parse is left as a single EXIF leaf, so is None too.
+pub fn makernote_leaves(path: &Path) -> Option<Vec<Vec<u8>>> {
+    let blob = extract_exif_blob(path)?;
+    let mut leaves = makernote::makernote_leaves(&blob).ok()??;
+    if leaves.is_empty() {
+        return None;
     }
+    leaves.sort();
+    Some(leaves)
 }
 
-pub fn print_exif_tags_json(path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Processing file: {} \n", path);
-    let file = std::fs::File::open(path)?;
-    let mut bufreader = std::io::BufReader::new(&file);
-    let exifreader = exif::Reader::new();
-    let exif = exifreader.read_from_container(&mut bufreader)?;
-    println!("EXIF Data: \n{{");
-    for f in exif.fields() {
-        println!("    \"{}\" : \"{}\",",
-                 f.tag, f.display_value().with_unit(&exif));
+/// The image's EXIF, or None if it has none.
+fn read_exif(path: &Path) -> Result<Option<exif::Exif>, Box<dyn std::error::Error>> {
+    let exif_data = if isobmff::is_heif(path)? {
+        isobmff::exif_item(path)?
+    } else if webp::is_webp(path)? {
+        webp::exif_chunk(path)?
+    } else if jpeg::is_jpeg(path)? {
+        jpeg::exif_blob(path)?
+    } else {
+        let file = File::open(path)?;
+        let mut bufreader = BufReader::new(&file);
+        let exifreader = exif::Reader::new();
+        return match exifreader.read_from_container(&mut bufreader) {
+            Ok(exif) => Ok(Some(exif)),
+            Err(exif::Error::NotFound(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        };
+    };
+    match exif_data {
+        Some(exif_data) => Ok(Some(exif::Reader::new().read_raw(exif_data)?)),
+        None => Ok(None),
     }
-    println!("}}");
-    Ok(())
+}
+
+fn read_exif_data(path: &Path) -> Result<ExifData, Box<dyn std::error::Error>> {
+    let exif = read_exif(path)?
+        .ok_or_else(|| format!("No EXIF metadata in {}", path.display()))?;
+    let fields: Vec<ExifField> = exif.fields()
+        .map(|f| ExifField {
+            tag: f.tag.to_string(),
+            ifd_num: f.ifd_num.to_string(),
+            value: f.display_value().with_unit(&exif).to_string(),
+            description: f.tag.description().unwrap_or_default().to_string(),
+        })
+        .collect();
+    Ok(ExifData {
+        file_path: path.display().to_string(),
+        total_fields: fields.len(),
+        fields,
+    })
 }
 
 fn print_ascii(blob: &[u8]) {
@@ -81,90 +269,328 @@ fn print_ascii(blob: &[u8]) {
     println!();
 }
 
-pub fn build_exif_merkle_tree(path: &str) -> Result<MerkleNode, Box<dyn std::error::Error>> {
-    let file = std::fs::File::open(path)?;
-    let mut bufreader = std::io::BufReader::new(&file);
-    let exifreader = exif::Reader::new();
-    let exif = exifreader.read_from_container(&mut bufreader)?;
-    
-    // Collect and sort EXIF fields
-    let mut leaves: Vec<Vec<u8>> = exif.fields()
-        .map(|f| {
-            let value = format!("{}:{}", f.tag, f.display_value());
-            value.as_bytes().to_vec()
-        })
-        .collect();
-    
+/// The image's XMP packet, from wherever its format keeps it: an APP1
+/// segment in a JPEG, an iTXt chunk in a PNG, the XMP chunk in a WebP, or
+/// the XMP tag in the IFDs of a TIFF-based file.
+fn read_xmp_packet(path: &Path, exif: Option<&exif::Exif>) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if jpeg::is_jpeg(path)? {
+        return jpeg::xmp_packet(path);
+    }
+    if png::is_png(path)? {
+        return png::xmp_packet(path);
+    }
+    if webp::is_webp(path)? {
+        return webp::xmp_packet(path);
+    }
+    let field = exif.and_then(|exif| exif.get_field(XMP_TAG, exif::In::PRIMARY));
+    match field.map(|field| &field.value) {
+        Some(exif::Value::Byte(packet) | exif::Value::Undefined(packet, _)) => {
+            Ok(Some(String::from_utf8(packet.clone())?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The tree's leaves, sorted, in the canonical encoding of `leaf`: one per
+/// EXIF field, one per XMP value, and one per IPTC field in a JPEG, so
+/// creator, rights, captions and edit history are committed to along with
+/// the capture settings. A JPEG also gets leaves for its MPF index and the
+/// extra images it lists, and an `ImageDataSHA256` leaf, the hash of its
+/// image data, binding the metadata to the pixels it describes. MP4 and
+/// QuickTime movies get one leaf per value in their movie metadata instead.
+/// A maker note in a known format becomes a subtree of its own entries,
+/// with a `MakerNote` leaf holding the subtree's root in place of the
+/// maker note's EXIF leaf. For a salted tree, pass its salt: the subtree's
+/// entries are salted too, under `leaf::makernote_salt`.
+pub fn exif_leaves(path: &Path, salt: Option<&[u8]>) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    if quicktime::is_video(path)? {
+        let mut leaves = quicktime::video_leaves(path)?;
+        leaves.sort();
+        return Ok(leaves);
+    }
+
+    let exif = read_exif(path)?;
+    let makernote = exif.as_ref().and_then(|_| makernote_leaves(path));
+    let mut leaves = exif.iter()
+        .flat_map(|exif| exif.fields())
+        // Its packet is committed to value by value below, and a parsed
+        // maker note entry by entry in its subtree
+        .filter(|f| f.tag != XMP_TAG && !(makernote.is_some() && f.tag == exif::Tag::MakerNote))
+        .map(leaf::exif_leaf)
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(makernote) = makernote {
+        let subtree_salt = salt.map(leaf::makernote_salt);
+        let subtree = build_merkle_tree(leaf::tree_leaves(&makernote, subtree_salt.as_deref()))
+            .ok_or("Failed to build maker note tree")?;
+        leaves.push(leaf::makernote_root_leaf(&subtree.hash));
+    }
+    let is_jpeg = jpeg::is_jpeg(path)?;
+    if let Some(packet) = read_xmp_packet(path, exif.as_ref())? {
+        leaves.extend(xmp::xmp_leaves(&packet)?);
+        if is_jpeg {
+            if let Some(extended) = jpeg::extended_xmp_packet(path, &packet)? {
+                leaves.extend(xmp::xmp_leaves(&extended)?);
+            }
+        }
+    }
+    if is_jpeg {
+        if let Some(record) = jpeg::iptc_record(path)? {
+            leaves.extend(iptc::iptc_leaves(&record)?);
+        }
+        leaves.extend(mpf::mpf_leaves(path)?);
+    }
+    if leaves.is_empty() {
+        return Err(format!("No EXIF, XMP or IPTC metadata in {}", path.display()).into());
+    }
+    if is_jpeg {
+        let image_data_hash = jpeg::image_data_hash(path)?;
+        leaves.push(leaf::named_leaf(leaf::Source::ImageData, IMAGE_DATA_LEAF, &image_data_hash));
+    }
+
     // Sort leaves by their content to ensure deterministic ordering
     leaves.sort();
+    Ok(leaves)
+}
 
-    build_merkle_tree(leaves)
-        .ok_or_else(|| "Failed to build Merkle tree".into())
+/// Names a sidecar for the image, e.g. `<image stem>_merkle.cbor`, in `out_dir`.
+fn sidecar_path(image_path: &Path, out_dir: &Path, suffix: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let image_stem = image_path.file_stem()
+        .ok_or_else(|| format!("Not a file path: {}", image_path.display()))?;
+    let mut name = image_stem.to_os_string();
+    name.push(suffix);
+    Ok(out_dir.join(name))
 }
 
-fn save_merkle_tree(path: &str, merkle_root: &MerkleNode) -> Result<(), Box<dyn std::error::Error>> {
-    // Create a merkle tree filename based on the image filename
-    let image_path = Path::new(path);
-    let image_stem = image_path.file_stem().unwrap().to_str().unwrap();
-    let merkle_path = format!("{}_merkle.json", image_stem);
-    
-    merkle_root.save_to_file(&merkle_path)?;
-    println!("Merkle tree saved to: {}", merkle_path);
-    Ok(())
+fn save_merkle_tree(path: &Path, out_dir: &Path, tree: &SavedTree) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let merkle_path = sidecar_path(path, out_dir, "_merkle.cbor")?;
+    std::fs::create_dir_all(out_dir)?;
+    tree.save_to_file(&merkle_path)?;
+    Ok(merkle_path)
 }
 
-fn verify_image_merkle_tree(image_path: &str, merkle_path: &str) -> Result<bool, Box<dyn std::error::Error>> {
-    // Load the stored Merkle tree
-    let stored_tree = MerkleNode::load_from_file(merkle_path)?;
-    
-    // Get current EXIF data
-    let file = std::fs::File::open(image_path)?;
-    let mut bufreader = std::io::BufReader::new(&file);
-    let exifreader = exif::Reader::new();
-    let exif = exifreader.read_from_container(&mut bufreader)?;
-    
-    // Convert current EXIF fields to bytes and sort them
-    let mut current_leaves: Vec<Vec<u8>> = exif.fields()
-        .map(|f| {
-            let value = format!("{}:{}", f.tag, f.display_value());
-            value.as_bytes().to_vec()
-        })
-        .collect();
-    
-    // Sort leaves by their content to ensure deterministic ordering
-    current_leaves.sort();
+/// Builds and saves the image's tree, salted with a fresh salt if `salted`,
+/// and with a timestamp token over its root from `tsa_url` if given.
+pub fn hash_image(path: &Path, out_dir: &Path, salted: bool, tsa_url: Option<&str>) -> Result<(SavedTree, PathBuf), Box<dyn std::error::Error>> {
+    let salt = salted.then(leaf::new_salt);
+    let leaves = exif_leaves(path, salt.as_deref())?;
+    let root = build_merkle_tree(leaf::tree_leaves(&leaves, salt.as_deref()))
+        .ok_or("Failed to build Merkle tree")?;
+    let timestamp = match tsa_url {
+        Some(tsa_url) => Some(hex::encode(timestamp::request_token(tsa_url, &root.hash)?)),
+        None => None,
+    };
+    let tree = SavedTree {
+        root,
+        salt: salt.map(hex::encode),
+        leaf_names: Some(leaves.iter()
+            .map(|leaf| Ok(leaf::decode(leaf)?.name))
+            .collect::<Result<_, Box<dyn std::error::Error>>>()?),
+        timestamp,
+    };
+    let merkle_path = save_merkle_tree(path, out_dir, &tree)?;
+    Ok((tree, merkle_path))
+}
+
+/// Checks the tree's timestamp token, if it has one, is over its root and,
+/// given the authority's CA, is signed by the authority. Prints the time it
+/// attests; returns whether it is valid.
+fn check_timestamp(tree: &SavedTree, tsa_ca: Option<&Path>) -> Result<bool, Box<dyn std::error::Error>> {
+    let Some(token) = tree.timestamp.as_deref().map(hex::decode).transpose()? else {
+        return Ok(true);
+    };
+    let info = match timestamp::check_token(&token, &tree.root.hash) {
+        Ok(info) => info,
+        Err(e) => {
+            println!("Timestamp: INVALID, {}", e);
+            return Ok(false);
+        }
+    };
+    println!("Timestamp: root existed at {}", info.time);
+    match tsa_ca {
+        Some(tsa_ca) => {
+            let is_valid = timestamp::verify_signature(&token, &tree.root.hash, tsa_ca)?;
+            println!("Timestamp signature: {}", if is_valid { "VALID" } else { "INVALID" });
+            Ok(is_valid)
+        }
+        None => {
+            println!("Timestamp signature not checked; pass --tsa-ca to check it");
+            Ok(true)
+        }
+    }
+}
 
-    // Verify the stored tree against current data
-    Ok(stored_tree.verify(&current_leaves))
+/// The salt of the image's saved tree, if one is given and salted, after
+/// checking the image still matches the tree.
+fn saved_tree_salt(image_path: &Path, tree_path: Option<&Path>) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let Some(tree_path) = tree_path else {
+        return Ok(None);
+    };
+    let stored_tree = SavedTree::load_from_file(tree_path)?;
+    let salt = stored_tree.salt_bytes()?;
+    if !stored_tree.root.verify(&leaf::tree_leaves(&exif_leaves(image_path, salt.as_deref())?, salt.as_deref())) {
+        return Err(format!("{} no longer matches {}", image_path.display(), tree_path.display()).into());
+    }
+    Ok(salt)
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    const PHOTOS: [&str; 1] = ["../../guttenfelder_photos/06_2015_RF_Guttenfelder_00004.JPG"];
-    println!("--------------------------------");
-    println!("EXIF Reader and Merkle Tree");
-    println!("--------------------------------");
-
-    for path in PHOTOS {
-        println!("Processing file: {}", path);
-        
-        // Extract and print EXIF data as JSON
-        print_exif_tags_json(path)?;
-        
-        // Build and print Merkle tree
-        let merkle_root = build_exif_merkle_tree(path)?;
-        println!("\nMerkle Root Hash: {}", hex::encode(&merkle_root.hash));
-        
-        // Save the Merkle tree
-        save_merkle_tree(path, &merkle_root)?;
-        
-        // Demonstrate verification
-        let image_path = Path::new(path);
-        let image_stem = image_path.file_stem().unwrap().to_str().unwrap();
-        let merkle_path = format!("{}_merkle.json", image_stem);
-        
-        let is_valid = verify_image_merkle_tree(path, &merkle_path)?;
-        println!("\nMerkle tree verification: {}", if is_valid { "VALID" } else { "INVALID" });
-    }
-    
+    match Cli::parse().command {
+        Command::Hash { image, out_dir, summary, salted, timestamp } if image.is_dir() => {
+            let failed = batch::hash_directory(&image, &out_dir, summary.as_deref(), salted, timestamp.as_deref())?;
+            if failed > 0 {
+                std::process::exit(1);
+            }
+        }
+        Command::Hash { image, out_dir, salted, timestamp, .. } => {
+            let (tree, merkle_path) = hash_image(&image, &out_dir, salted, timestamp.as_deref())?;
+            println!("Merkle Root Hash: {}", hex::encode(&tree.root.hash));
+            if let Some(token) = &tree.timestamp {
+                println!("Timestamped at: {}", timestamp::check_token(&hex::decode(token)?, &tree.root.hash)?.time);
+            }
+            println!("Merkle tree saved to: {}", merkle_path.display());
+            if salted {
+                println!("Leaves are salted; keep the tree private, its salt reveals the fields");
+            }
+        }
+        Command::Verify { image, tree, out, tsa_ca } => {
+            let stored_tree = SavedTree::load_from_file(&tree)?;
+            let report = diff::diff_image(&image, &stored_tree)?;
+            for entry in &report.missing {
+                println!("Missing: {} {}", entry.name.as_deref().unwrap_or("(unnamed)"), entry.hash);
+            }
+            for entry in &report.added {
+                println!("Added: {}:{}", entry.name.as_deref().unwrap_or_default(), entry.value.as_deref().unwrap_or_default());
+            }
+            for entry in &report.changed {
+                println!("Changed: {} now {}", entry.name, entry.value);
+            }
+            if let (Some(embedded), Some(matches)) = (&report.embedded_root, report.embedded_root_matches) {
+                println!("Embedded root: {} ({})", embedded, if matches { "matches the tree" } else { "differs from the tree" });
+            }
+            if let Some(out) = out {
+                std::fs::write(&out, serde_json::to_string_pretty(&report)?)?;
+                println!("Report saved to: {}", out.display());
+            }
+            let is_valid = check_timestamp(&stored_tree, tsa_ca.as_deref())?
+                && report.valid
+                && report.embedded_root_matches != Some(false);
+            println!("Merkle tree verification: {}", if is_valid { "VALID" } else { "INVALID" });
+            if !is_valid {
+                std::process::exit(1);
+            }
+        }
+        Command::Embed { image, out, tree, signature } => {
+            if let Some(signature) = &signature {
+                hex::decode(signature)?;
+            }
+            let salt = saved_tree_salt(&image, tree.as_deref())?;
+            let root = build_merkle_tree(leaf::tree_leaves(&exif_leaves(&image, salt.as_deref())?, salt.as_deref()))
+                .ok_or("Failed to build Merkle tree")?;
+            let embedded = embed::EmbeddedRoot { root: hex::encode(&root.hash), signature };
+            embed::embed_root(&image, &out, &embedded)?;
+            // The MPF images are found through offsets that inserting the
+            // root segment could shift, so check they are still in place
+            if mpf::mpf_leaves(&out)? != mpf::mpf_leaves(&image)? {
+                std::fs::remove_file(&out)?;
+                return Err(format!("Embedding the root moved the MPF images of {}; nothing was written", image.display()).into());
+            }
+            // Embedding must leave every leaf as it was, or the copy won't
+            // match the root it carries
+            let out_root = build_merkle_tree(leaf::tree_leaves(&exif_leaves(&out, salt.as_deref())?, salt.as_deref()))
+                .ok_or("Failed to build Merkle tree")?;
+            if out_root.hash != root.hash {
+                std::fs::remove_file(&out)?;
+                return Err(format!("Embedding the root changed the tree of {}; nothing was written", image.display()).into());
+            }
+            println!("Merkle Root Hash: {}", embedded.root);
+            println!("Image with embedded root saved to: {}", out.display());
+        }
+        Command::Prove { image, tag, tree, out } => {
+            let salt = saved_tree_salt(&image, tree.as_deref())?;
+            let json = serde_json::to_string_pretty(&proof::prove_tag(&image, &tag, salt.as_deref())?)?;
+            match out {
+                Some(out) => {
+                    std::fs::write(&out, json)?;
+                    println!("Proof saved to: {}", out.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+        Command::VerifyProof { proof, root, value } => {
+            let proofs: Vec<proof::InclusionProof> = serde_json::from_str(&std::fs::read_to_string(&proof)?)?;
+            if proofs.is_empty() {
+                return Err(format!("No proofs in {}", proof.display()).into());
+            }
+            let mut all_valid = true;
+            for proof in &proofs {
+                let decoded = proof.decoded_leaf()?;
+                let value_matches = value.as_deref().is_none_or(|value| {
+                    decoded.value == value || decoded.unquoted_value() == value
+                });
+                let is_valid = proof.verify(&root)? && value_matches;
+                println!("{} (leaf {} of {}): {}", decoded, proof.leaf_index + 1, proof.leaf_count, if is_valid { "VALID" } else { "INVALID" });
+                all_valid &= is_valid;
+            }
+            println!("Proof verification: {}", if all_valid { "VALID" } else { "INVALID" });
+            if !all_valid {
+                std::process::exit(1);
+            }
+        }
+        Command::Disclose { image, tags, tree, out } => {
+            let salt = saved_tree_salt(&image, tree.as_deref())?;
+            let json = serde_json::to_string_pretty(&disclosure::disclose_tags(&image, &tags, salt.as_deref())?)?;
+            match out {
+                Some(out) => {
+                    std::fs::write(&out, json)?;
+                    println!("Disclosure saved to: {}", out.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+        Command::VerifyDisclosure { disclosure, root } => {
+            let disclosure: disclosure::Disclosure = serde_json::from_str(&std::fs::read_to_string(&disclosure)?)?;
+            let is_valid = disclosure.verify(&root)?;
+            println!("Disclosed fields:");
+            for value in disclosure.values()? {
+                println!("    {}", value);
+            }
+            println!("Root verification: {}", if is_valid { "VALID" } else { "INVALID" });
+            if !is_valid {
+                std::process::exit(1);
+            }
+        }
+        Command::CheckPolicy { image, policy, out } => {
+            let report = policy::check_policy(&image, &policy::Policy::load_from_file(&policy)?)?;
+            for check in &report.checks {
+                println!("{} {}: {}", if check.passed { "PASS" } else { "FAIL" }, check.rule, check.reason);
+            }
+            if let Some(out) = out {
+                std::fs::write(&out, serde_json::to_string_pretty(&report)?)?;
+                println!("Report saved to: {}", out.display());
+            }
+            println!("Policy check: {}", if report.passed { "PASS" } else { "FAIL" });
+            if !report.passed {
+                std::process::exit(1);
+            }
+        }
+        Command::Inspect { image, raw: true, .. } => {
+            let blob = extract_exif_blob(&image)
+                .ok_or_else(|| format!("No EXIF segment found in {}", image.display()))?;
+            print_ascii(&blob);
+        }
+        Command::Inspect { image, out_dir, raw: false } => {
+            let json = serde_json::to_string_pretty(&read_exif_data(&image)?)?;
+            match out_dir {
+                Some(out_dir) => {
+                    let exif_path = sidecar_path(&image, &out_dir, "_exif.json")?;
+                    std::fs::create_dir_all(&out_dir)?;
+                    std::fs::write(&exif_path, json)?;
+                    println!("EXIF data saved to: {}", exif_path.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+    }
+
     Ok(())
 }
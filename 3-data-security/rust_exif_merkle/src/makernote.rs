@@ -0,0 +1,134 @@
+use crate::leaf;
+use crate::tiff::{Endian, read_ifd};
+
+const MAKE_TAG: u16 = 0x010F;
+const EXIF_IFD_POINTER_TAG: u16 = 0x8769;
+const MAKER_NOTE_TAG: u16 = 0x927C;
+
+/// Names of the Canon maker note entries most useful for provenance:
+/// serial numbers, counters and firmware
+const CANON_NAMES: [(u16, &str); 13] = [
+    (0x0001, "CameraSettings"),
+    (0x0004, "ShotInfo"),
+    (0x0006, "ImageType"),
+    (0x0007, "FirmwareVersion"),
+    (0x0008, "FileNumber"),
+    (0x0009, "OwnerName"),
+    (0x000C, "SerialNumber"),
+    (0x000D, "CameraInfo"),
+    (0x0010, "ModelID"),
+    (0x0095, "LensModel"),
+    (0x0096, "InternalSerialNumber"),
+    (0x0097, "DustRemovalData"),
+    (0x4001, "ColorData"),
+];
+
+const NIKON_NAMES: [(u16, &str); 8] = [
+    (0x0001, "MakerNoteVersion"),
+    (0x0002, "ISO"),
+    (0x0004, "Quality"),
+    (0x0005, "WhiteBalance"),
+    (0x001D, "SerialNumber"),
+    (0x0084, "Lens"),
+    (0x00A7, "ShutterCount"),
+    (0x00AB, "VariProgram"),
+];
+
+const SONY_NAMES: [(u16, &str); 3] = [
+    (0x0102, "Quality"),
+    (0xB020, "CreativeStyle"),
+    (0xB027, "LensType"),
+];
+
+const FUJIFILM_NAMES: [(u16, &str); 4] = [
+    (0x0000, "Version"),
+    (0x0010, "InternalSerialNumber"),
+    (0x1000, "Quality"),
+    (0x1001, "Sharpness"),
+];
+
+const APPLE_NAMES: [(u16, &str); 5] = [
+    (0x0001, "MakerNoteVersion"),
+    (0x0008, "AccelerationVector"),
+    (0x000A, "HDRImageType"),
+    (0x000B, "BurstUUID"),
+    (0x0011, "ContentIdentifier"),
+];
+
+/// Where a maker note's IFD is, what its value offsets count from, and the
+/// names of its entries
+struct Layout<'a> {
+    data: &'a [u8],
+    ifd_at: usize,
+    endian: Endian,
+    names: &'static [(u16, &'static str)],
+}
+
+/// The layout of a maker note at `note_at` in `tiff`, going by its header,
+/// or for Canon's, which has none, by the camera make.
+fn layout<'a>(make: &str, tiff: &'a [u8], note_at: usize, note: &'a [u8], endian: Endian) -> Option<Layout<'a>> {
+    if note.starts_with(b"Nikon\0\x02") {
+        // Its own TIFF header, which its offsets count from
+        let inner = note.get(10..)?;
+        let endian = Endian::of_header(inner)?;
+        let ifd_at = endian.u32(inner.get(4..8)?) as usize;
+        return Some(Layout { data: inner, ifd_at, endian, names: &NIKON_NAMES });
+    }
+    if note.starts_with(b"SONY DSC \0\0\0") || note.starts_with(b"SONY CAM \0\0\0") {
+        return Some(Layout { data: tiff, ifd_at: note_at + 12, endian, names: &SONY_NAMES });
+    }
+    if note.starts_with(b"FUJIFILM") {
+        // Always little-endian, with offsets from the maker note's start
+        let endian = Endian { little: true };
+        let ifd_at = endian.u32(note.get(8..12)?) as usize;
+        return Some(Layout { data: note, ifd_at, endian, names: &FUJIFILM_NAMES });
+    }
+    if note.starts_with(b"Apple iOS\0") {
+        return Some(Layout { data: note, ifd_at: 14, endian: Endian { little: false }, names: &APPLE_NAMES });
+    }
+    if make.starts_with("Canon") {
+        return Some(Layout { data: tiff, ifd_at: note_at, endian, names: &CANON_NAMES });
+    }
+    None
+}
+
+/// One leaf per entry of the maker note in `tiff`, EXIF's TIFF data, if
+/// it is in a format this knows: Canon, Nikon, Sony, Fujifilm or Apple.
+/// Entries are named as in ExifTool where known, e.g.
+/// `MakerNote:SerialNumber`, and by number otherwise, e.g.
+/// `MakerNote:0x4013`.
+pub fn makernote_leaves(tiff: &[u8]) -> Result<Option<Vec<Vec<u8>>>, Box<dyn std::error::Error>> {
+    let endian = Endian::of_header(tiff).ok_or("Not TIFF data")?;
+    let ifd0_at = endian.u32(tiff.get(4..8).ok_or("Truncated TIFF header")?) as usize;
+    let ifd0 = read_ifd(tiff, ifd0_at, endian)?;
+    let make = match ifd0.iter().find(|entry| entry.tag == MAKE_TAG) {
+        Some(entry) => String::from_utf8_lossy(entry.value_bytes(tiff, endian)?.unwrap_or_default()).into_owned(),
+        None => String::new(),
+    };
+    let Some(exif_ifd) = ifd0.iter().find(|entry| entry.tag == EXIF_IFD_POINTER_TAG) else {
+        return Ok(None);
+    };
+    let exif_ifd = read_ifd(tiff, endian.u32(&exif_ifd.value) as usize, endian)?;
+    let Some(note_entry) = exif_ifd.iter().find(|entry| entry.tag == MAKER_NOTE_TAG) else {
+        return Ok(None);
+    };
+    let note = note_entry.value_bytes(tiff, endian)?.ok_or("Invalid MakerNote type")?;
+    let note_at = endian.u32(&note_entry.value) as usize;
+    let Some(layout) = layout(&make, tiff, note_at, note, endian) else {
+        return Ok(None);
+    };
+
+    let mut leaves = Vec::new();
+    for entry in read_ifd(layout.data, layout.ifd_at, layout.endian)? {
+        let name = layout.names.iter()
+            .find(|(tag, _)| *tag == entry.tag)
+            .map(|(_, name)| name.to_string())
+            .unwrap_or_else(|| format!("{:#06X}", entry.tag));
+        // Entries of types this doesn't know can't be read, so are left out
+        let Some(value) = entry.big_endian_value(layout.data, layout.endian)? else {
+            continue;
+        };
+        leaves.push(leaf::makernote_leaf(&name, entry.field_type, entry.count, &value));
+    }
+    Ok(Some(leaves))
+}
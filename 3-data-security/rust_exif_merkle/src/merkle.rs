@@ -1,8 +1,41 @@
 use sha2::{Sha256, Digest};
 use serde::{Serialize, Deserialize};
+use serde_bytes::ByteBuf;
 use std::fs;
 use std::path::Path;
 
+/// Version of the compact sidecar format, see `CompactTree`
+const COMPACT_FORMAT: u8 = 1;
+
+/// Prefixes hashed before a leaf and before a pair of child hashes, so a
+/// leaf can never pass for an internal node, or an internal node for a leaf
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Which side of the running hash a proof's sibling is joined on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// One level of an inclusion proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofStep {
+    /// Hex SHA-256 of the sibling node
+    pub sibling: String,
+    pub side: Side,
+}
+
+/// A leaf in a selective disclosure: its data, for the verifier to hash and
+/// read, or only its hash, keeping the data private
+#[derive(Debug, Clone)]
+pub enum DisclosedLeaf {
+    Data(Vec<u8>),
+    Hash(Vec<u8>),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MerkleNode {
     pub hash: Vec<u8>,
@@ -10,12 +43,101 @@ pub struct MerkleNode {
     pub right: Option<Box<MerkleNode>>,
 }
 
+/// A tree as saved in a sidecar, with the salt its leaves were derived
+/// with if it is salted. Anyone with a salted tree's sidecar can brute-force
+/// its fields, so it must be kept private. Sidecars are saved compact, as a
+/// `CompactTree`; older sidecars hold the whole tree as JSON, in this shape.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTree {
+    #[serde(flatten)]
+    pub root: MerkleNode,
+    /// Hex HMAC key of the leaves, see `leaf::salted_leaf`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    /// Names of the leaves, in order, so `verify` can name the fields that
+    /// changed; absent from trees saved before they were kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaf_names: Option<Vec<String>>,
+    /// Hex DER of an RFC 3161 timestamp token over the root, see `timestamp`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+}
+
+/// A sidecar in the compact format: CBOR holding the root and the ordered
+/// leaf hashes, from which the rest of the tree is rebuilt on load
+#[derive(Debug, Serialize, Deserialize)]
+struct CompactTree {
+    format: u8,
+    root: ByteBuf,
+    leaves: Vec<ByteBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<ByteBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    leaf_names: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<ByteBuf>,
+}
+
+impl SavedTree {
+    pub fn save_to_file(&self, filepath: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let compact = CompactTree {
+            format: COMPACT_FORMAT,
+            root: ByteBuf::from(self.root.hash.clone()),
+            leaves: self.leaf_hashes().into_iter().map(ByteBuf::from).collect(),
+            salt: self.salt_bytes()?.map(ByteBuf::from),
+            leaf_names: self.leaf_names.clone(),
+            timestamp: self.timestamp.as_deref().map(hex::decode).transpose()?.map(ByteBuf::from),
+        };
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&compact, &mut bytes)?;
+        fs::write(filepath, bytes)?;
+        Ok(())
+    }
+
+    /// Loads a compact sidecar, or an older one holding the whole tree as
+    /// JSON.
+    pub fn load_from_file(filepath: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = fs::read(filepath)?;
+        if bytes.first() == Some(&b'{') {
+            let tree: SavedTree = serde_json::from_slice(&bytes)?;
+            return Ok(tree);
+        }
+
+        let compact: CompactTree = ciborium::from_reader(bytes.as_slice())?;
+        if compact.format != COMPACT_FORMAT {
+            return Err(format!("Unsupported sidecar format: {}", compact.format).into());
+        }
+        let root = build_merkle_tree_from_hashes(compact.leaves.into_iter().map(ByteBuf::into_vec).collect())
+            .filter(|root| root.hash == compact.root.as_slice())
+            .ok_or_else(|| format!("Leaves in {} don't rebuild its root", filepath.display()))?;
+        Ok(SavedTree {
+            root,
+            salt: compact.salt.map(hex::encode),
+            leaf_names: compact.leaf_names,
+            timestamp: compact.timestamp.map(hex::encode),
+        })
+    }
+
+    /// The hashes of the tree's leaves, in order.
+    pub fn leaf_hashes(&self) -> Vec<Vec<u8>> {
+        self.root.leaf_hashes()
+    }
+
+    pub fn salt_bytes(&self) -> Result<Option<Vec<u8>>, hex::FromHexError> {
+        self.salt.as_deref().map(hex::decode).transpose()
+    }
+}
+
 impl MerkleNode {
     pub fn new(data: &[u8]) -> Self {
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        let hash = hasher.finalize().to_vec();
-        
+        MerkleNode {
+            hash: hash_leaf(data),
+            left: None,
+            right: None,
+        }
+    }
+
+    pub fn from_hash(hash: Vec<u8>) -> Self {
         MerkleNode {
             hash,
             left: None,
@@ -24,10 +146,7 @@ impl MerkleNode {
     }
 
     pub fn from_children(left: MerkleNode, right: MerkleNode) -> Self {
-        let mut hasher = Sha256::new();
-        hasher.update(&left.hash);
-        hasher.update(&right.hash);
-        let hash = hasher.finalize().to_vec();
+        let hash = hash_children(&left.hash, &right.hash);
 
         MerkleNode {
             hash,
@@ -36,18 +155,6 @@ impl MerkleNode {
         }
     }
 
-    pub fn save_to_file(&self, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(filepath, json)?;
-        Ok(())
-    }
-
-    pub fn load_from_file(filepath: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let json = fs::read_to_string(filepath)?;
-        let node: MerkleNode = serde_json::from_str(&json)?;
-        Ok(node)
-    }
-
     pub fn verify(&self, data: &[Vec<u8>]) -> bool {
         // Rebuild a new tree from the data
         if let Some(new_tree) = build_merkle_tree(data.to_vec()) {
@@ -57,17 +164,51 @@ impl MerkleNode {
             false
         }
     }
+
+    /// The hashes of the leaves the tree was built from, in order: its
+    /// nodes without children, left to right.
+    pub fn leaf_hashes(&self) -> Vec<Vec<u8>> {
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => {
+                let mut row = left.leaf_hashes();
+                row.extend(right.leaf_hashes());
+                row
+            }
+            _ => vec![self.hash.clone()],
+        }
+    }
+
+    /// Like `verify`, for leaves of which only some are disclosed.
+    pub fn verify_disclosed(&self, leaves: &[DisclosedLeaf]) -> bool {
+        let hashes = leaves.iter()
+            .map(|leaf| match leaf {
+                DisclosedLeaf::Data(data) => hash_leaf(data),
+                DisclosedLeaf::Hash(hash) => hash.clone(),
+            })
+            .collect();
+        build_merkle_tree_from_hashes(hashes)
+            .is_some_and(|tree| self.hash == tree.hash)
+    }
 }
 
 pub fn build_merkle_tree(leaves: Vec<Vec<u8>>) -> Option<MerkleNode> {
-    if leaves.is_empty() {
+    build_from_nodes(leaves.iter()
+        .map(|data| MerkleNode::new(data))
+        .collect())
+}
+
+/// Builds the same tree as `build_merkle_tree` from the leaves' hashes.
+pub fn build_merkle_tree_from_hashes(leaf_hashes: Vec<Vec<u8>>) -> Option<MerkleNode> {
+    build_from_nodes(leaf_hashes.into_iter()
+        .map(MerkleNode::from_hash)
+        .collect())
+}
+
+fn build_from_nodes(mut nodes: Vec<MerkleNode>) -> Option<MerkleNode> {
+    if nodes.is_empty() {
         return None;
     }
 
-    let mut nodes: Vec<MerkleNode> = leaves.iter()
-        .map(|data| MerkleNode::new(data))
-        .collect();
-
     while nodes.len() > 1 {
         let mut new_nodes = Vec::new();
         for chunk in nodes.chunks(2) {
@@ -78,12 +219,10 @@ pub fn build_merkle_tree(leaves: Vec<Vec<u8>>) -> Option<MerkleNode> {
                         right.clone()
                     ));
                 }
-                [left] => {
-                    // If odd number of nodes, duplicate the last one
-                    new_nodes.push(MerkleNode::from_children(
-                        left.clone(),
-                        left.clone()
-                    ));
+                [last] => {
+                    // The last node of an odd level is promoted to the
+                    // next as it is, so no two leaf lists share a root
+                    new_nodes.push(last.clone());
                 }
                 _ => unreachable!(),
             }
@@ -92,4 +231,85 @@ pub fn build_merkle_tree(leaves: Vec<Vec<u8>>) -> Option<MerkleNode> {
     }
 
     Some(nodes.pop().unwrap())
-} 
\ No newline at end of file
+} 
+
+/// SHA-256 of `0x00 || data`.
+pub fn hash_leaf(data: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize().to_vec()
+}
+
+/// SHA-256 of `0x01 || left || right`.
+pub fn hash_children(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().to_vec()
+}
+
+/// The sibling hashes on the way from the leaf at `index` up to the root of
+/// the tree `build_merkle_tree` builds over `leaves`, or None if there is no
+/// such leaf.
+pub fn inclusion_proof(leaves: &[Vec<u8>], mut index: usize) -> Option<Vec<ProofStep>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut level: Vec<Vec<u8>> = leaves.iter().map(|data| hash_leaf(data)).collect();
+    let mut path = Vec::new();
+    while level.len() > 1 {
+        // The last node of an odd level has no sibling and is promoted
+        if let Some(sibling) = level.get(index ^ 1) {
+            let side = if index.is_multiple_of(2) { Side::Right } else { Side::Left };
+            path.push(ProofStep { sibling: hex::encode(sibling), side });
+        }
+
+        level = level.chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_children(left, right),
+                [last] => last.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+        index /= 2;
+    }
+    Some(path)
+}
+
+/// The root an inclusion proof leads to from the hash of the leaf at
+/// `index` of `count`, joining each sibling on its side. The tree's shape
+/// fixes how many siblings there are and which side each joins on, so
+/// None if the path doesn't fit it.
+pub fn root_from_proof(leaf_hash: &[u8], mut index: usize, mut count: usize, path: &[ProofStep]) -> Result<Option<Vec<u8>>, hex::FromHexError> {
+    if index >= count {
+        return Ok(None);
+    }
+    let mut steps = path.iter();
+    let mut hash = leaf_hash.to_vec();
+    while count > 1 {
+        // Nodes are paired left to right; an odd level's last is promoted
+        if index ^ 1 < count {
+            let Some(step) = steps.next() else {
+                return Ok(None);
+            };
+            let side = if index.is_multiple_of(2) { Side::Right } else { Side::Left };
+            if step.side != side {
+                return Ok(None);
+            }
+            let sibling = hex::decode(&step.sibling)?;
+            hash = match side {
+                Side::Left => hash_children(&sibling, &hash),
+                Side::Right => hash_children(&hash, &sibling),
+            };
+        }
+        index /= 2;
+        count = count.div_ceil(2);
+    }
+    if steps.next().is_some() {
+        return Ok(None);
+    }
+    Ok(Some(hash))
+}
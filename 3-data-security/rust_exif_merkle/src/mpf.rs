@@ -0,0 +1,111 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use sha2::{Digest, Sha256};
+
+use crate::jpeg;
+use crate::leaf::{Source, named_leaf};
+use crate::tiff::{Endian, read_ifd};
+
+/// Identifier in front of the MPF index in an APP2 segment
+const MPF_HEADER: &[u8] = b"MPF\0";
+
+const MPF_VERSION_TAG: u16 = 0xB000;
+const NUMBER_OF_IMAGES_TAG: u16 = 0xB001;
+const MP_ENTRY_TAG: u16 = 0xB002;
+
+/// Length of one image's entry in the MP Entry tag
+const MP_ENTRY_LEN: usize = 16;
+
+/// SHA-256 of `len` bytes of the file from `offset`.
+fn hash_range(path: &Path, offset: u64, len: u64) -> Result<String, Box<dyn std::error::Error>> {
+    let mut file = File::open(path)?;
+    if offset + len > file.metadata()?.len() {
+        return Err(format!("MPF image runs past the end of {}", path.display()).into());
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file.take(len), &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Adds `delta` to the size the MPF index in an APP2 segment's payload
+/// records for the primary image, after the primary image's segments have
+/// grown or shrunk by that much. The other images' offsets count from the
+/// MP header, so they don't move. Payloads that aren't an MPF index are
+/// left as they are.
+pub fn shift_primary_size(payload: &mut [u8], delta: i64) -> Result<(), Box<dyn std::error::Error>> {
+    if !payload.starts_with(MPF_HEADER) {
+        return Ok(());
+    }
+    let header = &mut payload[MPF_HEADER.len()..];
+    let endian = Endian::of_header(header).ok_or("Invalid MPF index")?;
+    let ifd_offset = endian.u32(header.get(4..8).ok_or("Invalid MPF index")?);
+    let Some(entry) = read_ifd(header, ifd_offset as usize, endian)?
+        .into_iter()
+        .find(|entry| entry.tag == MP_ENTRY_TAG)
+    else {
+        return Ok(());
+    };
+    let image = entry
+        .value_bytes(header, endian)?
+        .ok_or("Invalid MPF index")?
+        .chunks_exact(MP_ENTRY_LEN)
+        .position(|entry| endian.u32(&entry[8..12]) == 0)
+        .ok_or("MPF index has no primary image")?;
+    // Stored at an offset, as each image's entry is longer than four bytes
+    let primary = endian.u32(&entry.value) as usize + image * MP_ENTRY_LEN;
+    let size = endian.u32(&header[primary + 4..primary + 8]);
+    let size = u32::try_from(size as i64 + delta).map_err(|_| "MPF primary image size out of range")?;
+    header[primary + 4..primary + 8].copy_from_slice(&endian.u32_bytes(size));
+    Ok(())
+}
+
+/// Leaves for a JPEG's Multi-Picture Format index, which lists the other
+/// images stored after the primary one, such as the large previews of Sony
+/// and Fujifilm cameras: `MPF:MPFVersion`, `MPF:NumberOfImages`, and for
+/// each image `MPF:Image<n>:Type` and, past the primary, `MPF:Image<n>:SHA256`
+/// of its bytes, so those images are committed to as well.
+pub fn mpf_leaves(path: &Path) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let Some((segment_offset, segment)) = jpeg::read_segments_at(path, jpeg::APP2)?
+        .into_iter()
+        .find(|(_, segment)| segment.starts_with(MPF_HEADER))
+    else {
+        return Ok(Vec::new());
+    };
+    let invalid = || format!("Invalid MPF index in {}", path.display());
+    // Image offsets count from the MP header, which starts like a TIFF file
+    let header_offset = segment_offset + MPF_HEADER.len() as u64;
+    let header = &segment[MPF_HEADER.len()..];
+    let endian = Endian::of_header(header).ok_or_else(invalid)?;
+
+    let ifd_offset = endian.u32(header.get(4..8).ok_or_else(invalid)?);
+
+    let mut leaves = Vec::new();
+    for entry in read_ifd(header, ifd_offset as usize, endian)? {
+        match entry.tag {
+            MPF_VERSION_TAG => {
+                leaves.push(named_leaf(Source::Mpf, "MPFVersion", &entry.value));
+            }
+            NUMBER_OF_IMAGES_TAG => {
+                leaves.push(named_leaf(Source::Mpf, "NumberOfImages", endian.u32(&entry.value).to_string().as_bytes()));
+            }
+            MP_ENTRY_TAG => {
+                let entries = entry.value_bytes(header, endian)?.ok_or_else(invalid)?;
+                for (image, entry) in entries.chunks_exact(MP_ENTRY_LEN).enumerate() {
+                    let name = format!("Image{}", image + 1);
+                    let image_type = endian.u32(&entry[..4]) & 0xFF_FFFF;
+                    leaves.push(named_leaf(Source::Mpf, &format!("{}:Type", name), format!("{:#08X}", image_type).as_bytes()));
+                    // The primary image is at offset 0, and has its own leaf
+                    let (size, offset) = (endian.u32(&entry[4..8]), endian.u32(&entry[8..12]));
+                    if offset != 0 {
+                        let hash = hash_range(path, header_offset + offset as u64, size as u64)?;
+                        leaves.push(named_leaf(Source::Mpf, &format!("{}:SHA256", name), hash.as_bytes()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(leaves)
+}
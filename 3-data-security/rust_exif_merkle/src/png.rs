@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::io::{Read, BufReader};
+use std::path::Path;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// iTXt keyword Adobe uses for an embedded XMP packet
+const XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+pub fn is_png(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut signature = [0u8; 8];
+    let read = File::open(path)?.read(&mut signature)?;
+    Ok(read == signature.len() && signature == PNG_SIGNATURE)
+}
+
+/// The data of every chunk of type `chunk_type`, in file order. Other
+/// chunks, including the image data, are skipped without being read.
+fn read_chunks(path: &Path, chunk_type: &[u8; 4]) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut signature = [0u8; 8];
+    reader.read_exact(&mut signature)?;
+    if signature != PNG_SIGNATURE {
+        return Err(format!("Not a PNG: {}", path.display()).into());
+    }
+
+    let mut chunks = Vec::new();
+    loop {
+        // Length, then type; the data follows, then a 4-byte CRC
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = &header[4..];
+
+        if kind == chunk_type {
+            let mut data = vec![0u8; len];
+            reader.read_exact(&mut data)?;
+            chunks.push(data);
+            reader.seek_relative(4)?;
+        } else if kind == b"IEND" {
+            return Ok(chunks);
+        } else {
+            reader.seek_relative(len as i64 + 4)?;
+        }
+    }
+}
+
+/// The raw EXIF (TIFF) data in the eXIf chunk, if there is one.
+pub fn exif_chunk(path: &Path) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    Ok(read_chunks(path, b"eXIf")?.into_iter().next())
+}
+
+/// The XMP packet in the image's iTXt chunks, if there is one.
+pub fn xmp_packet(path: &Path) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    for chunk in read_chunks(path, b"iTXt")? {
+        // Keyword, NUL, compression flag and method, language tag, NUL,
+        // translated keyword, NUL, then the text
+        let mut fields = chunk.splitn(2, |&b| b == 0);
+        if fields.next() != Some(XMP_KEYWORD) {
+            continue;
+        }
+        let rest = fields.next().unwrap_or_default();
+        if rest.first().is_some_and(|&compressed| compressed != 0) {
+            return Err(format!("Compressed XMP is not supported: {}", path.display()).into());
+        }
+        let text = rest.get(2..)
+            .and_then(|rest| rest.splitn(3, |&b| b == 0).nth(2))
+            .ok_or_else(|| format!("Malformed iTXt chunk in {}", path.display()))?;
+        return Ok(Some(String::from_utf8(text.to_vec())?));
+    }
+    Ok(None)
+}
@@ -0,0 +1,142 @@
+use std::path::Path;
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{exif_leaves, leaf};
+
+/// Formats a date tag's value may be in: EXIF's as read back, EXIF's as
+/// stored, and XMP's without a zone offset
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S", "%Y:%m:%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S%.f"];
+
+/// What an image's metadata must satisfy, read from a JSON policy file.
+/// Tags are named as in `prove --tag`, e.g. `Model` or `XMP:dc:creator[1]`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// Tags the image must have
+    pub required_tags: Vec<String>,
+    /// Camera makes allowed in the Make tag; empty allows any
+    pub allowed_makes: Vec<String>,
+    /// Camera models allowed in the Model tag; empty allows any
+    pub allowed_models: Vec<String>,
+    pub timestamp_windows: Vec<TimestampWindow>,
+}
+
+/// A range a date tag such as DateTimeOriginal must fall in, either end
+/// optional, as `YYYY-MM-DD HH:MM:SS`
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TimestampWindow {
+    pub tag: String,
+    pub not_before: Option<String>,
+    pub not_after: Option<String>,
+}
+
+/// The outcome of one rule of a policy
+#[derive(Serialize, Debug)]
+pub struct PolicyCheck {
+    pub rule: String,
+    pub passed: bool,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PolicyReport {
+    pub image: String,
+    pub passed: bool,
+    pub checks: Vec<PolicyCheck>,
+}
+
+impl Policy {
+    pub fn load_from_file(filepath: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = std::fs::read_to_string(filepath)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// A date as the camera's local clock time. EXIF dates have no zone, so an
+/// XMP date's offset is dropped to compare like with like.
+fn parse_date(value: &str) -> Option<NaiveDateTime> {
+    DATE_FORMATS.iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| DateTime::parse_from_rfc3339(value).ok().map(|date| date.naive_local()))
+}
+
+/// Checks that every value of `tag` is one of `allowed`.
+fn check_allowed(rule: &str, tag: &str, allowed: &[String], leaves: &[leaf::DecodedLeaf]) -> PolicyCheck {
+    let values: Vec<&str> = leaves.iter()
+        .filter(|leaf| leaf.name == tag)
+        .map(|leaf| leaf.unquoted_value())
+        .collect();
+    let (passed, reason) = match values.iter().find(|value| !allowed.iter().any(|allowed| allowed == *value)) {
+        _ if values.is_empty() => (false, format!("no {} tag", tag)),
+        Some(value) => (false, format!("{} is not allowed", value)),
+        None => (true, format!("{} is allowed", values[0])),
+    };
+    PolicyCheck { rule: rule.to_string(), passed, reason }
+}
+
+fn check_window(window: &TimestampWindow, leaves: &[leaf::DecodedLeaf]) -> Result<PolicyCheck, Box<dyn std::error::Error>> {
+    let bound = |bound: &Option<String>| bound.as_deref()
+        .map(|bound| parse_date(bound).ok_or_else(|| format!("Invalid date in policy: {}", bound)))
+        .transpose();
+    let (not_before, not_after) = (bound(&window.not_before)?, bound(&window.not_after)?);
+
+    let values: Vec<&str> = leaves.iter()
+        .filter(|leaf| leaf.name == window.tag)
+        .map(|leaf| leaf.unquoted_value())
+        .collect();
+    let mut reason = None;
+    for value in &values {
+        reason = match parse_date(value) {
+            None => Some(format!("{} is not a date", value)),
+            Some(date) if not_before.is_some_and(|not_before| date < not_before) => {
+                Some(format!("{} is before {}", value, window.not_before.as_deref().unwrap_or_default()))
+            }
+            Some(date) if not_after.is_some_and(|not_after| date > not_after) => {
+                Some(format!("{} is after {}", value, window.not_after.as_deref().unwrap_or_default()))
+            }
+            Some(_) => continue,
+        };
+        break;
+    }
+    let (passed, reason) = match reason {
+        _ if values.is_empty() => (false, format!("no {} tag", window.tag)),
+        Some(reason) => (false, reason),
+        None => (true, format!("{} is in the window", values[0])),
+    };
+    Ok(PolicyCheck { rule: format!("{} window", window.tag), passed, reason })
+}
+
+/// Checks the image's metadata against the policy, rule by rule.
+pub fn check_policy(image_path: &Path, policy: &Policy) -> Result<PolicyReport, Box<dyn std::error::Error>> {
+    let leaves = exif_leaves(image_path, None)?
+        .iter()
+        .map(|data| leaf::decode(data))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut checks = Vec::new();
+    for tag in &policy.required_tags {
+        let passed = leaves.iter().any(|leaf| leaf.name == *tag);
+        checks.push(PolicyCheck {
+            rule: format!("required {}", tag),
+            passed,
+            reason: if passed { "present".to_string() } else { "missing".to_string() },
+        });
+    }
+    if !policy.allowed_makes.is_empty() {
+        checks.push(check_allowed("allowed makes", "Make", &policy.allowed_makes, &leaves));
+    }
+    if !policy.allowed_models.is_empty() {
+        checks.push(check_allowed("allowed models", "Model", &policy.allowed_models, &leaves));
+    }
+    for window in &policy.timestamp_windows {
+        checks.push(check_window(window, &leaves)?);
+    }
+
+    Ok(PolicyReport {
+        image: image_path.display().to_string(),
+        passed: checks.iter().all(|check| check.passed),
+        checks,
+    })
+}
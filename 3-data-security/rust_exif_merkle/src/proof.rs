@@ -0,0 +1,187 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::{exif_leaves, leaf, makernote_leaves};
+use crate::merkle::{ProofStep, build_merkle_tree, hash_leaf, inclusion_proof, root_from_proof};
+
+/// Everything a verifier needs to check one EXIF field against a published
+/// root, without seeing the image or any other field
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InclusionProof {
+    pub tag: String,
+    /// Hex of the disclosed leaf in its canonical encoding
+    pub leaf: String,
+    /// The leaf read back as `tag:value`, for people; check it against
+    /// `leaf` rather than trusting it
+    pub value: String,
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    /// Siblings from the leaf up to the root
+    pub path: Vec<ProofStep>,
+    /// Hex root hash the path leads to
+    pub root: String,
+    /// Hex salt of the leaf in a salted tree, see `leaf::leaf_salt`, which
+    /// the leaf is HMACed with before hashing. The tree's own salt is not
+    /// revealed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    /// For a maker note entry, whose `path` leads to the root of the maker
+    /// note's subtree: the proof of that subtree's leaf in the main tree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<ParentProof>,
+}
+
+/// Proof of the `MakerNote` leaf, holding a maker note subtree's root, in
+/// the main tree
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ParentProof {
+    /// Hex of the `MakerNote` leaf in its canonical encoding
+    pub leaf: String,
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    /// Siblings from the `MakerNote` leaf up to the main tree's root
+    pub path: Vec<ProofStep>,
+    /// Hex salt of the `MakerNote` leaf in a salted tree; the proof's own
+    /// `salt` is then the entry's, within the subtree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+}
+
+impl InclusionProof {
+    /// The proven leaf read back from `leaf`, after checking it is the
+    /// proof's tag; `value` is not trusted.
+    pub fn decoded_leaf(&self) -> Result<leaf::DecodedLeaf, Box<dyn std::error::Error>> {
+        let decoded = leaf::decode(&hex::decode(&self.leaf)?)?;
+        if decoded.name != self.tag {
+            return Err(format!("Proof for {} holds a {} leaf", self.tag, decoded.name).into());
+        }
+        Ok(decoded)
+    }
+
+    /// Whether the leaf's path leads to `root`, a hex root hash from a
+    /// trusted source, with the siblings a tree of `leaf_count` leaves has
+    /// for `leaf_index`. The proof's own `root` field is not trusted.
+    pub fn verify(&self, root: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut data = tree_leaf(&self.leaf, self.salt.as_deref())?;
+        let (mut index, mut count, mut path) = (self.leaf_index, self.leaf_count, &self.path);
+        if let Some(parent) = &self.parent {
+            // A subtree is salted only in a salted tree, and then both are
+            if parent.salt.is_some() != self.salt.is_some() {
+                return Ok(false);
+            }
+            // The subtree's root must be the one the parent leaf holds
+            let Some(subtree_root) = root_from_proof(&hash_leaf(&data), index, count, path)? else {
+                return Ok(false);
+            };
+            if hex::decode(&parent.leaf)? != leaf::makernote_root_leaf(&subtree_root) {
+                return Ok(false);
+            }
+            data = tree_leaf(&parent.leaf, parent.salt.as_deref())?;
+            (index, count, path) = (parent.leaf_index, parent.leaf_count, &parent.path);
+        }
+        Ok(root_from_proof(&hash_leaf(&data), index, count, path)? == Some(hex::decode(root)?))
+    }
+}
+
+/// A hex leaf as hashed into its tree, salted with its hex salt if it has
+/// one.
+fn tree_leaf(leaf: &str, salt: Option<&str>) -> Result<Vec<u8>, hex::FromHexError> {
+    let leaf = hex::decode(leaf)?;
+    Ok(match salt {
+        Some(salt) => leaf::salted_leaf(&hex::decode(salt)?, &leaf),
+        None => leaf,
+    })
+}
+
+/// Proves each of the image's leaves for `tag`. Most tags have one; tags
+/// such as XResolution can appear in both the primary and thumbnail IFDs.
+/// Maker note entries, e.g. `MakerNote:SerialNumber`, are proven within
+/// the maker note's subtree and then, through its `MakerNote` leaf, within
+/// the main tree. For a salted tree, pass its salt; each proof carries only
+/// the salt of its own leaf, and of the `MakerNote` leaf for an entry.
+pub fn prove_tag(image_path: &Path, tag: &str, salt: Option<&[u8]>) -> Result<Vec<InclusionProof>, Box<dyn std::error::Error>> {
+    let leaves = exif_leaves(image_path, salt)?;
+    let tree_leaves = leaf::tree_leaves(&leaves, salt);
+    let root = build_merkle_tree(tree_leaves.clone())
+        .ok_or("Failed to build Merkle tree")?;
+
+    if tag.starts_with("MakerNote:") {
+        return prove_makernote_entry(image_path, tag, &leaves, &tree_leaves, &root.hash, salt);
+    }
+
+    let mut proofs = Vec::new();
+    for (index, leaf) in leaves.iter().enumerate() {
+        let decoded = leaf::decode(leaf)?;
+        if decoded.name != tag {
+            continue;
+        }
+        proofs.push(InclusionProof {
+            tag: tag.to_string(),
+            leaf: hex::encode(leaf),
+            value: decoded.to_string(),
+            leaf_index: index,
+            leaf_count: leaves.len(),
+            path: inclusion_proof(&tree_leaves, index).expect("leaf index is in range"),
+            root: hex::encode(&root.hash),
+            salt: salt.map(|salt| hex::encode(leaf::leaf_salt(salt, index))),
+            parent: None,
+        });
+    }
+
+    if proofs.is_empty() {
+        return Err(format!("{} has no {} tag", image_path.display(), tag).into());
+    }
+    Ok(proofs)
+}
+
+/// Proves each maker note entry for `tag` in the subtree, with the proof of
+/// the subtree's `MakerNote` leaf among the main tree's `leaves`.
+fn prove_makernote_entry(
+    image_path: &Path,
+    tag: &str,
+    leaves: &[Vec<u8>],
+    tree_leaves: &[Vec<u8>],
+    root: &[u8],
+    salt: Option<&[u8]>,
+) -> Result<Vec<InclusionProof>, Box<dyn std::error::Error>> {
+    let entries = makernote_leaves(image_path)
+        .ok_or_else(|| format!("{} has no maker note this can read", image_path.display()))?;
+    let subtree_salt = salt.map(leaf::makernote_salt);
+    let subtree_leaves = leaf::tree_leaves(&entries, subtree_salt.as_deref());
+    let subtree_root = build_merkle_tree(subtree_leaves.clone())
+        .ok_or("Failed to build maker note tree")?;
+    let parent_leaf = leaf::makernote_root_leaf(&subtree_root.hash);
+    let parent_index = leaves.iter()
+        .position(|leaf| *leaf == parent_leaf)
+        .ok_or("Maker note subtree isn't in the tree")?;
+
+    let mut proofs = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let decoded = leaf::decode(entry)?;
+        if decoded.name != tag {
+            continue;
+        }
+        proofs.push(InclusionProof {
+            tag: tag.to_string(),
+            leaf: hex::encode(entry),
+            value: decoded.to_string(),
+            leaf_index: index,
+            leaf_count: entries.len(),
+            path: inclusion_proof(&subtree_leaves, index).expect("leaf index is in range"),
+            root: hex::encode(root),
+            salt: subtree_salt.as_deref().map(|salt| hex::encode(leaf::leaf_salt(salt, index))),
+            parent: Some(ParentProof {
+                leaf: hex::encode(&parent_leaf),
+                leaf_index: parent_index,
+                leaf_count: leaves.len(),
+                path: inclusion_proof(tree_leaves, parent_index).expect("leaf index is in range"),
+                salt: salt.map(|salt| hex::encode(leaf::leaf_salt(salt, parent_index))),
+            }),
+        });
+    }
+
+    if proofs.is_empty() {
+        return Err(format!("{} has no {} tag", image_path.display(), tag).into());
+    }
+    Ok(proofs)
+}
@@ -0,0 +1,212 @@
+use std::fs::File;
+use std::io::{Read, BufReader};
+use std::path::Path;
+use chrono::DateTime;
+
+use crate::isobmff::{self, Fields, child_boxes, find_box};
+use crate::leaf::{Source, named_leaf};
+
+/// ftyp brands of MP4 and QuickTime movies, including phone and camera
+/// variants
+const VIDEO_BRANDS: [&[u8; 4]; 14] = [
+    b"isom", b"iso2", b"iso4", b"iso5", b"iso6", b"mp41", b"mp42", b"avc1",
+    b"qt  ", b"M4V ", b"3gp4", b"3gp5", b"3g2a", b"XAVC",
+];
+
+/// Top-level boxes an older QuickTime movie, with no ftyp box, starts with
+const QUICKTIME_LEADING_BOXES: [&[u8; 4]; 5] = [b"moov", b"mdat", b"wide", b"free", b"skip"];
+
+/// Seconds from the QuickTime epoch, 1904-01-01, to the Unix epoch
+const QUICKTIME_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Leaf names for QuickTime's ©-prefixed user data atoms
+const USER_DATA_NAMES: [(&[u8; 4], &str); 10] = [
+    (b"\xA9xyz", "GPSCoordinates"),
+    (b"\xA9mak", "Make"),
+    (b"\xA9mod", "Model"),
+    (b"\xA9swr", "Software"),
+    (b"\xA9day", "ContentCreateDate"),
+    (b"\xA9too", "Encoder"),
+    (b"\xA9nam", "Title"),
+    (b"\xA9des", "Description"),
+    (b"\xA9cmt", "Comment"),
+    (b"\xA9ART", "Artist"),
+];
+
+/// Whether the file is an MP4 or QuickTime movie, going by its ftyp brands,
+/// or by its first box for QuickTime movies without one.
+pub fn is_video(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    if isobmff::is_heif(path)? {
+        return Ok(false);
+    }
+    if let Some(brands) = isobmff::ftyp_brands(path)? {
+        return Ok(brands.iter().any(|brand| VIDEO_BRANDS.contains(&brand)));
+    }
+    let mut header = [0u8; 8];
+    let read = File::open(path)?.read(&mut header)?;
+    Ok(read == header.len() && QUICKTIME_LEADING_BOXES.iter().any(|box_type| box_type.as_slice() == &header[4..]))
+}
+
+/// An atom type as text, reading the © prefix byte as Latin-1
+fn atom_name(atom_type: &[u8; 4]) -> String {
+    USER_DATA_NAMES.iter()
+        .find(|(known_type, _)| *known_type == atom_type)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| atom_type.iter().map(|&b| b as char).collect())
+}
+
+fn format_time(quicktime_seconds: u64) -> Result<String, Box<dyn std::error::Error>> {
+    let time = DateTime::from_timestamp(quicktime_seconds as i64 - QUICKTIME_EPOCH_OFFSET, 0)
+        .ok_or("Invalid QuickTime timestamp")?;
+    Ok(time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+}
+
+/// Creation and modification time, and duration, from the movie header.
+fn movie_header_values(mvhd: &[u8], values: &mut Vec<(String, String)>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut fields = Fields::new(mvhd);
+    let version = fields.full_box_header()?;
+    let (created, modified) = if version == 1 {
+        (fields.u64()?, fields.u64()?)
+    } else {
+        (fields.u32()? as u64, fields.u32()? as u64)
+    };
+    let timescale = fields.u32()?;
+    let duration = if version == 1 { fields.u64()? } else { fields.u32()? as u64 };
+
+    for (name, time) in [("CreationTime", created), ("ModificationTime", modified)] {
+        // Zero when the recorder didn't set it
+        if time != 0 {
+            values.push((name.to_string(), format_time(time)?));
+        }
+    }
+    if timescale != 0 {
+        values.push(("Duration".to_string(), format!("{:.3} s", duration as f64 / timescale as f64)));
+    }
+    Ok(())
+}
+
+/// A big-endian integer of 1 to 8 bytes.
+fn int_value(bytes: &[u8], signed: bool) -> String {
+    let fill = if signed && bytes[0] & 0x80 != 0 { 0xFF } else { 0 };
+    let mut buf = [fill; 8];
+    buf[8 - bytes.len()..].copy_from_slice(bytes);
+    if signed {
+        i64::from_be_bytes(buf).to_string()
+    } else {
+        u64::from_be_bytes(buf).to_string()
+    }
+}
+
+/// A data box's value, shown according to its well-known type; values of
+/// other types are shown as hex.
+fn data_value(data: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut fields = Fields::new(data);
+    let data_type = fields.u32()? & 0xFF_FFFF;
+    fields.u32()?; // locale
+    let value = fields.rest();
+    Ok(match (data_type, value.len()) {
+        (1, _) => String::from_utf8_lossy(value).into_owned(),
+        (21, 1 | 2 | 4 | 8) => int_value(value, true),
+        (22, 1 | 2 | 4 | 8) => int_value(value, false),
+        (23, 4) => f32::from_be_bytes(value.try_into()?).to_string(),
+        (24, 8) => f64::from_be_bytes(value.try_into()?).to_string(),
+        _ => hex::encode(value),
+    })
+}
+
+/// The key names in a keys box, in order; items refer to them from 1.
+fn read_keys(keys: &[u8]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut fields = Fields::new(keys);
+    fields.full_box_header()?;
+    let count = fields.u32()?;
+    let mut names = Vec::new();
+    for _ in 0..count {
+        // Size, counting itself and the namespace, then namespace and name
+        let size = fields.u32()? as usize;
+        fields.take(4)?;
+        let name = fields.take(size.checked_sub(8).ok_or("Invalid metadata key size")?)?;
+        names.push(String::from_utf8_lossy(name).into_owned());
+    }
+    Ok(names)
+}
+
+/// Every value in a meta box's item list. QuickTime metadata names its
+/// items in a keys box, e.g. `com.apple.quicktime.model`; otherwise items
+/// are named by their atom type, as in iTunes-style lists.
+fn metadata_values(meta: &[u8], values: &mut Vec<(String, String)>) -> Result<(), Box<dyn std::error::Error>> {
+    // An MP4 meta box is a full box; a QuickTime one starts straight with
+    // its hdlr box
+    let body = if meta.get(4..8) == Some(b"hdlr".as_slice()) {
+        meta
+    } else {
+        meta.get(4..).ok_or("Truncated meta box")?
+    };
+    let boxes = child_boxes(body)?;
+    let keys = match find_box(&boxes, b"keys") {
+        Some(keys) => read_keys(keys)?,
+        None => Vec::new(),
+    };
+    let Some(ilst) = find_box(&boxes, b"ilst") else {
+        return Ok(());
+    };
+
+    for (item_type, item) in child_boxes(ilst)? {
+        let name = if keys.is_empty() {
+            atom_name(&item_type)
+        } else {
+            let index = u32::from_be_bytes(item_type) as usize;
+            keys.get(index.wrapping_sub(1))
+                .cloned()
+                .ok_or_else(|| format!("No key for metadata item {}", index))?
+        };
+        for (box_type, data) in child_boxes(item)? {
+            if &box_type == b"data" {
+                values.push((name.clone(), data_value(data)?));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// QuickTime user data: ©-prefixed text atoms such as the ©xyz location,
+/// and any meta box inside it.
+fn user_data_values(udta: &[u8], values: &mut Vec<(String, String)>) -> Result<(), Box<dyn std::error::Error>> {
+    for (atom_type, atom) in child_boxes(udta)? {
+        if &atom_type == b"meta" {
+            metadata_values(atom, values)?;
+        } else if atom_type[0] == 0xA9 {
+            // Text length and language, then the text
+            let mut fields = Fields::new(atom);
+            let len = fields.u16()? as usize;
+            fields.u16()?;
+            let text = String::from_utf8_lossy(fields.take(len)?);
+            values.push((atom_name(&atom_type), text.trim_end_matches('\0').to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// One leaf per metadata value in the movie: `CreationTime`,
+/// `ModificationTime` and `Duration` from the movie header, user data such
+/// as `GPSCoordinates` from ©xyz, and device keys such as
+/// `com.apple.quicktime.model`.
+pub fn video_leaves(path: &Path) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let moov = isobmff::read_top_level_box(&mut reader, b"moov")?
+        .ok_or_else(|| format!("No moov box in {}", path.display()))?;
+    let boxes = child_boxes(&moov)?;
+
+    let mut values = Vec::new();
+    if let Some(mvhd) = find_box(&boxes, b"mvhd") {
+        movie_header_values(mvhd, &mut values)?;
+    }
+    if let Some(udta) = find_box(&boxes, b"udta") {
+        user_data_values(udta, &mut values)?;
+    }
+    if let Some(meta) = find_box(&boxes, b"meta") {
+        metadata_values(meta, &mut values)?;
+    }
+    Ok(values.into_iter()
+        .map(|(name, value)| named_leaf(Source::Movie, &name, value.as_bytes()))
+        .collect())
+}
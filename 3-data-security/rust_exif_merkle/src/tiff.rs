@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Byte-order marks and the magic number 42, little- and big-endian
+pub const TIFF_HEADERS: [[u8; 4]; 2] = [*b"II*\0", *b"MM\0*"];
+
+/// Whether the file is TIFF-based, like TIFF itself, DNG, and Canon CR2,
+/// Nikon NEF and Sony ARW raws: its metadata, maker note included, is the
+/// file's own IFD structure, with no container around it.
+pub fn is_tiff(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut header = [0u8; 4];
+    let read = File::open(path)?.read(&mut header)?;
+    Ok(read == header.len() && TIFF_HEADERS.contains(&header))
+}
+
+/// Reads integers in a TIFF structure's byte order.
+#[derive(Debug, Clone, Copy)]
+pub struct Endian {
+    pub little: bool,
+}
+
+impl Endian {
+    /// The byte order of TIFF data starting with one of `TIFF_HEADERS`.
+    pub fn of_header(data: &[u8]) -> Option<Self> {
+        TIFF_HEADERS.iter()
+            .position(|header| data.starts_with(header))
+            .map(|index| Endian { little: index == 0 })
+    }
+
+    pub fn u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.little { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) }
+    }
+
+    pub fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.little { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) }
+    }
+
+    pub fn u32_bytes(&self, value: u32) -> [u8; 4] {
+        if self.little { value.to_le_bytes() } else { value.to_be_bytes() }
+    }
+}
+
+/// One entry of an IFD: tag, TIFF field type, count, and the four bytes
+/// holding the value or, for values that don't fit, its offset
+#[derive(Debug, Clone, Copy)]
+pub struct IfdEntry {
+    pub tag: u16,
+    pub field_type: u16,
+    pub count: u32,
+    pub value: [u8; 4],
+}
+
+/// Size of one value of a TIFF field type, or None for types this doesn't
+/// know.
+pub fn unit_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 | 13 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+/// The entries of the IFD at `offset` into `data`.
+pub fn read_ifd(data: &[u8], offset: usize, endian: Endian) -> Result<Vec<IfdEntry>, Box<dyn std::error::Error>> {
+    let count = endian.u16(data.get(offset..offset + 2).ok_or("IFD out of range")?) as usize;
+    let entries = data.get(offset + 2..offset + 2 + count * 12).ok_or("Truncated IFD")?;
+    Ok(entries.chunks_exact(12)
+        .map(|entry| IfdEntry {
+            tag: endian.u16(&entry[..2]),
+            field_type: endian.u16(&entry[2..4]),
+            count: endian.u32(&entry[4..8]),
+            value: entry[8..12].try_into().expect("entries are 12 bytes"),
+        })
+        .collect())
+}
+
+impl IfdEntry {
+    /// The entry's value bytes, in place if they fit in four bytes, or else
+    /// at their offset into `data`. None for types this doesn't know.
+    pub fn value_bytes<'a>(&'a self, data: &'a [u8], endian: Endian) -> Result<Option<&'a [u8]>, Box<dyn std::error::Error>> {
+        let Some(unit_size) = unit_size(self.field_type) else {
+            return Ok(None);
+        };
+        let len = unit_size.checked_mul(self.count as usize).ok_or("IFD value too large")?;
+        if len <= 4 {
+            return Ok(Some(&self.value[..len]));
+        }
+        let offset = endian.u32(&self.value) as usize;
+        Ok(Some(data.get(offset..offset.saturating_add(len)).ok_or("IFD value out of range")?))
+    }
+
+    /// The value bytes made big-endian, as leaves hold them.
+    pub fn big_endian_value(&self, data: &[u8], endian: Endian) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        let Some(bytes) = self.value_bytes(data, endian)? else {
+            return Ok(None);
+        };
+        // Rationals are two 4-byte integers, each swapped on its own
+        let swap_size = match self.field_type {
+            5 | 10 => 4,
+            field_type => unit_size(field_type).unwrap_or(1),
+        };
+        if !endian.little || swap_size == 1 {
+            return Ok(Some(bytes.to_vec()));
+        }
+        Ok(Some(bytes.chunks_exact(swap_size)
+            .flat_map(|unit| unit.iter().rev().copied())
+            .collect()))
+    }
+}
@@ -0,0 +1,208 @@
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use chrono::NaiveDateTime;
+
+/// DER of the SHA-256 algorithm identifier's OID, 2.16.840.1.101.3.4.2.1
+const SHA256_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+/// DER of id-ct-TSTInfo, 1.2.840.113549.1.9.16.1.4, the content type of a
+/// timestamp token's signed content
+const TST_INFO_OID: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x10, 0x01, 0x04];
+
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OID: u8 = 0x06;
+const GENERALIZED_TIME: u8 = 0x18;
+const SEQUENCE: u8 = 0x30;
+const BOOLEAN: u8 = 0x01;
+/// Tag of an explicit `[0]`, as around signed data and its content
+const CONTEXT_0: u8 = 0xA0;
+
+/// What a timestamp token attests: that `root` existed at `time`
+#[derive(Debug)]
+pub struct TimestampInfo {
+    /// Time the authority signed at, e.g. `2026-10-16 10:23:00 UTC`
+    pub time: String,
+    nonce: Option<Vec<u8>>,
+}
+
+/// A DER element: tag, length and content.
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut element = vec![tag];
+    if content.len() < 0x80 {
+        element.push(content.len() as u8);
+    } else {
+        let len = content.len().to_be_bytes();
+        let len = &len[len.iter().position(|&b| b != 0).unwrap_or(len.len() - 1)..];
+        element.push(0x80 | len.len() as u8);
+        element.extend(len);
+    }
+    element.extend(content);
+    element
+}
+
+/// Splits the next DER element off the front of `rest`, returning its tag
+/// and content.
+fn read_der<'a>(rest: &mut &'a [u8]) -> Result<(u8, &'a [u8]), Box<dyn std::error::Error>> {
+    let truncated = "Truncated timestamp token";
+    let (&tag, after_tag) = rest.split_first().ok_or(truncated)?;
+    let (&first, after_len) = after_tag.split_first().ok_or(truncated)?;
+    let (len, content_at) = if first < 0x80 {
+        (first as usize, after_len)
+    } else {
+        // Long form: the low bits give the size of the length
+        let len_size = (first & 0x7F) as usize;
+        let len_bytes = after_len.get(..len_size).filter(|bytes| bytes.len() <= 8).ok_or("Invalid DER length")?;
+        (len_bytes.iter().fold(0usize, |len, &b| len << 8 | b as usize), &after_len[len_size..])
+    };
+    let content = content_at.get(..len).ok_or(truncated)?;
+    *rest = &content_at[len..];
+    Ok((tag, content))
+}
+
+/// Like `read_der`, for an element that must have `tag`.
+fn expect_der<'a>(rest: &mut &'a [u8], tag: u8) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+    let (found, content) = read_der(rest)?;
+    if found != tag {
+        return Err(format!("Unexpected DER tag {:#04x}, expected {:#04x}", found, tag).into());
+    }
+    Ok(content)
+}
+
+/// An RFC 3161 TimeStampReq for `digest`, a SHA-256 hash, asking for the
+/// authority's certificate in the token.
+fn timestamp_request(digest: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let algorithm = der(SEQUENCE, &[der(OID, SHA256_OID), der(NULL, &[])].concat());
+    let message_imprint = der(SEQUENCE, &[algorithm, der(OCTET_STRING, digest)].concat());
+    der(SEQUENCE, &[
+        der(INTEGER, &[1]),
+        message_imprint,
+        der(INTEGER, nonce),
+        der(BOOLEAN, &[0xFF]),
+    ].concat())
+}
+
+/// A random positive nonce, as DER integer content.
+fn new_nonce() -> Vec<u8> {
+    let mut nonce = rand::random::<[u8; 8]>();
+    // Clear the sign bit, and set the next so there is no leading zero
+    nonce[0] = nonce[0] & 0x7F | 0x40;
+    nonce.to_vec()
+}
+
+/// Reads the TSTInfo out of a timestamp token, a CMS ContentInfo holding
+/// SignedData, and checks its message imprint is `digest`.
+pub fn check_token(token: &[u8], digest: &[u8]) -> Result<TimestampInfo, Box<dyn std::error::Error>> {
+    let mut rest = token;
+    let mut content_info = expect_der(&mut rest, SEQUENCE)?;
+    expect_der(&mut content_info, OID)?;
+    let mut explicit = expect_der(&mut content_info, CONTEXT_0)?;
+    let mut signed_data = expect_der(&mut explicit, SEQUENCE)?;
+    expect_der(&mut signed_data, INTEGER)?; // version
+    read_der(&mut signed_data)?; // digest algorithms
+    let mut encapsulated = expect_der(&mut signed_data, SEQUENCE)?;
+    if expect_der(&mut encapsulated, OID)? != TST_INFO_OID {
+        return Err("Timestamp token doesn't hold a TSTInfo".into());
+    }
+    let mut explicit = expect_der(&mut encapsulated, CONTEXT_0)?;
+    let mut tst_info_der = expect_der(&mut explicit, OCTET_STRING)?;
+
+    let mut tst_info = expect_der(&mut tst_info_der, SEQUENCE)?;
+    expect_der(&mut tst_info, INTEGER)?; // version
+    expect_der(&mut tst_info, OID)?; // policy
+    let mut message_imprint = expect_der(&mut tst_info, SEQUENCE)?;
+    let mut algorithm = expect_der(&mut message_imprint, SEQUENCE)?;
+    if expect_der(&mut algorithm, OID)? != SHA256_OID {
+        return Err("Timestamp token isn't over a SHA-256 hash".into());
+    }
+    if expect_der(&mut message_imprint, OCTET_STRING)? != digest {
+        return Err("Timestamp token is over a different root".into());
+    }
+    expect_der(&mut tst_info, INTEGER)?; // serial number
+    let gen_time = std::str::from_utf8(expect_der(&mut tst_info, GENERALIZED_TIME)?)?;
+    let time = NaiveDateTime::parse_from_str(gen_time.trim_end_matches('Z'), "%Y%m%d%H%M%S%.f")?;
+
+    // Then optional accuracy and ordering before the nonce
+    let mut nonce = None;
+    while !tst_info.is_empty() {
+        let (tag, content) = read_der(&mut tst_info)?;
+        if tag == INTEGER {
+            nonce = Some(content.to_vec());
+            break;
+        }
+    }
+    Ok(TimestampInfo {
+        time: time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        nonce,
+    })
+}
+
+/// Asks the timestamp authority at `tsa_url` for an RFC 3161 token over
+/// `root`, the tree's root hash, which stands in as the SHA-256 digest the
+/// token signs. Returns the token's DER.
+pub fn request_token(tsa_url: &str, root: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let nonce = new_nonce();
+    let response = ureq::post(tsa_url)
+        .set("Content-Type", "application/timestamp-query")
+        .send_bytes(&timestamp_request(root, &nonce))?;
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body)?;
+
+    // TimeStampResp: the status, then the token if granted
+    let mut rest = body.as_slice();
+    let mut timestamp_response = expect_der(&mut rest, SEQUENCE)?;
+    let mut status_info = expect_der(&mut timestamp_response, SEQUENCE)?;
+    let status = expect_der(&mut status_info, INTEGER)?;
+    // 0 is granted, 1 granted with modifications
+    if !matches!(status, [0] | [1]) {
+        return Err(format!("Timestamp authority refused the request, status {}", hex::encode(status)).into());
+    }
+    let token = timestamp_response;
+    if token.is_empty() {
+        return Err("Timestamp authority granted the request but sent no token".into());
+    }
+    let info = check_token(token, root)?;
+    if info.nonce.as_deref() != Some(nonce.as_slice()) {
+        return Err("Timestamp token doesn't answer this request".into());
+    }
+    Ok(token.to_vec())
+}
+
+/// A file only this user can read, under a random name in the temp
+/// directory, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn create(contents: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("{}.tst", hex::encode(rand::random::<[u8; 16]>())));
+        // Fails rather than writing through a file or link someone else put there
+        let mut file = OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path)?;
+        let temp_file = TempFile(path);
+        file.write_all(contents)?;
+        Ok(temp_file)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Checks the token's signature and the authority's certificate chain up to
+/// `ca_file`, a PEM of the authority's CA, with the openssl command-line tool.
+pub fn verify_signature(token: &[u8], root: &[u8], ca_file: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let token_file = TempFile::create(token)?;
+    let output = Command::new("openssl")
+        .args(["ts", "-verify", "-token_in", "-digest", &hex::encode(root), "-in"])
+        .arg(&token_file.0)
+        .arg("-CAfile")
+        .arg(ca_file)
+        .output()
+        .map_err(|e| format!("Couldn't run openssl to check the timestamp: {}", e))?;
+    Ok(output.status.success())
+}
@@ -0,0 +1,55 @@
+use std::fs::File;
+use std::io::{Read, BufReader, ErrorKind};
+use std::path::Path;
+
+pub fn is_webp(path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut header = [0u8; 12];
+    let read = File::open(path)?.read(&mut header)?;
+    Ok(read == header.len() && &header[..4] == b"RIFF" && &header[8..] == b"WEBP")
+}
+
+/// The data of the first chunk of type `chunk_type`, if there is one. Other
+/// chunks, including the image data, are skipped without being read.
+fn read_chunk(path: &Path, chunk_type: &[u8; 4]) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != b"RIFF" || &header[8..] != b"WEBP" {
+        return Err(format!("Not a WebP: {}", path.display()).into());
+    }
+
+    loop {
+        // FourCC, then little-endian length; the data follows, padded to
+        // an even length
+        let mut header = [0u8; 8];
+        match reader.read_exact(&mut header) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+
+        if &header[..4] == chunk_type {
+            let mut data = vec![0u8; len];
+            reader.read_exact(&mut data)?;
+            return Ok(Some(data));
+        }
+        reader.seek_relative((len + len % 2) as i64)?;
+    }
+}
+
+/// The raw EXIF (TIFF) data in the EXIF chunk, if there is one. Some
+/// writers keep the JPEG "Exif\0\0" marker in front of it; that is dropped.
+pub fn exif_chunk(path: &Path) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    Ok(read_chunk(path, b"EXIF")?.map(|data| match data.strip_prefix(b"Exif\0\0") {
+        Some(tiff) => tiff.to_vec(),
+        None => data,
+    }))
+}
+
+/// The XMP packet in the "XMP " chunk, if there is one.
+pub fn xmp_packet(path: &Path) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    match read_chunk(path, b"XMP ")? {
+        Some(data) => Ok(Some(String::from_utf8(data)?)),
+        None => Ok(None),
+    }
+}
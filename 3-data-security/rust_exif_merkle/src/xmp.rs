@@ -0,0 +1,132 @@
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+use crate::leaf::{Source, named_leaf};
+
+/// RDF elements that only give the packet its structure
+const RDF_CONTAINERS: [&str; 5] = ["RDF", "Description", "Seq", "Bag", "Alt"];
+
+struct Element {
+    /// What the element adds to the property path: `prefix:Name` for a
+    /// property, `[n]` for an array item, nothing for RDF structure
+    segment: Option<String>,
+    /// Array items seen so far, for rdf:Seq, Bag and Alt
+    items: usize,
+}
+
+fn qualified(prefix: &str, local: &str) -> String {
+    if prefix.is_empty() {
+        local.to_string()
+    } else {
+        format!("{}:{}", prefix, local)
+    }
+}
+
+fn join(path: &mut String, segment: &str) {
+    if !path.is_empty() && !segment.starts_with('[') {
+        path.push('/');
+    }
+    path.push_str(segment);
+}
+
+fn property_path(stack: &[Element]) -> String {
+    let mut path = String::new();
+    for segment in stack.iter().filter_map(|element| element.segment.as_deref()) {
+        join(&mut path, segment);
+    }
+    path
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity.strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// One leaf per value in an XMP packet, named by its property's prefixed
+/// name, e.g. `XMP:xmp:CreatorTool`, with
+/// `[n]` for array items and `/` into structures, e.g. `XMP:dc:creator[1]`
+/// or `XMP:xmpMM:History[2]/stEvt:action`.
+pub fn xmp_leaves(packet: &str) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut stack: Vec<Element> = Vec::new();
+    let mut leaves = Vec::new();
+    let mut push_leaf = |path: &str, value: &str| {
+        leaves.push(named_leaf(Source::Xmp, path, value.as_bytes()));
+    };
+
+    for token in Tokenizer::from(packet) {
+        match token? {
+            Token::ElementStart { prefix, local, .. } => {
+                let segment = match (prefix.as_str(), local.as_str()) {
+                    ("rdf", "li") => stack.last_mut().map(|parent| {
+                        parent.items += 1;
+                        format!("[{}]", parent.items)
+                    }),
+                    ("rdf", name) if RDF_CONTAINERS.contains(&name) => None,
+                    ("x", "xmpmeta") => None,
+                    (prefix, local) => Some(qualified(prefix, local)),
+                };
+                stack.push(Element { segment, items: 0 });
+            }
+            Token::Attribute { prefix, local, value, .. } => {
+                let mut path = property_path(&stack);
+                match (prefix.as_str(), local.as_str()) {
+                    ("xmlns", _) | ("", "xmlns") => continue,
+                    // A property whose value is a URI
+                    ("rdf", "resource") => {}
+                    ("rdf", _) => continue,
+                    (prefix, local) => join(&mut path, &qualified(prefix, local)),
+                }
+                push_leaf(&path, &unescape(value.as_str()));
+            }
+            Token::Text { text } => {
+                let value = text.as_str().trim();
+                let path = property_path(&stack);
+                if !value.is_empty() && !path.is_empty() {
+                    push_leaf(&path, &unescape(value));
+                }
+            }
+            Token::Cdata { text, .. } => {
+                let path = property_path(&stack);
+                if !path.is_empty() {
+                    push_leaf(&path, text.as_str());
+                }
+            }
+            Token::ElementEnd { end: ElementEnd::Close(..) | ElementEnd::Empty, .. } => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    Ok(leaves)
+}